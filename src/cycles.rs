@@ -0,0 +1,246 @@
+
+use std::{collections::HashMap, hash::Hash};
+
+use num::Integer;
+
+/// Describes a cycle in an iterated sequence x0, f(x0), f(f(x0)), ...:
+/// the sequence enters the cycle after `prefix_len` steps, and from there
+/// repeats every `period` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub prefix_len: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// Returns the smallest iteration count that leads to the same state
+    /// as n iterations.
+    pub fn reduce(&self, n: u64) -> u64 {
+        let prefix_len = self.prefix_len as u64;
+        if n < prefix_len {
+            return n;
+        }
+        prefix_len + (n - prefix_len) % self.period as u64
+    }
+}
+
+/// Finds the cycle by remembering each seen state with its index.
+/// Each state is calculated only once, but all states until the cycle
+/// are kept in memory, so the state must be hashable.
+// no day needs the bare cycle yet (Day 14 uses nth_state(), Day 08 find_cycle_with_states()):
+#[allow(dead_code)]
+pub fn find_cycle<S, F>(start: S, step: F) -> Cycle
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let (_, cycle) = walk_until_cycle(start, step, None);
+    cycle.unwrap()
+}
+
+/// Brent's algorithm: only needs to compare states (no hashing), and keeps
+/// just two states in memory. This is useful if states are cheap to compare and
+/// to calculate, but expensive to store.
+#[allow(dead_code)]
+pub fn find_cycle_brent<S, F>(start: S, mut step: F) -> Cycle
+where
+    S: PartialEq + Clone,
+    F: FnMut(&S) -> S,
+{
+    // find the period: the hare runs ahead, the tortoise teleports to the hare
+    // each time the search window (a power of 2) is used up:
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    // find the prefix length: start two pointers `period` steps apart,
+    // and move both until they meet at the start of the cycle:
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut prefix_len = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix_len += 1;
+    }
+
+    Cycle { prefix_len, period }
+}
+
+/// Like find_cycle(), but also returns the states: states[i] is the state after i steps,
+/// for all i < prefix_len + period.
+pub fn find_cycle_with_states<S, F>(start: S, step: F) -> (Vec<S>, Cycle)
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let (states, cycle) = walk_until_cycle(start, step, None);
+    (states, cycle.unwrap())
+}
+
+/// Returns the state after n iterations, without calculating all of them:
+/// as soon as a cycle is detected, the result is taken from the already seen states.
+pub fn nth_state<S, F>(start: S, step: F, n: u64) -> S
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let (mut states, cycle) = walk_until_cycle(start, step, Some(n));
+    match cycle {
+        Some(cycle) => states.swap_remove(cycle.reduce(n) as usize),
+        None => states.pop().unwrap(),
+    }
+}
+
+/// Iterates the step function until a state repeats (or `max_steps` steps are done).
+/// Returns all the seen states (in order, index = nr of steps), and the cycle, if one was found.
+fn walk_until_cycle<S, F>(start: S, mut step: F, max_steps: Option<u64>) -> (Vec<S>, Option<Cycle>)
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let mut seen: HashMap<S, usize> = HashMap::new();
+    let mut states: Vec<S> = Vec::new();
+    let mut act_state = start;
+    loop {
+        if let Some(first_seen) = seen.get(&act_state) {
+            let cycle = Cycle {
+                prefix_len: *first_seen,
+                period: states.len() - first_seen,
+            };
+            return (states, Some(cycle));
+        }
+        seen.insert(act_state.clone(), states.len());
+        let next_state = step(&act_state);
+        states.push(act_state);
+        if let Some(max_steps) = max_steps {
+            if states.len() as u64 > max_steps {
+                return (states, None);
+            }
+        }
+        act_state = next_state;
+    }
+}
+
+/// The combined modulus of two congruences (the lcm of their moduli) does not fit in a u64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModulusOverflow;
+
+/// Combines the congruences n ≡ a1 (mod m1) and n ≡ a2 (mod m2) to a single one,
+/// n ≡ a (mod lcm(m1, m2)), with the generalised Chinese Remainder Theorem: the moduli
+/// do not need to be coprime. Returns None if no n satisfies both, or an error if
+/// lcm(m1, m2) is too large for a u64.
+/// This finds the steps at which several cycles are in given phases at the same time.
+pub fn combine_congruences(
+    (a1, m1): (u64, u64),
+    (a2, m2): (u64, u64),
+) -> Result<Option<(u64, u64)>, ModulusOverflow> {
+    let (a1, m1, a2, m2) = (a1 as i128, m1 as i128, a2 as i128, m2 as i128);
+    // n = a1 + k * m1, so k * m1 ≡ a2 - a1 (mod m2), which needs gcd(m1, m2) | a2 - a1:
+    let gcd = m1.extended_gcd(&m2);
+    let diff = a2 - a1;
+    if diff % gcd.gcd != 0 {
+        return Ok(None);
+    }
+    let m2_reduced = m2 / gcd.gcd;
+    let lcm = u64::try_from(m1 as u128 * m2_reduced as u128).map_err(|_| ModulusOverflow)?;
+    // gcd.x is the inverse of m1 / gcd, modulo m2 / gcd. Both factors are reduced
+    // below m2 / gcd first, so their product fits in a u128:
+    let factor = (diff / gcd.gcd).rem_euclid(m2_reduced) as u128;
+    let inverse = gcd.x.rem_euclid(m2_reduced) as u128;
+    let k = (factor * inverse % m2_reduced as u128) as i128;
+    let a = (a1 + k * m1).rem_euclid(lcm as i128);
+    Ok(Some((a as u64, lcm)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // x -> x^2 + 1 mod 255, starting at 3: 3, 10, 101, 2, 5, 26, 167, 95, 101, ...
+    fn step(x: &u64) -> u64 {
+        (x * x + 1) % 255
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let cycle = find_cycle(3, step);
+        assert_eq!(
+            cycle,
+            Cycle {
+                prefix_len: 2,
+                period: 6
+            }
+        );
+        assert_eq!(find_cycle_brent(3, step), cycle);
+    }
+
+    #[test]
+    fn test_nth_state() {
+        let mut expected = 3;
+        for n in 0..50 {
+            assert_eq!(nth_state(3, step, n), expected);
+            expected = step(&expected);
+        }
+    }
+
+    #[test]
+    fn test_cycle_reduce() {
+        let cycle = Cycle {
+            prefix_len: 2,
+            period: 6,
+        };
+        assert_eq!(cycle.reduce(1), 1);
+        assert_eq!(cycle.reduce(8), 2);
+        assert_eq!(cycle.reduce(1000000000), 2 + (1000000000 - 2) % 6);
+    }
+
+    #[test]
+    fn test_combine_congruences() {
+        // coprime: n ≡ 2 (mod 3), n ≡ 3 (mod 4)
+        assert_eq!(combine_congruences((2, 3), (3, 4)), Ok(Some((11, 12))));
+        // not coprime: n ≡ 3 (mod 6), n ≡ 1 (mod 4)
+        assert_eq!(combine_congruences((3, 6), (1, 4)), Ok(Some((9, 12))));
+        assert_eq!(combine_congruences((2, 6), (1, 4)), Ok(None));
+        assert_eq!(combine_congruences((0, 1), (5, 7)), Ok(Some((5, 7))));
+        for a1 in 0..6 {
+            for a2 in 0..10 {
+                let expected = (0..30).find(|n| n % 6 == a1 && n % 10 == a2);
+                let combined = combine_congruences((a1, 6), (a2, 10));
+                assert_eq!(combined, Ok(expected.map(|n| (n, 30))));
+            }
+        }
+    }
+
+    #[test]
+    fn test_combine_large_congruences() {
+        // coprime moduli just below 2^32: the lcm (almost 2^64) still fits
+        let (m1, m2) = ((1 << 32) - 5, (1 << 32) - 17);
+        let (a1, a2) = (m1 - 1, 12345);
+        let (a, lcm) = combine_congruences((a1, m1), (a2, m2)).unwrap().unwrap();
+        assert_eq!(lcm, m1 * m2);
+        assert_eq!((a % m1, a % m2), (a1, a2));
+        // too large:
+        let huge = combine_congruences((1, u64::MAX), (0, 2));
+        assert_eq!(huge, Err(ModulusOverflow));
+        let huge = combine_congruences((1 << 63, (1 << 63) + 1), (5, 1 << 63));
+        assert_eq!(huge, Err(ModulusOverflow));
+        assert_eq!(
+            combine_congruences((7, u64::MAX), (7, u64::MAX)),
+            Ok(Some((7, u64::MAX)))
+        );
+    }
+}
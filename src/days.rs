@@ -54,10 +54,88 @@ pub use day23::Day23;
 pub use day24::Day24;
 pub use day25::Day25;
 
+use crate::parser::{Input, ParseErrors};
+
 pub trait Day {
     fn day_nr(&self) -> String;
     fn title(&self) -> String;
     fn prepare(&mut self);
+    /// Parses the given puzzle input. Invalid input is reported as error,
+    /// it must never panic (see the fuzz command).
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors>;
     fn solve1(&mut self) -> String;
     fn solve2(&mut self) -> String;
 }
+
+/// Creates the problem instance for the given day
+pub fn create(day: u32) -> Option<Box<dyn Day>> {
+    let day: Box<dyn Day> = match day {
+        0 => Box::new(Day00::new()),
+        1 => Box::new(Day01::new()),
+        2 => Box::new(Day02::new()),
+        3 => Box::new(Day03::new()),
+        4 => Box::new(Day04::new()),
+        5 => Box::new(Day05::new()),
+        6 => Box::new(Day06::new()),
+        7 => Box::new(Day07::new()),
+        8 => Box::new(Day08::new()),
+        9 => Box::new(Day09::new()),
+        10 => Box::new(Day10::new()),
+        11 => Box::new(Day11::new()),
+        12 => Box::new(Day12::new()),
+        13 => Box::new(Day13::new()),
+        14 => Box::new(Day14::new()),
+        15 => Box::new(Day15::new()),
+        16 => Box::new(Day16::new()),
+        17 => Box::new(Day17::new()),
+        18 => Box::new(Day18::new()),
+        19 => Box::new(Day19::new()),
+        20 => Box::new(Day20::new()),
+        21 => Box::new(Day21::new()),
+        22 => Box::new(Day22::new()),
+        23 => Box::new(Day23::new()),
+        24 => Box::new(Day24::new()),
+        25 => Box::new(Day25::new()),
+        _ => return None,
+    };
+    Some(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The examples of the puzzle texts: (day, file, part 1, part 2).
+    /// A part is None if the example does not cover it.
+    const EXAMPLES: &[(u32, &str, Option<&str>, Option<&str>)] = &[
+        (2, "data/day02-test.txt", Some("8"), Some("2286")),
+        (3, "data/day03-test.txt", Some("4361"), Some("467835")),
+        (4, "data/day04-test.txt", Some("13"), Some("30")),
+        (6, "data/day06-test.txt", Some("288"), Some("71503")),
+        (7, "data/day07-test.txt", Some("6440"), Some("5905")),
+        (8, "data/day08-test.txt", Some("2"), None),
+        (8, "data/day08-test2.txt", Some("6"), None),
+        (9, "data/day09-test.txt", Some("114"), Some("2")),
+        (10, "data/day10-test1.txt", Some("4"), Some("1")),
+        (10, "data/day10-test2-1.txt", Some("23"), Some("4")),
+        (10, "data/day10-test2-3.txt", Some("70"), Some("8")),
+        (10, "data/day10-test2-4.txt", Some("80"), Some("10")),
+        (10, "data/day10-test2.txt", Some("8"), Some("1")),
+        (11, "data/day11-test.txt", Some("374"), Some("82000210")),
+        (12, "data/day12-test.txt", Some("21"), Some("525152")),
+    ];
+
+    #[test]
+    fn test_examples() {
+        for (nr, file, solution1, solution2) in EXAMPLES.iter().copied() {
+            let mut day = create(nr).unwrap();
+            day.parse(Input::read(file)).unwrap();
+            if let Some(solution1) = solution1 {
+                assert_eq!(day.solve1(), solution1, "part 1 of {0}", file);
+            }
+            if let Some(solution2) = solution2 {
+                assert_eq!(day.solve2(), solution2, "part 2 of {0}", file);
+            }
+        }
+    }
+}
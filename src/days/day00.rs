@@ -1,14 +1,28 @@
 use super::Day;
-use alex_lib::read_lines;
-use regex::Regex;
+use crate::parser::{self, Input, ParseErrors};
+use rand::{rngs::StdRng, Rng};
 
 pub struct Day00 {
-    lines: Vec<String>,
+    input: Input,
+    // calories per elf, one block of numbers for each elf
+    elves: Vec<Vec<i64>>,
 }
 
 impl Day00 {
     pub fn new() -> Day00 {
-        Day00 { lines: Vec::new() }
+        Day00 {
+            input: Input::new(),
+            elves: Vec::new(),
+        }
+    }
+
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for section in self.input.sections() {
+            let calories = section.numbers::<i64>(&mut errors);
+            self.elves.push(calories.into_iter().flatten().collect());
+        }
+        errors.into_result()
     }
 }
 
@@ -21,43 +35,32 @@ impl Day for Day00 {
     }
 
     fn prepare(&mut self) {
-        self.lines = read_lines("data/00.txt");
-        // self.lines = read_lines("data/00-test.txt");
-        self.lines.push(String::from(""));
+        let input = Input::read("data/00.txt");
+        // let input = Input::read("data/00-test.txt");
+        parser::exit_on_error(self.parse(input));
     }
 
-    fn solve1(&mut self) -> String {
-        let mut maxsum: i64 = 0;
-        let mut actsum: i64 = 0;
-
-        let re = Regex::new(r"^\d+$").unwrap();
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
+    }
 
-        for line in &self.lines {
-            if !re.is_match(line) {
-                if actsum > maxsum {
-                    maxsum = actsum;
-                }
-                actsum = 0;
-            } else {
-                actsum += str::parse::<i64>(line).unwrap();
-            }
-        }
+    fn solve1(&mut self) -> String {
+        let maxsum: i64 = self
+            .elves
+            .iter()
+            .map(|calories| calories.iter().sum())
+            .max()
+            .unwrap_or(0);
 
         String::from(format!("{0}", maxsum))
     }
     fn solve2(&mut self) -> String {
-        let mut sum_per_elve: Vec<i64> = Vec::new();
-        let re = Regex::new(r"^\d+$").unwrap();
-        let mut actsum: i64 = 0;
-
-        for line in &self.lines {
-            if !re.is_match(line) {
-                sum_per_elve.push(actsum);
-                actsum = 0;
-            } else {
-                actsum += str::parse::<i64>(line).unwrap();
-            }
-        }
+        let mut sum_per_elve: Vec<i64> = self
+            .elves
+            .iter()
+            .map(|calories| calories.iter().sum())
+            .collect();
         sum_per_elve.sort();
         sum_per_elve.reverse();
         let total: i64 = sum_per_elve.iter().take(3).sum();
@@ -65,3 +68,17 @@ impl Day for Day00 {
         String::from(format!("{0}", total))
     }
 }
+
+/// Random input: `size` elves, each carrying a few food items
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for elf in 0..size {
+        if elf > 0 {
+            lines.push(String::new());
+        }
+        for _ in 0..rng.gen_range(1..=6) {
+            lines.push(rng.gen_range(1000..=60000).to_string());
+        }
+    }
+    lines
+}
@@ -1,26 +1,92 @@
-use alex_lib::read_lines;
-use regex::Regex;
+use std::cmp::Reverse;
+
+use rand::{rngs::StdRng, Rng};
 
 use super::Day;
+use crate::parser::{self, Input, ParseErrors};
+use crate::patterns::PatternMatcher;
+
+/// The digits, for part 1
+pub const DIGITS: [(&str, u32); 10] = [
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
+
+/// The spelled-out digits, that also count for part 2
+pub const ENGLISH_DIGIT_WORDS: [(&str, u32); 9] = [
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
 
 #[derive(Debug)]
 pub struct Day01 {
-    input: Vec<String>,
-    number_pattern: Regex,
+    input: Input,
+    lines: Vec<String>,
+    digits: PatternMatcher<u32>,
+    digits_and_words: PatternMatcher<u32>,
 }
 
 impl Day01 {
     pub fn new() -> Day01 {
+        Day01::with_words(&ENGLISH_DIGIT_WORDS)
+    }
+
+    /// Uses another word-to-digit table for part 2, e.g. for another language
+    pub fn with_words(words: &[(&str, u32)]) -> Day01 {
+        let digits_and_words: Vec<(&str, u32)> =
+            DIGITS.iter().chain(words.iter()).copied().collect();
         Day01 {
-            input: Vec::new(),
-            number_pattern: regex::Regex::new(
-                r"(1|2|3|4|5|6|7|8|9|one|two|three|four|five|six|seven|eight|nine)",
-            )
-            .unwrap(),
+            input: Input::new(),
+            lines: Vec::new(),
+            digits: PatternMatcher::new(&DIGITS),
+            digits_and_words: PatternMatcher::new(&digits_and_words),
+        }
+    }
+
+    /// Any text is a valid calibration line, it may just contain no digits at all.
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        for line in self.input.non_empty_lines() {
+            self.lines.push(line.remaining().trim().to_string());
         }
+        Ok(())
     }
 }
 
+/// The calibration value of a line: its first and last digit, as a two-digit number.
+/// The digits are searched in a single pass, overlapping ones included: in 'eightwo',
+/// the first digit is 8, the last one is 2. None if the line contains no digit at all.
+fn calibration_value(digits: &PatternMatcher<u32>, line: &str) -> Option<u64> {
+    let mut matches = digits.find_all(line);
+    let found = matches.next()?;
+    let (mut first, mut last) = (found, found);
+    for found in matches {
+        // the matches are ordered by their end, so a longer one may start earlier.
+        // On the same start, the longer word wins:
+        if (found.start, Reverse(found.end)) < (first.start, Reverse(first.end)) {
+            first = found;
+        }
+        if (found.start, found.end) > (last.start, last.end) {
+            last = found;
+        }
+    }
+    Some(10 * first.value as u64 + last.value as u64)
+}
+
 impl Day for Day01 {
     fn day_nr(&self) -> String {
         String::from("01")
@@ -30,89 +96,81 @@ impl Day for Day01 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day01.txt");
-        // let input = read_lines("data/day01-test2.txt");
+        let input = Input::read("data/day01.txt");
+        // let input = Input::read("data/day01-test2.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
-        let mut sum: u32 = 0;
-        for line in self.input.iter() {
-            let first = line.chars().find(|c| c.is_numeric());
-            let last = line.chars().rev().find(|c| c.is_numeric());
-            if first.is_some() && last.is_some() {
-                let nr: u32 = 10 * (first.unwrap().to_digit(10).unwrap())
-                    + (last.unwrap().to_digit(10).unwrap());
-                sum += nr;
-            }
-        }
+        let sum: u64 = self
+            .lines
+            .iter()
+            .filter_map(|line| calibration_value(&self.digits, line))
+            .sum();
         String::from(format!("{0}", sum))
     }
+
     fn solve2(&mut self) -> String {
-        let mut sum: u64 = 0;
-        for line in self.input.iter() {
-            let all = find_nrs(&self.number_pattern, line);
-            if all.len() > 0 {
-                let first_str = all[0].as_str();
-                let last_str = all[all.len() - 1].as_str();
-                let first = str_to_nr(first_str);
-                let last = str_to_nr(last_str);
-                // if all.len() == 1 {
-                //     println!("single entry: {0}: {1}", all[0], line);
-                //     println!("{0} {1}", first, last);
-                // }
-                sum += (10 * first) + last;
-            }
-        }
+        let sum: u64 = self
+            .lines
+            .iter()
+            .filter_map(|line| calibration_value(&self.digits_and_words, line))
+            .sum();
         String::from(format!("{0}", sum))
     }
 }
 
-fn find_nrs(re: &Regex, line: &str) -> Vec<String> {
-    // attention: we cannot just simply find all regex sub-groups, as there are some
-    // mean entries like "eighthree" or "sevenine"..... So I loop over all smaller substrings, removing
-    // one character at at time from the beginning, and test the remaining.
-    let mut res = Vec::new();
-
-    for m in 0..(line.len()) {
-        let sub = &line[m..];
-        let m = re.find(sub);
-        if m.is_some() {
-            let s = m.unwrap().as_str();
-            res.push(String::from(s));
+/// Random input: `size` lines of letters, digits and spelled digits.
+/// Each line contains at least one real digit, as part 1 needs it.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let words: Vec<&str> = ENGLISH_DIGIT_WORDS.iter().map(|(word, _)| *word).collect();
+    let mut lines: Vec<String> = Vec::new();
+    for _ in 0..size {
+        let mut line = String::new();
+        for _ in 0..rng.gen_range(1..=8) {
+            match rng.gen_range(0..3) {
+                0 => line.push(char::from(b'0' + rng.gen_range(1..=9))),
+                1 => line.push_str(words[rng.gen_range(0..words.len())]),
+                _ => line.push(char::from(rng.gen_range(b'a'..=b'z'))),
+            }
         }
+        let digit = char::from(b'0' + rng.gen_range(1..=9));
+        line.insert(rng.gen_range(0..=line.len()), digit);
+        lines.push(line);
     }
-
-    // contains now a list of all numbers found, including overlapping
-    res
+    lines
 }
 
-fn str_to_nr(line: &str) -> u64 {
-    match line {
-        "one" => 1,
-        "1" => 1,
-        "two" => 2,
-        "eightwo" => 2,
-        "2" => 2,
-        "three" => 3,
-        "eighthree" => 3,
-        "3" => 3,
-        "four" => 4,
-        "4" => 4,
-        "five" => 5,
-        "5" => 5,
-        "six" => 6,
-        "6" => 6,
-        "seven" => 7,
-        "7" => 7,
-        "eight" => 8,
-        "oneight" => 8,
-        "threeight" => 8,
-        "fiveight" => 8,
-        "nineight" => 8,
-        "8" => 8,
-        "nine" => 9,
-        "9" => 9,
-        _ => panic!("Unknown number"),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(day: &mut Day01, lines: &[&str]) -> (String, String) {
+        let lines = lines.iter().map(|l| l.to_string()).collect();
+        day.parse(Input::from_lines("test.txt", lines)).unwrap();
+        (day.solve1(), day.solve2())
+    }
+
+    #[test]
+    fn test_overlapping_words() {
+        let lines = ["two1nine", "eightwothree", "xtwone3four", "zoneight234", "7pqrstsixteen"];
+        let (_, solution2) = solve(&mut Day01::new(), &lines);
+        assert_eq!(solution2, (29 + 83 + 24 + 14 + 76).to_string());
+        let (_, solution2) = solve(&mut Day01::new(), &["oneight", "twone", "sevenine"]);
+        assert_eq!(solution2, (18 + 21 + 79).to_string());
+    }
+
+    #[test]
+    fn test_other_words_and_utf8() {
+        let german = [("eins", 1), ("zwei", 2), ("drei", 3), ("vier", 4)];
+        let mut day = Day01::with_words(&german);
+        let (solution1, solution2) = solve(&mut day, &["ü7zweiß€", "einsdrei😀", "5ö"]);
+        assert_eq!(solution1, (77 + 55).to_string());
+        assert_eq!(solution2, (72 + 13 + 55).to_string());
     }
 }
@@ -1,70 +1,126 @@
-use alex_lib::read_lines;
-use regex::Regex;
+use std::collections::BTreeMap;
 
 use super::Day;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 
 #[derive(Debug)]
 pub struct Day02 {
-    input: Vec<String>,
+    input: Input,
     games: Vec<Game>,
+    bag: Bag,
 }
 
+/// A set of cubes, by colour. Colours not in the bag count as 0 cubes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Bag {
+    cubes: BTreeMap<String, u32>,
+}
+
+impl Bag {
+    pub fn new(cubes: &[(&str, u32)]) -> Bag {
+        let mut bag = Bag::default();
+        for (color, nr) in cubes.iter() {
+            bag.add(color, *nr);
+        }
+        bag
+    }
+
+    pub fn add(&mut self, color: &str, nr: u32) {
+        *self.cubes.entry(color.to_string()).or_insert(0) += nr;
+    }
+
+    pub fn count(&self, color: &str) -> u32 {
+        self.cubes.get(color).copied().unwrap_or(0)
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = &str> {
+        self.cubes.keys().map(|c| c.as_str())
+    }
+
+    /// true if the other bag's cubes can be taken from this bag
+    pub fn contains(&self, other: &Bag) -> bool {
+        other
+            .cubes
+            .iter()
+            .all(|(color, nr)| *nr <= self.count(color))
+    }
+}
+
+/// A game: the draws are kept separately, each one is a bag of the cubes shown.
 #[derive(Debug)]
-struct Game {
+pub struct Game {
     id: u32,
-    groups: Vec<(u32, String)>,
+    draws: Vec<Bag>,
+}
+
+impl Game {
+    /// true if every draw could have been taken from the given bag
+    pub fn is_possible_with(&self, bag: &Bag) -> bool {
+        self.draws.iter().all(|draw| bag.contains(draw))
+    }
+
+    /// The smallest bag that makes the game possible: the max. count of each colour
+    pub fn minimum_bag(&self) -> Bag {
+        let mut min_bag = Bag::default();
+        for draw in self.draws.iter() {
+            for (color, nr) in draw.cubes.iter() {
+                let count = min_bag.cubes.entry(color.clone()).or_insert(0);
+                *count = (*count).max(*nr);
+            }
+        }
+        min_bag
+    }
+
+    /// The product of the minimum bag's counts of the given colours: a colour
+    /// never drawn makes the power 0.
+    pub fn power<'a>(&self, colors: impl Iterator<Item = &'a str>) -> u64 {
+        let min_bag = self.minimum_bag();
+        colors.map(|color| min_bag.count(color) as u64).product()
+    }
 }
 
 impl Day02 {
     pub fn new() -> Day02 {
         Day02 {
-            input: Vec::new(),
+            input: Input::new(),
             games: Vec::new(),
+            bag: Bag::new(&[("red", 12), ("green", 13), ("blue", 14)]),
         }
     }
 
-    fn parse_input(&mut self) {
-        let game_id_match = Regex::new(r"Game (\d+):").unwrap();
-        let group_match = Regex::new(r"(\d+) (\w+)").unwrap();
-        for line in self.input.iter() {
-            let mut game = Game {
-                id: 0,
-                groups: Vec::new(),
-            };
-
-            // extract game id:
-            let game_id_opt = game_id_match.captures(line);
-            if !game_id_opt.is_some() {
-                continue;
-            }
-            let game_id_str = game_id_opt.unwrap().get(1).unwrap().as_str();
-            let game_id = game_id_str.parse::<u32>().unwrap();
-            game.id = game_id;
-
-            // rest of string:
-            let rest = &line[(line.find(':').unwrap() + 1)..];
-
-            // split groups:
-            let groups: Vec<String> = rest.split(';').map(|m| m.to_string()).collect();
-
-            // parse each group:
-            for group in groups {
-                group_match
-                    .captures_iter(group.as_str())
-                    .map(|m| {
-                        let (_, [a, b]) = m.extract();
-                        (a.parse::<u32>().unwrap(), b)
-                    })
-                    // emit a tuple (nr, color) into the game.groups vec:
-                    .for_each(|(nr, color)| {
-                        game.groups.push((nr, color.to_string()));
-                    });
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for mut line in self.input.non_empty_lines() {
+            if let Some(game) = errors.check(parse_game(&mut line)) {
+                self.games.push(game);
             }
-            self.games.push(game);
         }
+        errors.into_result()
     }
 }
 
+/// Parses lines like 'Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green'
+fn parse_game(line: &mut LineParser) -> ParseResult<Game> {
+    line.literal("Game")?;
+    let id = line.number::<u32>()?;
+    line.literal(":")?;
+    // draws are separated by ';', each draw contains ','-separated (nr, color) pairs:
+    let draws = line.separated(";", |draw| {
+        let mut bag = Bag::default();
+        draw.separated(",", |entry| {
+            let nr = entry.number::<u32>()?;
+            let color = entry.word()?;
+            bag.add(color, nr);
+            Ok(())
+        })?;
+        Ok(bag)
+    })?;
+    line.end()?;
+
+    Ok(Game { id, draws })
+}
+
 impl Day for Day02 {
     fn day_nr(&self) -> String {
         String::from("02")
@@ -74,59 +130,79 @@ impl Day for Day02 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day02.txt");
-        // let input = read_lines("data/day02-test.txt");
+        let input = Input::read("data/day02.txt");
+        // let input = Input::read("data/day02-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
-        let mut sum = 0;
-        for game in self.games.iter() {
-            // Check if game is valid
-            let mut valid = true;
-            for part in game.groups.iter() {
-                if part.1 == "red" && part.0 > 12 {
-                    valid = false;
-                    break;
-                }
-                if part.1 == "green" && part.0 > 13 {
-                    valid = false;
-                    break;
-                }
-                if part.1 == "blue" && part.0 > 14 {
-                    valid = false;
-                    break;
-                }
-            }
-            if valid {
-                sum += game.id;
-            }
-        }
+        let sum: u32 = self
+            .games
+            .iter()
+            .filter(|game| game.is_possible_with(&self.bag))
+            .map(|game| game.id)
+            .sum();
         String::from(format!("{0}", sum))
     }
     fn solve2(&mut self) -> String {
-        let mut sum = 0;
-        for game in self.games.iter() {
-            // parse each group:
-            let mut max_red = 0;
-            let mut max_green = 0;
-            let mut max_blue = 0;
-            for group in game.groups.iter() {
-                // Check if game is valid
-                if group.1 == "red" && group.0 > max_red {
-                    max_red = group.0;
-                }
-                if group.1 == "green" && group.0 > max_green {
-                    max_green = group.0;
-                }
-                if group.1 == "blue" && group.0 > max_blue {
-                    max_blue = group.0;
+        let sum: u64 = self
+            .games
+            .iter()
+            .map(|game| game.power(self.bag.colors()))
+            .sum();
+        String::from(format!("{0}", sum))
+    }
+}
+
+/// Random input: `size` games with a few draws each
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let colors = ["red", "green", "blue"];
+    let mut lines: Vec<String> = Vec::new();
+    for game in 1..=size {
+        let mut draws: Vec<String> = Vec::new();
+        for _ in 0..rng.gen_range(1..=6) {
+            let mut cubes: Vec<String> = Vec::new();
+            for color in colors.iter() {
+                if cubes.is_empty() || rng.gen_bool(0.6) {
+                    cubes.push(format!("{0} {1}", rng.gen_range(1..=20), color));
                 }
             }
-            let power = max_red * max_green * max_blue;
-            sum += power;
+            cubes.shuffle(rng);
+            draws.push(cubes.join(", "));
         }
-        String::from(format!("{0}", sum))
+        lines.push(format!("Game {0}: {1}", game, draws.join("; ")));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Game {
+        parse_game(&mut LineParser::new("test.txt", 1, line)).unwrap()
+    }
+
+    #[test]
+    fn test_draws_are_kept_separately() {
+        let game = parse("Game 7: 3 blue, 4 red; 1 red, 2 blue, 2 red");
+        assert_eq!(game.draws.len(), 2);
+        assert_eq!(game.draws[1], Bag::new(&[("red", 3), ("blue", 2)]));
+        assert_eq!(game.minimum_bag(), Bag::new(&[("red", 4), ("blue", 3)]));
+    }
+
+    #[test]
+    fn test_other_colors() {
+        let game = parse("Game 1: 2 yellow, 1 purple; 5 yellow");
+        assert!(game.is_possible_with(&Bag::new(&[("yellow", 5), ("purple", 1)])));
+        assert!(!game.is_possible_with(&Bag::new(&[("yellow", 4), ("purple", 1)])));
+        assert!(!game.is_possible_with(&Bag::new(&[("yellow", 5)])));
+        assert_eq!(game.power(["yellow", "purple"].into_iter()), 5);
+        assert_eq!(game.power(["yellow", "red"].into_iter()), 0);
     }
 }
@@ -1,28 +1,45 @@
-use alex_lib::{read_lines, types::{Coord2dMap, Coord2d}};
+use alex_lib::types::{Coord2dMap, Coord2d};
+use rand::{rngs::StdRng, Rng};
 
 use super::Day;
+use crate::parser::{self, Input, LineParser, ParseErrors};
+
+/// A number in the schematic: its value, and the cells it occupies,
+/// from `coord` to the right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartNumber {
+    pub value: i64,
+    pub coord: Coord2d,
+    pub len: i64,
+}
 
 #[derive(Debug)]
 pub struct Day03 {
-    input: Vec<String>,
-    numbers: Coord2dMap<String>,
+    input: Input,
+    numbers: Vec<PartNumber>,
+    // the index in self.numbers of the number occupying each (number) cell:
+    number_cells: Coord2dMap<usize>,
     symbols: Coord2dMap<char>,
 }
 
 impl Day03 {
     pub fn new() -> Day03 {
         Day03 {
-            input: Vec::new(),
-            numbers: Coord2dMap::new(),
+            input: Input::new(),
+            numbers: Vec::new(),
+            number_cells: Coord2dMap::new(),
             symbols: Coord2dMap::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        for y in 0..self.input.len() {
-            let val = String::from(&self.input[y]);
-            self.parse_line(&val, y as i64);
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        // parse_line() fills the maps in self, so it cannot borrow self.input:
+        let input = self.input.clone();
+        for (y, line) in input.lines().enumerate() {
+            self.parse_line(&line, y as i64, &mut errors);
         }
+        errors.into_result()
     }
 
     // This is a little parser:
@@ -30,81 +47,132 @@ impl Day03 {
     /// If a number of a symbol is found, it is stored in its
     /// corresponding coordinate map: I store numbers and symbols in separate maps,
     /// for faster lookups later.
-    fn parse_line(&mut self, line: &String, y: i64) {
-        let mut nr_str = String::from("");
-        let mut str_start: i64 = 0;
-
-        if line.len() == 0 {
-            return;
-        }
-        for x in 0..line.len() as i64 {
-            let chr = line.chars().nth(x as usize).unwrap();
-            if chr >= '0' && chr <= '9' {
-                // if nr_str is empty, this is the start of a number:
-                if nr_str.len() == 0 {
-                    str_start = x;
-                }
-                nr_str.push(chr);
-            } else {
-                let coord = Coord2d { x, y };
-                if chr != '.' {
-                    self.symbols.insert(coord, chr);
-                }
-                // finished number, store it:
-                if nr_str.len() > 0 {
-                    let coord = Coord2d { x: str_start, y };
-                    self.numbers.insert(coord, String::from(nr_str.as_str()));
-                    nr_str.clear();
-                }
+    /// The line is scanned once, char by char: each char (even a multi-byte one) is
+    /// a single x position. Everything except digits, '.' and whitespace is a symbol.
+    fn parse_line(&mut self, line: &LineParser, y: i64, errors: &mut ParseErrors) {
+        let text = line.remaining();
+        // (x, byte position) of the number we are in:
+        let mut number_start: Option<(i64, usize)> = None;
+        for (x, (pos, chr)) in text.char_indices().enumerate() {
+            let x = x as i64;
+            if chr.is_ascii_digit() {
+                number_start.get_or_insert((x, pos));
+                continue;
+            }
+            // finished number, store it:
+            if let Some((start_x, start)) = number_start.take() {
+                self.store_number(&text[start..pos], start_x, y, line, start, errors);
+            }
+            if chr != '.' && !chr.is_whitespace() {
+                self.symbols.insert(Coord2d { x, y }, chr);
             }
         }
         // finish a number at the end of the line:
-        if nr_str.len() > 0 {
-            let coord = Coord2d { x: str_start, y };
-            self.numbers.insert(coord, String::from(nr_str.as_str()));
-            nr_str.clear();
+        if let Some((start_x, start)) = number_start {
+            self.store_number(&text[start..], start_x, y, line, start, errors);
         }
     }
 
-    /// Checks if the given number is a part number: a number is a part number
-    /// if it is adjacent to a symbol.
-    fn is_part_number(&self, nr_str: &str, nr_coord: &Coord2d) -> bool {
-        // check all surrounding coordinates for a symbol:
-        for y in (nr_coord.y - 1)..=(nr_coord.y + 1) {
-            for x in (nr_coord.x - 1)..=(nr_coord.x + nr_str.len() as i64) {
-                let check_entry = self.symbols.get(&Coord2d { x, y });
-                if let Some(_) = check_entry {
-                    return true;
-                }
+    /// Stores a number that starts at (x, y): it must fit into an i64, as the solutions calculate with it.
+    /// Each of its cells points to it.
+    fn store_number(
+        &mut self,
+        nr_str: &str,
+        x: i64,
+        y: i64,
+        line: &LineParser,
+        pos: usize,
+        errors: &mut ParseErrors,
+    ) {
+        let value = match nr_str.parse::<i64>() {
+            Ok(value) => value,
+            Err(_) => {
+                errors.push(line.error_at(pos, "a number of type i64"));
+                return;
             }
+        };
+        let len = nr_str.len() as i64;
+        for cell_x in x..x + len {
+            self.number_cells
+                .insert(Coord2d { x: cell_x, y }, self.numbers.len());
         }
-        false
+        self.numbers.push(PartNumber {
+            value,
+            coord: Coord2d { x, y },
+            len,
+        });
     }
 
-    /// Returns a list of numbers that are adjacent to the given coordinate.
-    /// we need this to find adjacent numbers for gears. coord here would
-    /// be a gear coordinate.
-    fn find_surrounding_numbers(&self, coord: &Coord2d) -> Vec<String> {
-        let mut numbers: Vec<String> = Vec::new();
-        for (number_coord, nr_str) in self.numbers.iter() {
-            if self.is_adjacent_number(coord, nr_str, number_coord) {
-                numbers.push(String::from(nr_str));
+    /// The indexes of the numbers adjacent (also diagonally) to the given cell, each one once.
+    /// Only looks up the 8 neighbour cells.
+    fn adjacent_number_indexes(&self, coord: &Coord2d) -> Vec<usize> {
+        let mut indexes: Vec<usize> = Vec::new();
+        for y in (coord.y - 1)..=(coord.y + 1) {
+            for x in (coord.x - 1)..=(coord.x + 1) {
+                if let Some(index) = self.number_cells.get(&Coord2d { x, y }) {
+                    if !indexes.contains(index) {
+                        indexes.push(*index);
+                    }
+                }
             }
         }
-        numbers
+        indexes
     }
 
-    /// Checks if the given number/coordinate is adjacent to the given coordinate.
-    fn is_adjacent_number(&self, coord: &Coord2d, nr_str: &str, number_coord: &Coord2d) -> bool {
-        for y in (number_coord.y - 1)..=(number_coord.y + 1) {
-            for x in (number_coord.x - 1)..=(number_coord.x + nr_str.len() as i64) {
-                let check_coord = Coord2d { x, y };
-                if check_coord == *coord {
-                    return true;
-                }
+    /// The numbers adjacent (also diagonally) to the given cell
+    pub fn adjacent_numbers(&self, coord: &Coord2d) -> Vec<&PartNumber> {
+        self.adjacent_number_indexes(coord)
+            .into_iter()
+            .map(|index| &self.numbers[index])
+            .collect()
+    }
+
+    /// The symbols matching the given predicate, with their coordinates, ordered by row, then column
+    pub fn symbols_where(&self, is_symbol: impl Fn(char) -> bool) -> Vec<(Coord2d, char)> {
+        let mut symbols: Vec<(Coord2d, char)> = self
+            .symbols
+            .iter()
+            .filter(|(_, symbol)| is_symbol(**symbol))
+            .map(|(coord, symbol)| (*coord, *symbol))
+            .collect();
+        symbols.sort_by_key(|(coord, _)| (coord.y, coord.x));
+        symbols
+    }
+
+    /// All numbers next to at least one of the symbols matching the predicate,
+    /// in the order they appear in the schematic. Each number is returned once.
+    pub fn numbers_next_to(&self, is_symbol: impl Fn(char) -> bool) -> Vec<&PartNumber> {
+        let mut is_next_to = vec![false; self.numbers.len()];
+        for (coord, _) in self.symbols_where(is_symbol) {
+            for index in self.adjacent_number_indexes(&coord) {
+                is_next_to[index] = true;
             }
         }
-        false
+        self.numbers
+            .iter()
+            .zip(is_next_to)
+            .filter(|(_, next_to)| *next_to)
+            .map(|(number, _)| number)
+            .collect()
+    }
+
+    /// The part numbers: all numbers next to any symbol
+    pub fn part_numbers(&self) -> Vec<&PartNumber> {
+        self.numbers_next_to(|_| true)
+    }
+
+    /// The given symbols with exactly `n` adjacent numbers, with these numbers:
+    /// for gears, these are the '*' symbols with exactly 2 numbers.
+    pub fn symbols_with_n_numbers(
+        &self,
+        symbol: char,
+        n: usize,
+    ) -> Vec<(Coord2d, Vec<&PartNumber>)> {
+        self.symbols_where(|s| s == symbol)
+            .into_iter()
+            .map(|(coord, _)| (coord, self.adjacent_numbers(&coord)))
+            .filter(|(_, numbers)| numbers.len() == n)
+            .collect()
     }
 }
 
@@ -117,37 +185,112 @@ impl Day for Day03 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day03.txt");
-        // let input = read_lines("data/day03-test.txt");
+        let input = Input::read("data/day03.txt");
+        // let input = Input::read("data/day03-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
-        let mut sum = 0;
-        for (number_coord, number_str) in self.numbers.iter() {
-            if self.is_part_number(number_str, number_coord) {
-                let nr = number_str.parse::<i64>().unwrap();
-                sum += nr;
-            }
-        }
+        let sum: i64 = self.part_numbers().iter().map(|nr| nr.value).sum();
         String::from(format!("{0}", sum))
     }
 
     fn solve2(&mut self) -> String {
-        let mut sum = 0;
-
-        for (coord, symbol) in self.symbols.iter() {
-            // check for surrounding numbers of gear symbol:
-            if *symbol == '*' {
-                let numbers = self.find_surrounding_numbers(&coord);
-                if numbers.len() == 2 {
-                    let nr1 = numbers[0].parse::<i64>().unwrap();
-                    let nr2 = numbers[1].parse::<i64>().unwrap();
-                    sum += nr1 * nr2;
+        let sum: i64 = self
+            .symbols_with_n_numbers('*', 2)
+            .iter()
+            .map(|(_, numbers)| numbers[0].value * numbers[1].value)
+            .sum();
+        String::from(format!("{0}", sum))
+    }
+}
+
+/// Random input: a square schematic of (at least) `size` x `size` cells,
+/// with numbers of 1-3 digits and symbols scattered around
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let size = size.max(5);
+    let symbols: Vec<char> = "*#+$/@=%&-".chars().collect();
+    let mut lines: Vec<String> = Vec::new();
+    for _ in 0..size {
+        let mut row = vec!['.'; size];
+        let mut x = 0;
+        while x < size {
+            let len = rng.gen_range(1..=3);
+            if x + len <= size && rng.gen_bool(0.15) {
+                for c in row.iter_mut().skip(x).take(len) {
+                    *c = char::from(b'0' + rng.gen_range(0..=9));
                 }
+                // no leading zeroes:
+                row[x] = char::from(b'0' + rng.gen_range(1..=9));
+                x += len + 1;
+            } else {
+                if rng.gen_bool(0.08) {
+                    row[x] = symbols[rng.gen_range(0..symbols.len())];
+                }
+                x += 1;
             }
         }
-        String::from(format!("{0}", sum))
+        lines.push(row.into_iter().collect());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: &[&str]) -> Day03 {
+        let mut day = Day03::new();
+        let lines = lines.iter().map(|l| l.to_string()).collect();
+        day.parse(Input::from_lines("test.txt", lines)).unwrap();
+        day
+    }
+
+    #[test]
+    fn test_multi_byte_chars_are_single_cells() {
+        // the 5 is at x = 2, not next to the '#' at x = 4 (it would be, counting bytes).
+        // The '€' after the 7 is a symbol next to it:
+        let mut day = parse(&["€.5..", "....#", "7€..."]);
+        assert_eq!(day.solve1(), "7");
+    }
+
+    fn values(numbers: &[&PartNumber]) -> Vec<i64> {
+        numbers.iter().map(|nr| nr.value).collect()
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut day = Day03::new();
+        day.parse(Input::read("data/day03-test.txt")).unwrap();
+        assert_eq!(values(&day.numbers_next_to(|s| s == '#')), vec![633]);
+        assert_eq!(
+            values(&day.numbers_next_to(|s| s == '$' || s == '+')),
+            vec![592, 664]
+        );
+        assert_eq!(
+            values(&day.adjacent_numbers(&Coord2d { x: 3, y: 1 })),
+            vec![467, 35]
+        );
+
+        let single = day.symbols_with_n_numbers('*', 1);
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].0, Coord2d { x: 3, y: 4 });
+        assert_eq!(values(&single[0].1), vec![617]);
+    }
+
+    #[test]
+    fn test_number_is_adjacent_once() {
+        // the '*' touches both digits of 12:
+        let day = parse(&["12.", "*.1"]);
+        assert_eq!(
+            values(&day.adjacent_numbers(&Coord2d { x: 0, y: 1 })),
+            vec![12]
+        );
+        assert_eq!(day.symbols_with_n_numbers('*', 2).len(), 0);
     }
 }
@@ -1,105 +1,77 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+
+use num::{BigUint, One, Zero};
 
 use super::Day;
-use alex_lib::read_lines;
-use regex::Regex;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, seq::SliceRandom};
 
 #[derive(Debug)]
 struct Card {
-    win: Vec<i64>,
+    win: HashSet<i64>,
     own: Vec<i64>,
 }
 
-type CardNumber = u32;
+impl Card {
+    /// The number of own numbers that are winning numbers
+    fn matches(&self) -> usize {
+        self.own.iter().filter(|nr| self.win.contains(nr)).count()
+    }
+}
 
 #[derive(Debug)]
 pub struct Day04 {
-    input: Vec<String>,
+    input: Input,
     cards: Vec<Card>,
-    // stores the number of winning cards for each card
-    winning_cards_memo: HashMap<CardNumber, u32>,
-    // stores the total count for each card, including gained sub-cards recursively
-    card_counter_memo: HashMap<CardNumber, u64>,
 }
 
 impl Day04 {
     pub fn new() -> Day04 {
         Day04 {
-            input: Vec::new(),
+            input: Input::new(),
             cards: Vec::new(),
-            winning_cards_memo: HashMap::new(),
-            card_counter_memo: HashMap::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let matcher = Regex::new(r".*:(.*)\|(.*)").unwrap();
-        for line in &self.input {
-            if line.trim().is_empty() {
-                continue;
-            }
-            let caps = matcher.captures(line).unwrap();
-            if caps.len() < 3 {
-                continue;
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for mut line in self.input.non_empty_lines() {
+            if let Some(card) = errors.check(parse_card(&mut line)) {
+                self.cards.push(card);
             }
-            let win = caps.get(1).unwrap().as_str();
-            let own = caps.get(2).unwrap().as_str();
-            let win_cards: Vec<i64> = win
-                .split(' ')
-                .filter(|s| !s.is_empty())
-                .map(|s| s.trim().parse::<i64>().unwrap())
-                .collect();
-            let own_cards: Vec<i64> = own
-                .split(' ')
-                .filter(|s| !s.is_empty())
-                .map(|s| s.trim().parse::<i64>().unwrap())
-                .collect();
-
-            self.cards.push(Card {
-                win: win_cards,
-                own: own_cards,
-            });
         }
+        errors.into_result()
     }
 
-    /// Clever memoization-based algorithm to count all winning cards
-    /// including the newly gained sub-cards. Each card's total count
-    /// is either calculated recursively, or, if already memoized,
-    /// returned from the cache.
-    /// This makes the calculation super-fast.
-    fn count_card(&mut self, card_nr: CardNumber) -> u64 {
-        let mut counter: u64 = 0;
-
-        if let Some(counter_memo) = self.card_counter_memo.get(&card_nr) {
-            return *counter_memo;
-        }
-
-        // 1st, count the one card:
-        counter += 1;
-
-        // process each sub-card based on the number of winning entries:
-        let winning_cards = match self.winning_cards_memo.get(&card_nr) {
-            Some(v) => *v,
-            None => 0,
-        };
-        if winning_cards > 0 {
-            for sub_card in (card_nr + 1)..=(card_nr + winning_cards) {
-                counter += self.count_card(sub_card);
-            }
+    /// Counts all cards, including the won copies, in a single pass from the last card
+    /// to the first: a card with m matches counts itself, plus the total count of the next m cards,
+    /// which are already known. These are summed up using a suffix sum, so each card takes
+    /// O(1) additions, no matter how many matches it has.
+    /// The counts grow exponentially with long winning streaks, so they are big integers.
+    fn count_cards(&self) -> BigUint {
+        let len = self.cards.len();
+        // suffix_sums[i]: total count of the cards i..len
+        let mut suffix_sums: Vec<BigUint> = vec![BigUint::zero(); len + 1];
+        for (nr, card) in self.cards.iter().enumerate().rev() {
+            let last = (nr + card.matches()).min(len - 1);
+            let count = BigUint::one() + &suffix_sums[nr + 1] - &suffix_sums[last + 1];
+            suffix_sums[nr] = count + &suffix_sums[nr + 1];
         }
-        self.card_counter_memo.insert(card_nr, counter);
-        return counter;
+        suffix_sums.swap_remove(0)
     }
+}
 
-    fn count_winning_cards(&self, card: &Card) -> u32 {
-        let mut count = 0;
-        for num in &card.own {
-            if card.win.contains(num) {
-                count += 1;
-            }
-        }
-        count
+/// Parses lines like 'Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53'
+fn parse_card(line: &mut LineParser) -> ParseResult<Card> {
+    line.literal("Card")?;
+    line.number::<u32>()?;
+    line.literal(":")?;
+    let mut win = HashSet::new();
+    while !line.try_literal("|") {
+        win.insert(line.number::<i64>()?);
     }
+    let own = line.numbers::<i64>()?;
+    Ok(Card { win, own })
 }
 
 impl Day for Day04 {
@@ -111,30 +83,87 @@ impl Day for Day04 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day04.txt");
-        // let input = read_lines("data/day04-test.txt");
+        let input = Input::read("data/day04.txt");
+        // let input = Input::read("data/day04-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
-        let mut sum = 0;
-        for (nr, card) in self.cards.iter().enumerate() {
-            let count = self.count_winning_cards(card);
-            // store results for solution 2:
-            self.winning_cards_memo.insert(nr as CardNumber, count);
-            let points = match count {
-                0 => 0,
-                _ => (2_i128).pow(count - 1),
-            };
-            sum += points;
-        }
+        // a card with m > 0 matches is worth 2^(m-1) points:
+        let sum: BigUint = self
+            .cards
+            .iter()
+            .map(|card| match card.matches() {
+                0 => BigUint::zero(),
+                count => BigUint::one() << (count - 1),
+            })
+            .sum();
         String::from(format!("{0}", sum))
     }
 
     fn solve2(&mut self) -> String {
-        let initial_cards: Vec<CardNumber> = (0..((self.cards.len()) as CardNumber)).collect();
-        let count: u64 = initial_cards.iter().map(|nr| self.count_card(*nr)).sum();
-        String::from(format!("{0}", count))
+        String::from(format!("{0}", self.count_cards()))
+    }
+}
+
+/// Random input: `size` cards with 5 winning and 8 own numbers
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let all: Vec<u32> = (1..100).collect();
+    let format = |numbers: &[u32]| -> String {
+        let numbers: Vec<String> = numbers.iter().map(|n| format!("{0:>2}", n)).collect();
+        numbers.join(" ")
+    };
+    let mut lines: Vec<String> = Vec::new();
+    for card in 1..=size {
+        // own numbers are drawn from a small pool, so that some of them win:
+        let pool: Vec<u32> = all.choose_multiple(rng, 20).copied().collect();
+        let winning: Vec<u32> = pool[0..5].to_vec();
+        let own: Vec<u32> = pool.choose_multiple(rng, 8).copied().collect();
+        lines.push(format!(
+            "Card {0:>3}: {1} | {2}",
+            card,
+            format(&winning),
+            format(&own)
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: Vec<String>) -> Day04 {
+        let mut day = Day04::new();
+        day.parse(Input::from_lines("test.txt", lines)).unwrap();
+        day
+    }
+
+    /// A card with the given number of matches: the numbers 1..=matches win
+    fn card(nr: usize, matches: usize) -> String {
+        let numbers: Vec<String> = (1..=matches).map(|n| n.to_string()).collect();
+        let numbers = numbers.join(" ");
+        format!("Card {0}: {1} 1000 | {1} 1001", nr, numbers)
+    }
+
+    #[test]
+    fn test_many_matches_do_not_overflow() {
+        let mut day = parse(vec![card(1, 200), card(2, 0)]);
+        assert_eq!(day.solve1(), (BigUint::one() << 199usize).to_string());
+        assert_eq!(day.solve2(), "3");
+    }
+
+    #[test]
+    fn test_long_winning_streak() {
+        // each card wins a copy of all following cards: 2^n - 1 cards in total
+        let n = 150;
+        let lines = (1..=n).map(|nr| card(nr, n - nr)).collect();
+        let mut day = parse(lines);
+        assert_eq!(day.solve2(), ((BigUint::one() << n) - 1u32).to_string());
     }
 }
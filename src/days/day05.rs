@@ -1,8 +1,8 @@
-use std::{sync::mpsc::channel, thread};
+use std::{fmt::Display, path::Path};
 
 use super::Day;
-use alex_lib::read_lines;
-use regex::Regex;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, Rng};
 
 #[derive(Debug, Clone, Copy)]
 struct MapEntry {
@@ -17,9 +17,161 @@ struct MaterialMap {
     mappings: Vec<MapEntry>,
 }
 
+/// A single piece of a piecewise-linear map:
+/// all values from start to end (both inclusive) are moved by offset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment {
+    start: i64,
+    end: i64,
+    offset: i64,
+}
+
+/// A material map (or a chain of them) as piecewise-linear function:
+/// a sorted list of non-overlapping segments. Values not covered
+/// by a segment have no mapped value.
+#[derive(Debug, Clone, PartialEq)]
+struct PiecewiseMap {
+    segments: Vec<Segment>,
+}
+
+impl PiecewiseMap {
+    /// Creates the function for a single material map: all non-negative values are covered,
+    /// values outside of the mapping entries map to themselves.
+    fn from_material_map(map: &MaterialMap) -> PiecewiseMap {
+        let mut entries = map.mappings.clone();
+        entries.sort_by_key(|e| e.input_start);
+
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut next_start: i64 = 0;
+        for entry in entries.iter() {
+            // fill the gap before the entry with an identity segment:
+            if entry.input_start > next_start {
+                segments.push(Segment {
+                    start: next_start,
+                    end: entry.input_start - 1,
+                    offset: 0,
+                });
+            }
+            let start = entry.input_start.max(next_start);
+            let end = entry.input_start + entry.range - 1;
+            if end >= start {
+                segments.push(Segment {
+                    start,
+                    end,
+                    offset: entry.output_start - entry.input_start,
+                });
+                next_start = end + 1;
+            }
+        }
+        segments.push(Segment {
+            start: next_start,
+            end: i64::MAX,
+            offset: 0,
+        });
+
+        PiecewiseMap::from_segments(segments)
+    }
+
+    /// Creates a map from sorted, non-overlapping segments.
+    /// Neighbouring segments with the same offset are merged.
+    fn from_segments(segments: Vec<Segment>) -> PiecewiseMap {
+        let mut merged: Vec<Segment> = Vec::new();
+        for segment in segments {
+            match merged.last_mut() {
+                Some(last) if last.end + 1 == segment.start && last.offset == segment.offset => {
+                    last.end = segment.end;
+                }
+                _ => merged.push(segment),
+            }
+        }
+        PiecewiseMap { segments: merged }
+    }
+
+    /// Returns the mapped value, or None if the value is not covered by the map.
+    fn get(&self, value: i64) -> Option<i64> {
+        let idx = self.segments.partition_point(|s| s.end < value);
+        match self.segments.get(idx) {
+            Some(segment) if segment.start <= value => Some(value + segment.offset),
+            _ => None,
+        }
+    }
+
+    /// Composes two maps: the returned map first applies self, then next.
+    /// Each segment is split where its output range crosses a segment boundary of next.
+    fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut segments: Vec<Segment> = Vec::new();
+        for segment in self.segments.iter() {
+            let out_start = segment.start + segment.offset;
+            let out_end = segment.end + segment.offset;
+            let first = next.segments.partition_point(|s| s.end < out_start);
+            for next_segment in next.segments[first..].iter() {
+                if next_segment.start > out_end {
+                    break;
+                }
+                let overlap_start = out_start.max(next_segment.start);
+                let overlap_end = out_end.min(next_segment.end);
+                segments.push(Segment {
+                    start: overlap_start - segment.offset,
+                    end: overlap_end - segment.offset,
+                    offset: segment.offset + next_segment.offset,
+                });
+            }
+        }
+        PiecewiseMap::from_segments(segments)
+    }
+
+    /// Returns the inverse map (e.g. location -> seed for a seed -> location map),
+    /// or None if the map is not invertible, because two segments map to the same values.
+    fn inverse(&self) -> Option<PiecewiseMap> {
+        let mut segments: Vec<Segment> = self
+            .segments
+            .iter()
+            .map(|s| Segment {
+                start: s.start + s.offset,
+                end: s.end + s.offset,
+                offset: -s.offset,
+            })
+            .collect();
+        segments.sort_by_key(|s| s.start);
+        for pair in segments.windows(2) {
+            if pair[0].end >= pair[1].start {
+                return None;
+            }
+        }
+        Some(PiecewiseMap::from_segments(segments))
+    }
+
+    /// Returns all values that map to the given value.
+    /// Works also for non-invertible maps, where a value can have more than one origin.
+    fn preimages(&self, value: i64) -> Vec<i64> {
+        self.segments
+            .iter()
+            .filter(|s| value >= s.start + s.offset && value <= s.end + s.offset)
+            .map(|s| value - s.offset)
+            .collect()
+    }
+}
+
+impl Display for PiecewiseMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for s in self.segments.iter() {
+            writeln!(
+                f,
+                "{0:>20} - {1:<20} -> {2:>20} - {3:<20} ({4:+})",
+                s.start,
+                s.end,
+                s.start + s.offset,
+                s.end + s.offset,
+                s.offset
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct Day05 {
-    input: Vec<String>,
+    input: Input,
     material_maps: Vec<MaterialMap>,
     seeds: Vec<i64>,
 }
@@ -27,74 +179,63 @@ pub struct Day05 {
 impl Day05 {
     pub fn new() -> Day05 {
         Day05 {
-            input: Vec::new(),
+            input: Input::new(),
             material_maps: Vec::new(),
             seeds: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let mut input_iter = self.input.iter();
-        // 1st line: Seeds:
-        let seed_line = input_iter.next().unwrap();
-        let seed_re = Regex::new(r"seeds: (.*)").unwrap();
-        let seeds = seed_re
-            .captures(seed_line)
-            .unwrap()
-            .get(1)
-            .unwrap()
-            .as_str();
-        let seeds = seeds
-            .split(' ')
-            .map(|s| s.parse::<i64>().unwrap())
-            .collect::<Vec<i64>>();
-
-        self.seeds = seeds;
-
-        // skip 2nd line:
-        input_iter.next();
-
-        // process all blocks of lines, an empty line marks the end of a block:
-        let mapping_match = Regex::new(r"(\d+)\s+(\d+)\s+(\d+)").unwrap();
-        let mut start_of_block = true;
-        let mut act_map = MaterialMap {
-            mappings: Vec::new(),
-            name: String::new(),
+    /// The input consists of blocks, separated by empty lines:
+    /// the seeds line, followed by a block for each map, with a 'seed-to-soil map:' header.
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        let sections = self.input.sections();
+        let (seeds_section, map_sections) = match sections.split_first() {
+            Some(sections) => sections,
+            None => return Err(ParseErrors::from(self.input.line(0).error("'seeds:'"))),
         };
-        for line in input_iter {
-            if start_of_block && !line.is_empty() {
-                act_map.name = line.clone();
-                start_of_block = false;
-                continue;
-            }
-            if !start_of_block && line.is_empty() {
-                // end of block: store it
-                self.material_maps.push(act_map);
-                act_map = MaterialMap {
-                    mappings: Vec::new(),
-                    name: String::new(),
-                };
-                start_of_block = true;
-                continue;
-            }
-            let captures = mapping_match.captures(line);
-            if let Some(captures) = captures {
-                // must be a line with number mappings
-                let entry = MapEntry {
-                    input_start: captures.get(2).unwrap().as_str().parse::<i64>().unwrap(),
-                    output_start: captures.get(1).unwrap().as_str().parse::<i64>().unwrap(),
-                    range: captures.get(3).unwrap().as_str().parse::<i64>().unwrap(),
-                };
-                act_map.mappings.push(entry);
+
+        // 1st block: Seeds:
+        let seeds = seeds_section.records(&mut errors, |line| {
+            line.literal("seeds:")?;
+            line.numbers::<i64>()
+        });
+        self.seeds = seeds.into_iter().flatten().collect();
+
+        // all other blocks: a header with the map name, followed by the mappings:
+        for section in map_sections {
+            let (mut header, body) = section.split_header();
+            let name = errors.check(parse_map_name(&mut header));
+            let mappings = body.records(&mut errors, parse_map_entry);
+            if let Some(name) = name {
+                self.material_maps.push(MaterialMap { name, mappings });
             }
         }
-        // capture the last entry, too:
-        if !start_of_block {
-            self.material_maps.push(act_map);
-        }
+        errors.into_result()
     }
 }
 
+/// A map header, like 'seed-to-soil map:'
+fn parse_map_name(line: &mut LineParser) -> ParseResult<String> {
+    let parts = line.separated("-", |p| p.word())?;
+    line.literal("map:")?;
+    line.end()?;
+    Ok(parts.join("-"))
+}
+
+/// A mapping line: 'destination_start source_start range'
+fn parse_map_entry(line: &mut LineParser) -> ParseResult<MapEntry> {
+    let output_start = line.number::<i64>()?;
+    let input_start = line.number::<i64>()?;
+    let range = line.number::<i64>()?;
+    line.end()?;
+    Ok(MapEntry {
+        input_start,
+        output_start,
+        range,
+    })
+}
+
 impl Day for Day05 {
     fn day_nr(&self) -> String {
         String::from("05")
@@ -104,128 +245,457 @@ impl Day for Day05 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day05.txt");
-        // let input = read_lines("data/day05-test.txt");
+        let input = Input::read("data/day05.txt");
+        // let input = Input::read("data/day05-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
+    /// The seed -> location chain is composed into a single piecewise-linear
+    /// function first, so each seed is just a lookup.
+    /// (Negative seeds are not covered by the almanac, and have no location.)
     fn solve1(&mut self) -> String {
-        let mut final_values: Vec<i64> = Vec::new();
-        for seed in self.seeds.iter() {
-            let mut act_val = *seed;
-            for map in self.material_maps.iter() {
-                act_val = find_mapped_value(map, act_val);
-            }
-            final_values.push(act_val);
+        let seed_to_location = compose_maps(&self.material_maps);
+        let min = self
+            .seeds
+            .iter()
+            .filter_map(|seed| seed_to_location.get(*seed))
+            .min();
+        match min {
+            Some(min) => String::from(format!("{0}", min)),
+            None => String::from("no seed with a location"),
         }
-        let min = final_values.iter().min().unwrap();
-        String::from(format!("{0}", *min))
     }
 
+    /// Instead of mapping each single seed (billions of them...), we push
+    /// whole seed ranges through the maps: each map splits the ranges at its
+    /// mapping boundaries, so after the last map we have a (small) list of location ranges.
+    /// The lowest location is then just the smallest range start.
     fn solve2(&mut self) -> String {
-        let mut final_values: Vec<i64> = Vec::new();
-        let final_seeds = merge_seeds(self.seeds.clone());
-        let (tx, rx) = channel();
-        for (start, end) in final_seeds.iter() {
-            let tx_thread = tx.clone();
-            let material_maps = self.material_maps.clone();
-            let start = *start;
-            let end = *end;
-            thread::spawn(move || {
-                let mut min: i64 = i64::MAX;
-                for seed in start..end {
-                    let mut act_val = seed;
-                    for map in material_maps.iter() {
-                        act_val = find_mapped_value(map, act_val);
-                    }
-                    if act_val < min {
-                        min = act_val;
-                    }
-                }
-                tx_thread.send(min).unwrap();
-            });
+        if self.seeds.len() % 2 == 1 {
+            return String::from("seeds must come in (start, length) pairs");
         }
-        drop(tx);
-        for val in rx.iter() {
-            final_values.push(val);
-        }
-
-        let min = final_values.iter().min().unwrap();
-        String::from(format!("{0}", *min))
-    }
-}
-
-/// Merge the seeds table into a overlap-removed seed table.
-/// We check each seed if it overlaps with the existing seeds,
-/// and split / remove them to non-overlapping versions.
-/// At the end, we return a non-overlapping (start, end) pair list.
-fn merge_seeds(mut seeds: Vec<i64>) -> Vec<(i64, i64)> {
-    let mut final_seeds: Vec<(i64, i64)> = Vec::new();
-
-    // takt the last 2 elements (start and len of seed), until
-    // there are no more pairs:
-    while seeds.len() > 0 {
-        let seed_len = seeds.pop().unwrap();
-        let mut seed_start = seeds.pop().unwrap();
-        let mut seed_end = seed_start + seed_len - 1;
-
-        // first seed goes to the final list anyway:
-        if final_seeds.len() == 0 {
-            final_seeds.push((seed_start, seed_end));
-            continue;
-        }
-
-        // Overlaps can happen as follows:
-        //      |--------final seed---------|
-        //           |..... within ....|
-        // |...........| start overlap
-        //               end overlap    |.............|
-        // |.....................................| <-- total overlap
-        // |..| <-- non-overlap
-        //                        non-overlap -->  |...|
-        for fseed in final_seeds.iter() {
-            // insertion seed fits within the target feed: skip it completely, already
-            // covered by the actual seed:
-            if seed_start >= fseed.0 && seed_end <= fseed.1 {
-                seed_end = -1;
-                break;
-            }
-            // does it overlap somehow?
-            if seed_start < fseed.1 && seed_end > fseed.0 {
-                // extract front part:
-                if seed_start < fseed.0 {
-                    seeds.push(seed_start);
-                    seeds.push(fseed.0 - seed_start);
-                }
-                // extract end part:
-                if seed_end > fseed.1 {
-                    seeds.push(fseed.1 + 1);
-                    seeds.push(seed_end - fseed.1);
-                }
-                seed_start = -1;
-                seed_end = -1;
-                break;
-            } else {
-                // no overlap, continue to the next final seed:
-                continue;
+        let final_seeds = seed_ranges(&self.seeds);
+        let min = match find_min_location(&self.material_maps, &final_seeds) {
+            Some(min) => min,
+            None => return String::from("no seed ranges"),
+        };
+        String::from(format!("{0}", min))
+    }
+}
+
+/// The seed table as (start, end) pairs: each (start, length) pair of seeds
+/// becomes an inclusive range, empty ranges are left out.
+/// Overlapping ranges are kept as they are: they only cost some mapping work,
+/// but do not change the lowest location.
+fn seed_ranges(seeds: &[i64]) -> Vec<(i64, i64)> {
+    seeds
+        .chunks(2)
+        .filter(|pair| pair[1] > 0)
+        .map(|pair| (pair[0], pair[0] + pair[1] - 1))
+        .collect()
+}
+
+/// Random input: a few seed ranges, and the 7 maps with non-overlapping source ranges,
+/// all within 0..100 * size
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let limit: u64 = 100 * size as u64;
+    let mut seeds: Vec<String> = Vec::new();
+    for _ in 0..rng.gen_range(2..=4) {
+        let start = rng.gen_range(0..limit);
+        seeds.push(start.to_string());
+        seeds.push(rng.gen_range(1..=(limit - start).min(50)).to_string());
+    }
+    let mut lines: Vec<String> = vec![format!("seeds: {0}", seeds.join(" "))];
+
+    let names = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+    for map in names.windows(2) {
+        lines.push(String::new());
+        lines.push(format!("{0}-to-{1} map:", map[0], map[1]));
+        // cut 0..limit into consecutive ranges, and map some of them:
+        let mut cuts: Vec<u64> = (0..rng.gen_range(2..=6))
+            .map(|_| rng.gen_range(0..=limit))
+            .collect();
+        cuts.push(0);
+        cuts.push(limit);
+        cuts.sort();
+        cuts.dedup();
+        for range in cuts.windows(2) {
+            if rng.gen_bool(0.7) {
+                let len = range[1] - range[0];
+                let dest = rng.gen_range(0..limit);
+                lines.push(format!("{0} {1} {2}", dest, range[0], len));
             }
         }
-        // no overlap, add it to final seeds:
-        if seed_start >= 0 && seed_end > 0 && seed_end >= seed_start {
-            final_seeds.push((seed_start, seed_end));
-        }
     }
-
-    // tuples with first and last value of a seed
-    final_seeds
+    lines
 }
 
+/// Maps a single value through a material map. This is the simple (slow) version
+/// of PiecewiseMap::get(), used as reference in the tests.
+#[cfg(test)]
 fn find_mapped_value(map: &MaterialMap, val: i64) -> i64 {
     for entry in map.mappings.iter() {
-        if val >= entry.input_start && val <= entry.input_start + entry.range {
+        if val >= entry.input_start && val < entry.input_start + entry.range {
             return entry.output_start + (val - entry.input_start);
         }
     }
     val
 }
+
+/// Maps a single seed through all material maps, and returns its location.
+#[cfg(test)]
+fn find_location(material_maps: &[MaterialMap], seed: i64) -> i64 {
+    let mut act_val = seed;
+    for map in material_maps.iter() {
+        act_val = find_mapped_value(map, act_val);
+    }
+    act_val
+}
+
+/// Composes all material maps into one seed -> location map.
+/// Without any maps, each (non-negative) seed is its own location.
+fn compose_maps(material_maps: &[MaterialMap]) -> PiecewiseMap {
+    let identity = PiecewiseMap::from_segments(vec![Segment {
+        start: 0,
+        end: i64::MAX,
+        offset: 0,
+    }]);
+    material_maps.iter().fold(identity, |composed, map| {
+        composed.then(&PiecewiseMap::from_material_map(map))
+    })
+}
+
+/// Returns the seeds that end up at the given location: a simple lookup in the inverse map,
+/// if there is one, or all origins of the location otherwise.
+fn seeds_for_location(seed_to_location: &PiecewiseMap, location: i64) -> Vec<i64> {
+    match seed_to_location.inverse() {
+        Some(location_to_seed) => location_to_seed.get(location).into_iter().collect(),
+        None => seed_to_location.preimages(location),
+    }
+}
+
+/// Maps a list of (start, end) value ranges (both inclusive) through a material map:
+/// Each range is split at the mapping entry boundaries, so that each resulting piece
+/// is either mapped completely by one entry, or not mapped at all (and keeps its values).
+fn map_ranges(map: &MaterialMap, ranges: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut mapped: Vec<(i64, i64)> = Vec::new();
+    let mut unmapped = ranges.to_vec();
+
+    for entry in map.mappings.iter() {
+        let entry_end = entry.input_start + entry.range - 1;
+        let offset = entry.output_start - entry.input_start;
+        let mut rest: Vec<(i64, i64)> = Vec::new();
+        for (start, end) in unmapped {
+            // no overlap with this entry: keep it for the next entries
+            if end < entry.input_start || start > entry_end {
+                rest.push((start, end));
+                continue;
+            }
+            // parts before / after the entry are checked against the next entries:
+            if start < entry.input_start {
+                rest.push((start, entry.input_start - 1));
+            }
+            if end > entry_end {
+                rest.push((entry_end + 1, end));
+            }
+            // the overlapping part is moved by the entry's offset:
+            mapped.push((
+                start.max(entry.input_start) + offset,
+                end.min(entry_end) + offset,
+            ));
+        }
+        unmapped = rest;
+    }
+
+    // whatever is left is not covered by any entry, and maps to itself:
+    mapped.extend(unmapped);
+    mapped
+}
+
+/// Pushes the seed ranges through all material maps, and returns the lowest location,
+/// or None if there are no seed ranges.
+fn find_min_location(material_maps: &[MaterialMap], seed_ranges: &[(i64, i64)]) -> Option<i64> {
+    let mut ranges = seed_ranges.to_vec();
+    for map in material_maps.iter() {
+        ranges = map_ranges(map, &ranges);
+    }
+    ranges.iter().map(|(start, _)| *start).min()
+}
+
+/// Brute-force version of find_min_location(): maps every single seed of the ranges.
+/// Way too slow for the real input, but obviously correct, so we use it to
+/// verify the range-based version.
+#[cfg(test)]
+fn find_min_location_brute_force(material_maps: &[MaterialMap], seed_ranges: &[(i64, i64)]) -> i64 {
+    let mut min = i64::MAX;
+    for (start, end) in seed_ranges.iter() {
+        for seed in *start..=*end {
+            min = min.min(find_location(material_maps, seed));
+        }
+    }
+    min
+}
+
+/// The `almanac <file> [location ...]` command: prints the names of the maps and
+/// the composed seed -> location map of an almanac (one line per segment: seeds -> locations (offset)),
+/// and which seeds produce the given locations.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let usage = "usage: almanac <file> [location ...]";
+    let (file, locations) = args.split_first().ok_or(usage)?;
+    let locations = locations
+        .iter()
+        .map(|l| l.parse().map_err(|_| format!("invalid location: {}", l)))
+        .collect::<Result<Vec<i64>, String>>()?;
+    if !Path::new(file).is_file() {
+        return Err(format!("cannot read {0}", file));
+    }
+    let mut day = Day05::new();
+    day.parse(Input::read(file)).map_err(|e| e.to_string())?;
+
+    let names: Vec<&str> = day.material_maps.iter().map(|m| m.name.as_str()).collect();
+    println!("maps: {0}", names.join(", "));
+    let seed_to_location = compose_maps(&day.material_maps);
+    println!(
+        "seed -> location map, {0} segments:",
+        seed_to_location.segments.len()
+    );
+    print!("{0}", seed_to_location);
+    for location in locations {
+        println!(
+            "seeds for location {0}: {1:?}",
+            location,
+            seeds_for_location(&seed_to_location, location)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difftest;
+
+    fn parse_file(filename: &str) -> Day05 {
+        let mut day = Day05::new();
+        day.input = Input::read(filename);
+        day.parse_input().unwrap();
+        day
+    }
+
+    #[test]
+    fn test_parse_last_block_and_trailing_lines() {
+        let lines = [
+            "seeds: 79 14",
+            "",
+            "seed-to-soil map:",
+            "50 98 2",
+            "",
+            "",
+            "soil-to-fertilizer map:  ",
+            "0 15 37",
+            "37 52 2",
+            "",
+            "  ",
+            "",
+        ];
+        let mut day = Day05::new();
+        day.input = Input::from_lines("test.txt", lines.iter().map(|l| l.to_string()).collect());
+        day.parse_input().unwrap();
+        assert_eq!(day.seeds, vec![79, 14]);
+        assert_eq!(day.material_maps.len(), 2);
+        assert_eq!(day.material_maps[1].name, "soil-to-fertilizer");
+        assert_eq!(day.material_maps[1].mappings.len(), 2);
+    }
+
+    #[test]
+    fn test_find_mapped_value_range_end() {
+        let map = MaterialMap {
+            name: String::from("test"),
+            mappings: vec![MapEntry {
+                input_start: 10,
+                output_start: 100,
+                range: 5,
+            }],
+        };
+        assert_eq!(find_mapped_value(&map, 9), 9);
+        assert_eq!(find_mapped_value(&map, 10), 100);
+        assert_eq!(find_mapped_value(&map, 14), 104);
+        assert_eq!(find_mapped_value(&map, 15), 15);
+    }
+
+    #[test]
+    fn test_range_mapping_matches_brute_force() {
+        for filename in ["data/day05-test.txt", "data/day05-test2.txt"] {
+            let day = parse_file(filename);
+            let seed_ranges = seed_ranges(&day.seeds);
+            assert_eq!(
+                find_min_location(&day.material_maps, &seed_ranges).unwrap(),
+                find_min_location_brute_force(&day.material_maps, &seed_ranges),
+                "range-based and brute-force result differ for {}",
+                filename
+            );
+        }
+    }
+
+    #[test]
+    fn test_seed_range_at_zero() {
+        // a length-1 range at seed 0, followed by another pair:
+        let mut lines = vec![String::from("seeds: 0 1 5 3"), String::new()];
+        let example = Input::read("data/day05-test.txt");
+        lines.extend(example.raw_lines().iter().skip(2).cloned());
+        let mut day = Day05::new();
+        day.input = Input::from_lines("test.txt", lines);
+        day.parse_input().unwrap();
+
+        let ranges = seed_ranges(&day.seeds);
+        assert_eq!(ranges, vec![(0, 0), (5, 7)]);
+        let expected = find_min_location_brute_force(&day.material_maps, &ranges);
+        assert_eq!(expected, find_location(&day.material_maps, 0));
+        assert_eq!(day.solve2(), expected.to_string());
+    }
+
+    #[test]
+    fn test_composed_map_matches_single_maps() {
+        let day = parse_file("data/day05-test.txt");
+        let composed = compose_maps(&day.material_maps);
+        for seed in 0..200 {
+            assert_eq!(
+                composed.get(seed),
+                Some(find_location(&day.material_maps, seed))
+            );
+        }
+        assert_eq!(composed.get(-1), None);
+    }
+
+    #[test]
+    fn test_inverse_map() {
+        let day = parse_file("data/day05-test.txt");
+        let composed = compose_maps(&day.material_maps);
+        let inverse = composed.inverse().unwrap();
+        for seed in 0..200 {
+            let location = composed.get(seed).unwrap();
+            assert_eq!(inverse.get(location), Some(seed));
+            assert_eq!(composed.preimages(location), vec![seed]);
+        }
+        // seed 82 is the closest one in the example, at location 46:
+        assert_eq!(inverse.get(46), Some(82));
+    }
+
+    #[test]
+    fn test_non_invertible_map() {
+        let map = MaterialMap {
+            name: String::from("test"),
+            mappings: vec![MapEntry {
+                input_start: 10,
+                output_start: 0,
+                range: 5,
+            }],
+        };
+        let map = PiecewiseMap::from_material_map(&map);
+        assert_eq!(map.inverse(), None);
+        assert_eq!(map.preimages(2), vec![2, 12]);
+        assert_eq!(map.preimages(12), vec![]);
+    }
+
+    #[test]
+    fn test_seeds_for_location() {
+        let day = parse_file("data/day05-test.txt");
+        let composed = compose_maps(&day.material_maps);
+        assert_eq!(seeds_for_location(&composed, 46), vec![82]);
+        let map = MaterialMap {
+            name: String::from("test"),
+            mappings: vec![MapEntry {
+                input_start: 10,
+                output_start: 0,
+                range: 5,
+            }],
+        };
+        assert_eq!(seeds_for_location(&compose_maps(&[map]), 2), vec![2, 12]);
+    }
+
+    #[test]
+    fn test_missing_seeds_or_maps() {
+        let parse = |lines: &[&str]| {
+            let mut day = Day05::new();
+            let lines = lines.iter().map(|l| l.to_string()).collect();
+            day.input = Input::from_lines("test.txt", lines);
+            day.parse_input().unwrap();
+            day
+        };
+        // no maps: each seed is its own location
+        let mut day = parse(&["seeds: 79 14 55 13"]);
+        assert_eq!(day.solve1(), "13");
+        assert_eq!(day.solve2(), "55");
+        let mut day = parse(&["seeds:", "", "seed-to-soil map:", "50 98 2"]);
+        assert_eq!(day.solve1(), "no seed with a location");
+        assert_eq!(day.solve2(), "no seed ranges");
+        let mut day = parse(&["seeds: -5 1 2"]);
+        assert_eq!(day.solve1(), "1");
+        assert_eq!(day.solve2(), "seeds must come in (start, length) pairs");
+    }
+
+    #[test]
+    fn test_solve2_example() {
+        let mut day = parse_file("data/day05-test.txt");
+        assert_eq!(day.solve2(), "46");
+    }
+
+    #[test]
+    fn test_solutions_match_single_seed_mapping() {
+        let parse = |input: &[String]| -> Option<Day05> {
+            let mut day = Day05::new();
+            day.input = Input::from_lines("generated", input.to_vec());
+            day.parse_input().ok()?;
+            // only valid almanacs: seed pairs with a length, and no overlapping source ranges
+            let pairs_ok = day
+                .seeds
+                .chunks(2)
+                .all(|pair| pair.len() == 2 && pair[1] > 0);
+            let maps_ok = day.material_maps.iter().all(|map| {
+                let mut ranges: Vec<(i64, i64)> = map
+                    .mappings
+                    .iter()
+                    .map(|m| (m.input_start, m.input_start + m.range))
+                    .collect();
+                ranges.sort();
+                ranges.windows(2).all(|r| r[0].1 <= r[1].0)
+            });
+            match !day.seeds.is_empty() && pairs_ok && maps_ok {
+                true => Some(day),
+                false => None,
+            }
+        };
+        let reference = |input: &[String]| {
+            let day = parse(input)?;
+            let maps = &day.material_maps;
+            let part1 = day.seeds.iter().map(|s| find_location(maps, *s)).min()?;
+            let ranges: Vec<(i64, i64)> = day
+                .seeds
+                .chunks(2)
+                .map(|pair| (pair[0], pair[0] + pair[1] - 1))
+                .collect();
+            let part2 = find_min_location_brute_force(maps, &ranges);
+            Some((part1.to_string(), part2.to_string()))
+        };
+        let optimised = |input: &[String]| {
+            let mut day = parse(input)?;
+            Some((day.solve1(), day.solve2()))
+        };
+        let generate = |rng: &mut StdRng| generate(rng.gen_range(1..=3), rng);
+        difftest::assert_agree(100, generate, reference, optimised);
+    }
+}
@@ -1,40 +1,76 @@
 use super::Day;
-use alex_lib::read_lines;
+use crate::parser::{self, Input, ParseErrors, ParseResult};
 use itertools::Itertools;
-use regex::Regex;
+use num::{BigUint, One, Zero};
+use rand::{rngs::StdRng, Rng};
 
 #[derive(Debug)]
 pub struct Day06 {
-    input: Vec<String>,
-    times: Vec<i64>,
-    distances: Vec<i64>,
+    input: Input,
+    times: Vec<BigUint>,
+    distances: Vec<BigUint>,
 }
 
 impl Day06 {
     pub fn new() -> Day06 {
         Day06 {
-            input: Vec::new(),
+            input: Input::new(),
             times: Vec::new(),
             distances: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
+    fn parse_input(&mut self) -> ParseResult<()> {
         // Line 1: Times:
-        let nr_regex = Regex::new(r"(\d+)").unwrap();
-        let times = nr_regex.find_iter(self.input.get(0).unwrap());
-        for t in times {
-            self.times.push(t.as_str().parse::<i64>().unwrap());
-        }
+        let mut line = self.input.line(0);
+        line.literal("Time:")?;
+        self.times = line.numbers::<BigUint>()?;
 
         // Line 2: Distances:
-        let distances = nr_regex.find_iter(self.input.get(1).unwrap());
-        for t in distances {
-            self.distances.push(t.as_str().parse::<i64>().unwrap());
+        let mut line = self.input.line(1);
+        line.literal("Distance:")?;
+        self.distances = line.numbers::<BigUint>()?;
+
+        if self.times.len() != self.distances.len() {
+            return Err(line.error(&format!("{} distances", self.times.len())));
         }
+        Ok(())
+    }
+
+    /// The numbers of a line, with the spaces removed: concatenated to a single number (0 if there are none)
+    fn kerned(numbers: &[BigUint]) -> BigUint {
+        numbers
+            .iter()
+            .map(|nr| nr.to_string())
+            .join("")
+            .parse()
+            .unwrap_or_default()
     }
 }
 
+/// Counts the hold times that beat the distance: hold * (time - hold) > dist.
+/// The winning hold times lie strictly between the roots of
+/// hold^2 - time * hold + dist = 0, which are (time ± sqrt(time^2 - 4 * dist)) / 2.
+/// With the integer square root, (time - isqrt) / 2 is at most 1 below the first winning
+/// hold time, so only the (exact) ties at the boundary need to be checked.
+/// The winning range is symmetric around time / 2.
+fn count_wins(time: &BigUint, dist: &BigUint) -> BigUint {
+    let wins = |hold: &BigUint| hold * (time - hold) > *dist;
+    let four_dist: BigUint = dist * 4u32;
+    let square = time * time;
+    if square < four_dist {
+        return BigUint::zero();
+    }
+    let mut hold: BigUint = (time - (square - four_dist).sqrt()) / 2u32;
+    while &hold * 2u32 <= *time && !wins(&hold) {
+        hold += 1u32;
+    }
+    if &hold * 2u32 > *time {
+        return BigUint::zero();
+    }
+    time - hold * 2u32 + 1u32
+}
+
 impl Day for Day06 {
     fn day_nr(&self) -> String {
         String::from("06")
@@ -44,57 +80,122 @@ impl Day for Day06 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day06.txt");
-        // let input = read_lines("data/day06-test.txt");
+        let input = Input::read("data/day06.txt");
+        // let input = Input::read("data/day06-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        Ok(self.parse_input()?)
     }
 
     fn solve1(&mut self) -> String {
-        let mut solution: u64 = 1;
-
-        for (i, time) in self.times.iter().enumerate() {
-            let mut win_count: u64 = 0;
-            let dist = *self.distances.get(i).unwrap();
-            for hold in 0..=*time {
-                let mydist = hold * (time - hold);
-                if mydist > dist {
-                    win_count += 1;
-                }
-            }
-            println!("Win count for time {0}: {1}", time, win_count);
-            solution *= win_count;
+        let mut solution = BigUint::one();
+        for (time, dist) in self.times.iter().zip(self.distances.iter()) {
+            solution *= count_wins(time, dist);
         }
-
         String::from(format!("{0}", solution))
     }
 
     fn solve2(&mut self) -> String {
-        let mut solution: u64 = 1;
-        let time = self
-            .times
-            .iter()
-            .map(|nr| nr.to_string())
-            .join("")
-            .parse::<u64>()
-            .unwrap();
-        let dist = self
-            .distances
-            .iter()
-            .map(|nr| nr.to_string())
-            .join("")
-            .parse::<u64>()
-            .unwrap();
-
-        let mut win_count: u64 = 0;
-        for hold in 0..=time {
-            let mydist = hold * (time - hold);
-            if mydist > dist {
-                win_count += 1;
+        let time = Day06::kerned(&self.times);
+        let dist = Day06::kerned(&self.distances);
+        String::from(format!("{0}", count_wins(&time, &dist)))
+    }
+}
+
+/// Random input: `size` races (at least 1), which can all be won. The race times get
+/// longer with the size (from 2 up to 9 digits), so the concatenated race for part 2
+/// gets arbitrarily long. It can be won, too.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let digits = (size / 4 + 2).min(9);
+    let max_time: u64 = 10u64.pow(digits as u32) - 1;
+    loop {
+        let mut times: Vec<u64> = Vec::new();
+        let mut distances: Vec<u64> = Vec::new();
+        for _ in 0..size.max(1) {
+            let time = rng.gen_range(7..=max_time);
+            let best = (time / 2) * (time - time / 2);
+            times.push(time);
+            distances.push(rng.gen_range(best / 3..best));
+        }
+        let concat = |values: &Vec<u64>| -> BigUint {
+            let values: Vec<BigUint> = values.iter().map(|v| BigUint::from(*v)).collect();
+            Day06::kerned(&values)
+        };
+        if count_wins(&concat(&times), &concat(&distances)).is_zero() {
+            continue;
+        }
+        let format = |values: &Vec<u64>| -> String {
+            let values: Vec<String> = values
+                .iter()
+                .map(|v| format!("{0:>1$}", v, 2 * digits + 1))
+                .collect();
+            values.concat()
+        };
+        return vec![
+            format!("Time:    {0}", format(&times)),
+            format!("Distance:{0}", format(&distances)),
+        ];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(time: u64, dist: u64) -> u64 {
+        let wins = count_wins(&BigUint::from(time), &BigUint::from(dist));
+        u64::try_from(wins).unwrap()
+    }
+
+    #[test]
+    fn test_boundary_ties() {
+        // the roots 10 and 20 are exact, and do not win:
+        assert_eq!(count(30, 200), 9);
+        assert_eq!(count(4, 4), 0);
+        assert_eq!(count(4, 3), 1);
+        assert_eq!(count(10, 0), 9);
+        assert_eq!(count(0, 0), 0);
+    }
+
+    #[test]
+    fn test_same_as_trying_every_hold_time() {
+        for time in 0..60 {
+            for dist in 0..=(time * time / 4 + 2) {
+                let expected = (0..=time).filter(|h| h * (time - h) > dist).count();
+                assert_eq!(count(time, dist), expected as u64, "{time} {dist}");
             }
         }
-        println!("Win count for time {0}: {1}", time, win_count);
-        solution *= win_count;
-        String::from(format!("{0}", solution))
+    }
+
+    #[test]
+    fn test_generated_long_races() {
+        use rand::SeedableRng;
+        let rng = &mut StdRng::seed_from_u64(6);
+        for size in [1, 10, 40] {
+            let mut day = Day06::new();
+            let input = Input::from_lines("generated", generate(size, rng));
+            day.parse(input).unwrap();
+            assert_eq!(day.times.len(), size);
+            assert_ne!(day.solve1(), "0");
+            assert_ne!(day.solve2(), "0");
+        }
+        // 40 races of up to 9 digits: the concatenated race does not fit in a u128
+        let mut day = Day06::new();
+        let input = Input::from_lines("generated", generate(40, rng));
+        day.parse(input).unwrap();
+        assert!(Day06::kerned(&day.times).bits() > 128);
+    }
+
+    #[test]
+    fn test_huge_race() {
+        // time = 2k, the best distance is k^2: only holding k ms (± 1) beats k^2 - 1 (k^2 - 4)
+        let k = BigUint::from(10u32).pow(40);
+        let time = &k * 2u32;
+        assert_eq!(count_wins(&time, &(&k * &k - 1u32)), BigUint::from(1u32));
+        assert_eq!(count_wins(&time, &(&k * &k - 4u32)), BigUint::from(3u32));
+        assert_eq!(count_wins(&time, &(&k * &k)), BigUint::zero());
     }
 }
@@ -1,132 +1,152 @@
-use std::{cmp::Ordering, collections::HashMap, rc::Rc};
+use std::collections::{HashMap, HashSet};
 
 use super::Day;
-use alex_lib::read_lines;
-use regex::Regex;
-
-type TypeRank = u64;
-
-#[derive(Debug)]
-struct Card {
-    card_type: TypeRank,
-    type_str: String,
-    hand: String,
-    bid: u64,
-    card_points: Rc<HashMap<char, u64>>,
+use crate::generate;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, Rng};
+
+/// Hands sort by their key: first by type, then card by card
+type SortKey = Vec<usize>;
+
+/// The rules of a Camel Cards variant: the cards from lowest to highest,
+/// the wildcards that act like any other card (none, one or several), and the hand size.
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    card_order: Vec<char>,
+    wildcards: HashSet<char>,
+    hand_size: usize,
 }
 
-impl Card {
-    fn compare_hands(&self, a: &String, b: &String) -> Ordering {
-        for i in 0..a.len() {
-            let c_a = a.chars().nth(i).unwrap();
-            let c_b = b.chars().nth(i).unwrap();
-            let p_a = self.card_points.get(&c_a).unwrap();
-            let p_b = self.card_points.get(&c_b).unwrap();
-            if p_a < p_b {
-                return Ordering::Less;
-            }
-            if p_a > p_b {
-                return Ordering::Greater;
-            }
+impl RuleSet {
+    pub fn new(card_order: &str, wildcards: &str, hand_size: usize) -> RuleSet {
+        RuleSet {
+            card_order: card_order.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+            hand_size,
         }
-        Ordering::Equal
     }
-}
 
-impl PartialEq for Card {
-    fn eq(&self, other: &Self) -> bool {
-        self.hand == other.hand
+    /// Part 1: no wildcards
+    pub fn standard() -> RuleSet {
+        RuleSet::new("23456789TJQKA", "", 5)
+    }
+
+    /// Part 2: J is a joker, the lowest card
+    pub fn jokers() -> RuleSet {
+        RuleSet::new("J23456789TQKA", "J", 5)
     }
-}
 
-impl Eq for Card {}
+    /// The rank of the card, 0 for the lowest
+    pub fn card_value(&self, card: char) -> Option<usize> {
+        self.card_order.iter().position(|c| *c == card)
+    }
 
-impl PartialOrd for Card {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.card_type < other.card_type {
-            return Some(std::cmp::Ordering::Less);
+    /// The type of a hand, as the sizes of its groups of equal cards, largest first:
+    /// e.g. [3, 2] for a full house, [2, 2, 1] for two pairs. Comparing these sizes
+    /// ranks the types: all hands have the same size, so a difference shows up before
+    /// the shorter one ends.
+    /// The wildcards join the largest group, which makes the best possible hand.
+    pub fn hand_type(&self, hand: &str) -> Vec<usize> {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        let mut wildcards = 0;
+        for card in hand.chars() {
+            match self.wildcards.contains(&card) {
+                true => wildcards += 1,
+                false => *counts.entry(card).or_insert(0) += 1,
+            }
         }
-        if self.card_type > other.card_type {
-            return Some(std::cmp::Ordering::Greater);
+        let mut groups: Vec<usize> = counts.into_values().collect();
+        groups.sort_by(|a, b| b.cmp(a));
+        match groups.first_mut() {
+            Some(largest) => *largest += wildcards,
+            None => groups.push(wildcards),
         }
-        return Some(self.compare_hands(&self.hand, &other.hand));
+        groups
     }
-}
 
-impl Ord for Card {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.partial_cmp(other) {
-            Some(o) => o,
-            _ => panic!("Failed to compare cards"),
-        }
+    /// The hand type, followed by the card values: hands are ranked by sorting their keys.
+    pub fn sort_key(&self, hand: &str) -> SortKey {
+        let mut key = self.hand_type(hand);
+        key.extend(hand.chars().map(|c| self.card_value(c).unwrap_or(0)));
+        key
     }
 }
 
+#[derive(Debug)]
+struct Hand {
+    cards: String,
+    bid: u64,
+}
+
 #[derive(Debug)]
 pub struct Day07 {
-    input: Vec<String>,
-    card_points: Rc<HashMap<char, u64>>,
-    card_points2: Rc<HashMap<char, u64>>,
-    cards: Vec<Card>,
+    input: Input,
+    rules: RuleSet,
+    joker_rules: RuleSet,
+    hands: Vec<Hand>,
 }
 
 impl Day07 {
     pub fn new() -> Day07 {
         Day07 {
-            input: Vec::new(),
-            card_points: Rc::new(HashMap::from([
-                ('2', 2),
-                ('3', 3),
-                ('4', 4),
-                ('5', 5),
-                ('6', 6),
-                ('7', 7),
-                ('8', 8),
-                ('9', 9),
-                ('T', 10),
-                ('J', 11),
-                ('Q', 12),
-                ('K', 13),
-                ('A', 14),
-            ])),
-            card_points2: Rc::new(HashMap::from([
-                ('J', 1),
-                ('2', 2),
-                ('3', 3),
-                ('4', 4),
-                ('5', 5),
-                ('6', 6),
-                ('7', 7),
-                ('8', 8),
-                ('9', 9),
-                ('T', 10),
-                ('Q', 12),
-                ('K', 13),
-                ('A', 14),
-            ])),
-            cards: Vec::new(),
+            input: Input::new(),
+            rules: RuleSet::standard(),
+            joker_rules: RuleSet::jokers(),
+            hands: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let matcher = Regex::new(r"(\w+)\s+(\d+)").unwrap();
-        for line in self.input.iter() {
-            if let Some(group) = matcher.captures(line) {
-                let hand = group.get(1).unwrap().as_str();
-                let bid = group.get(2).unwrap().as_str().parse::<u64>().unwrap();
-                self.cards.push(Card {
-                    hand: String::from(hand),
+    /// Parses all hands, and reports all invalid lines at once.
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        // matches: 32T3K 765
+        for mut line in self.input.non_empty_lines() {
+            if let Some((cards, bid)) = errors.check(parse_hand(&self.rules, &mut line)) {
+                self.hands.push(Hand {
+                    cards: String::from(cards),
                     bid,
-                    card_type: 0,
-                    type_str: "".to_string(),
-                    card_points: self.card_points.clone(),
                 });
             }
         }
-        // println!("{:?}", self.cards);
+        errors.into_result()
+    }
+
+    /// Ranks the hands with the given rules: each hand wins its bid times its rank.
+    fn total_winnings(&self, rules: &RuleSet) -> u64 {
+        let mut ranked: Vec<(SortKey, u64)> = self
+            .hands
+            .iter()
+            .map(|hand| (rules.sort_key(&hand.cards), hand.bid))
+            .collect();
+        ranked.sort();
+        ranked
+            .iter()
+            .enumerate()
+            .map(|(i, (_, bid))| (i as u64 + 1) * bid)
+            .sum()
     }
 }
 
+/// A hand of cards known to the rules, of the rules' hand size, followed by the bid.
+/// (Both rule sets of the puzzle know the same cards.)
+fn parse_hand<'a>(rules: &RuleSet, line: &mut LineParser<'a>) -> ParseResult<(&'a str, u64)> {
+    line.skip_whitespace();
+    let start = line.mark();
+    let hand = line.word()?;
+    for (pos, c) in hand.char_indices() {
+        if rules.card_value(c).is_none() {
+            let cards: String = rules.card_order.iter().collect();
+            return Err(line.char_error_at(start + pos, &format!("a card (one of {0})", cards)));
+        }
+    }
+    if hand.chars().count() != rules.hand_size {
+        return Err(line.error_at(start, &format!("a hand of {0} cards", rules.hand_size)));
+    }
+    let bid = line.number::<u64>()?;
+    line.end()?;
+    Ok((hand, bid))
+}
+
 impl Day for Day07 {
     fn day_nr(&self) -> String {
         String::from("07")
@@ -136,216 +156,77 @@ impl Day for Day07 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day07.txt");
-        // let input = read_lines("data/day07-test.txt");
+        let input = Input::read("data/day07.txt");
+        // let input = Input::read("data/day07-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
-        let mut solution: u64 = 0;
-
-        for card in self.cards.iter_mut() {
-            card.card_points = self.card_points.clone();
-            let (card_type, type_str) = calc_type(card);
-            card.card_type = card_type;
-            card.type_str = type_str;
-        }
-
-        self.cards.sort();
-
-        for (i, card) in self.cards.iter().enumerate() {
-            // println!(
-            //     "Cards: {0}, Type: {1} ({2})",
-            //     card.hand, card.card_type, card.type_str
-            // );
-            solution += (i as u64 + 1) * card.bid;
-        }
-
+        let solution = self.total_winnings(&self.rules);
         String::from(format!("{0}", solution))
     }
 
     fn solve2(&mut self) -> String {
-        let mut solution: u64 = 0;
-
-        for card in self.cards.iter_mut() {
-            card.card_points = self.card_points2.clone();
-            let (card_type, type_str) = calc_type2(card);
-            card.card_type = card_type;
-            card.type_str = type_str;
-        }
-
-        self.cards.sort();
-
-        for (i, card) in self.cards.iter().enumerate() {
-            // println!(
-            //     "Cards: {0}, Type: {1} ({2})",
-            //     card.hand, card.card_type, card.type_str
-            // );
-            solution += (i as u64 + 1) * card.bid;
-        }
-
+        let solution = self.total_winnings(&self.joker_rules);
         String::from(format!("{0}", solution))
     }
 }
 
-fn calc_type(card: &Card) -> (TypeRank, String) {
-    let mut card_count: HashMap<char, u64> = HashMap::new();
-    // count the amount of each card in the hand:
-    for c in card.hand.chars() {
-        if let Some(count) = card_count.get(&c) {
-            card_count.insert(c, count + 1);
-        } else {
-            card_count.insert(c, 1);
-        }
-    }
-
-    // a list of how many times a certain amount of cards appear:
-    // e.g. for a Two Pair, we have 2 labels that appear 2 times, and one label that appear one time.
-    let mut group_count: HashMap<u64, u64> = HashMap::new();
-    for amount in card_count.values() {
-        if let Some(count) = group_count.get(amount) {
-            group_count.insert(*amount, count + 1);
-        } else {
-            group_count.insert(*amount, 1);
-        }
-    }
-
-    // figure out the type of the card:
-
-    // five of a kind:
-    if let Some(count) = group_count.get(&5) {
-        if *count == 1 {
-            return (7, "Five of a kind".to_string());
-        }
-    }
-    // four of a kind:
-    if let Some(count) = group_count.get(&4) {
-        if *count == 1 {
-            return (6, "Four of a kind".to_string());
-        }
-    }
-    // Full house: 3 same, and 2 same:
-    if group_count.contains_key(&3) && group_count.contains_key(&2) {
-        return (5, "Full House".to_string());
-    }
-
-    // Three of a kind: 3 same, and 2 different:
-    if group_count.contains_key(&3) && group_count.contains_key(&1) {
-        return (4, "Three of a kind".to_string());
-    }
-
-    // Two pair
-    if let Some(count) = group_count.get(&2) {
-        if *count == 2 {
-            return (3, "Two pair".to_string());
-        }
-    }
-
-    // One Pair
-    if let Some(count_2) = group_count.get(&2) {
-        if let Some(count_1) = group_count.get(&1) {
-            if *count_2 == 1 && *count_1 == 3 {
-                return (2, "One pair".to_string());
-            }
-        }
-    }
-
-    // High card
-    if let Some(count) = group_count.get(&1) {
-        if *count == 5 {
-            return (1, "High card".to_string());
-        }
-    }
-
-    panic!("Unknown card type");
+/// Random input: `size` distinct hands with their bids
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    let mut lines: Vec<String> = Vec::new();
+    while lines.len() < size {
+        // use less different cards from time to time, for more pairs, full houses, ...:
+        let cards = &"23456789TJQKA"[rng.gen_range(0..9)..];
+        let hand = generate::unique_name(rng, 5, cards, &mut used);
+        lines.push(format!("{0} {1}", hand, rng.gen_range(1..=1000)));
+    }
+    lines
 }
 
-fn calc_type2(card: &Card) -> (TypeRank, String) {
-    let mut card_count: HashMap<char, u64> = HashMap::new();
-    // count the amount of each card in the hand:
-    // count the J's to the most present card:
-    let mut count_j = 0;
-    for c in card.hand.chars() {
-        if c == 'J' {
-            count_j += 1;
-        } else {
-            if let Some(count) = card_count.get(&c) {
-                card_count.insert(c, count + 1);
-            } else {
-                card_count.insert(c, 1);
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hand_types() {
+        let rules = RuleSet::standard();
+        assert_eq!(rules.hand_type("AAAAA"), vec![5]);
+        assert_eq!(rules.hand_type("AA8AA"), vec![4, 1]);
+        assert_eq!(rules.hand_type("23332"), vec![3, 2]);
+        assert_eq!(rules.hand_type("TTT98"), vec![3, 1, 1]);
+        assert_eq!(rules.hand_type("23432"), vec![2, 2, 1]);
+        assert_eq!(rules.hand_type("A23A4"), vec![2, 1, 1, 1]);
+        assert_eq!(rules.hand_type("23456"), vec![1, 1, 1, 1, 1]);
+        // the J is a normal card:
+        assert_eq!(rules.hand_type("KTJJT"), vec![2, 2, 1]);
+
+        let jokers = RuleSet::jokers();
+        assert_eq!(jokers.hand_type("KTJJT"), vec![4, 1]);
+        assert_eq!(jokers.hand_type("JJJJJ"), vec![5]);
+        assert!(jokers.sort_key("JKKK2") < jokers.sort_key("QQQQ2"));
+    }
+
+    #[test]
+    fn test_other_rule_sets() {
+        // two kinds of wildcards, 3 cards per hand:
+        let rules = RuleSet::new("xyabc", "xy", 3);
+        assert_eq!(rules.hand_type("axy"), vec![3]);
+        assert_eq!(rules.hand_type("aby"), vec![2, 1]);
+        assert!(rules.sort_key("abc") < rules.sort_key("xbc"));
+        assert!(rules.sort_key("xbc") < rules.sort_key("ybc"));
+
+        let mut line = LineParser::new("test.txt", 1, "abcc 12");
+        assert!(parse_hand(&rules, &mut line).is_err());
+        let mut line = LineParser::new("test.txt", 1, "yba 12");
+        assert_eq!(parse_hand(&rules, &mut line).unwrap(), ("yba", 12));
+        let mut line = LineParser::new("test.txt", 1, "ybd 12");
+        assert!(parse_hand(&rules, &mut line).is_err());
     }
-    if count_j == 5 {
-        card_count.insert('A', 5);
-    } else {
-        let (c, val) = card_count.iter().max_by(|a, b| a.1.cmp(b.1)).unwrap();
-        // println!("Card: {0}, max char: {1}, max: {2}", card.hand, c, val);
-        card_count.insert(*c, val + count_j);
-    }
-
-    // a list of how many times a certain amount of cards appear:
-    // e.g. for a Two Pair, we have 2 labels that appear 2 times, and one label that appear one time.
-    let mut group_count: HashMap<u64, u64> = HashMap::new();
-    for (c, amount) in card_count.iter() {
-        if *c == 'J' {
-            count_j += amount;
-        } else {
-            if let Some(count) = group_count.get(amount) {
-                group_count.insert(*amount, count + 1);
-            } else {
-                group_count.insert(*amount, 1);
-            }
-        }
-    }
-    // figure out the type of the card:
-    // println!("Card {0}, group counts: {1:?}", card.hand, group_count);
-
-    // five of a kind:
-    if let Some(count) = group_count.get(&5) {
-        if *count == 1 {
-            return (7, "Five of a kind".to_string());
-        }
-    }
-    // four of a kind:
-    if let Some(count) = group_count.get(&4) {
-        if *count == 1 {
-            return (6, "Four of a kind".to_string());
-        }
-    }
-    // Full house: 3 same, and 2 same:
-    if group_count.contains_key(&3) && group_count.contains_key(&2) {
-        return (5, "Full House".to_string());
-    }
-
-    // Three of a kind: 3 same, and 2 different:
-    if group_count.contains_key(&3) && group_count.contains_key(&1) {
-        return (4, "Three of a kind".to_string());
-    }
-
-    // Two pair
-    if let Some(count) = group_count.get(&2) {
-        if *count == 2 {
-            return (3, "Two pair".to_string());
-        }
-    }
-
-    // One Pair
-    if let Some(count_2) = group_count.get(&2) {
-        if let Some(count_1) = group_count.get(&1) {
-            if *count_2 == 1 && *count_1 == 3 {
-                return (2, "One pair".to_string());
-            }
-        }
-    }
-
-    // High card
-    if let Some(count) = group_count.get(&1) {
-        if *count == 5 {
-            return (1, "High card".to_string());
-        }
-    }
-
-    panic!("Unknown card type");
 }
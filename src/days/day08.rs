@@ -1,19 +1,105 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::path::Path;
 
 use super::Day;
-use alex_lib::{read_lines, lcm};
-use regex::Regex;
+use crate::cycles::{self, Cycle, ModulusOverflow};
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use alex_lib::lcm;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 
 #[derive(Debug)]
 struct Node {
-    name: String,
     left: String,
     right: String,
 }
 
+/// Which assumption about its Z nodes a ghost's walk satisfies, from the strongest to the weakest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Assumption {
+    /// It reaches a Z node only once per cycle, exactly after each full period: the Z steps
+    /// are the multiples of the period, so the lcm of all periods is the solution.
+    Lcm,
+    /// Only one Z node per cycle, at some offset: a single congruence per ghost (CRT).
+    SingleZ,
+    /// Several Z nodes per cycle (or some before the cycle starts): each combination of Z
+    /// steps is a candidate, or it needs a simulation.
+    MultipleZ,
+    /// No Z node within the cycle: the ghost stops hitting Z nodes at some point.
+    NoZ,
+}
+
+impl Display for Assumption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Assumption::Lcm => "single Z after each full cycle (lcm)",
+            Assumption::SingleZ => "single Z per cycle, with offset (CRT)",
+            Assumption::MultipleZ => "multiple Z per cycle (CRT combinations / simulation)",
+            Assumption::NoZ => "no Z in the cycle",
+        };
+        write!(f, "{0}", text)
+    }
+}
+
+/// The walk of a ghost from its start node. Its state is the node and the index of the
+/// next instruction: as soon as a state repeats, the walk repeats, too.
+#[derive(Debug)]
+struct GhostPath {
+    start: String,
+    cycle: Cycle,
+    // on_z[i]: the ghost is on a Z node after i steps, for all steps before the cycle repeats
+    on_z: Vec<bool>,
+}
+
+impl GhostPath {
+    fn is_on_z(&self, step: u64) -> bool {
+        self.on_z[self.cycle.reduce(step) as usize]
+    }
+
+    fn period(&self) -> u64 {
+        self.cycle.period as u64
+    }
+
+    /// The steps within the first cycle that hit a Z node: from then on,
+    /// the ghost hits a Z node at each step ≡ hit (mod period).
+    fn cycle_hits(&self) -> Vec<u64> {
+        (self.cycle.prefix_len..self.on_z.len())
+            .filter(|i| self.on_z[*i])
+            .map(|i| i as u64)
+            .collect()
+    }
+
+    fn assumption(&self) -> Assumption {
+        let prefix_hits = self.on_z[..self.cycle.prefix_len].iter().any(|z| *z);
+        match self.cycle_hits()[..] {
+            [] => Assumption::NoZ,
+            [hit] if !prefix_hits && hit == self.period() => Assumption::Lcm,
+            [_] if !prefix_hits => Assumption::SingleZ,
+            _ => Assumption::MultipleZ,
+        }
+    }
+}
+
+impl Display for GhostPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{0}: enters its cycle of {1} steps after {2} steps, Z at {3:?}: {4}",
+            self.start,
+            self.period(),
+            self.cycle.prefix_len,
+            self.cycle_hits(),
+            self.assumption()
+        )
+    }
+}
+
+/// Up to this number of Z step combinations, each one is solved with the CRT.
+const MAX_COMBINATIONS: usize = 10000;
+
 #[derive(Debug)]
 pub struct Day08 {
-    input: Vec<String>,
+    input: Input,
     dirs: String,
     nodes: HashMap<String, Node>,
 }
@@ -21,35 +107,182 @@ pub struct Day08 {
 impl Day08 {
     pub fn new() -> Day08 {
         Day08 {
-            input: Vec::new(),
+            input: Input::new(),
             dirs: String::new(),
             nodes: HashMap::new(),
         }
     }
 
-    fn parse_input(&mut self) {
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+
         // 1st line: dirs
-        self.dirs = self.input[0].clone();
-
-        // 3rd.. lines: nodes
-        let matcher = Regex::new(r"(\w+)\s+=\s+\((\w+),\s+(\w+)\)").unwrap();
-        for i in 2..self.input.len() {
-            let line = self.input.get(i).unwrap();
-            if let Some(group) = matcher.captures(line) {
-                let name = group.get(1).unwrap().as_str().to_string();
-                let left = group.get(2).unwrap().as_str().to_string();
-                let right = group.get(3).unwrap().as_str().to_string();
+        if let Some(dirs) = errors.check(parse_dirs(&mut self.input.line(0))) {
+            self.dirs = dirs.to_string();
+        }
+
+        // 3rd.. lines: nodes, like 'AAA = (BBB, CCC)'.
+        // All targets are remembered with their position, to check that they exist.
+        let mut targets: Vec<(LineParser, usize, &str)> = Vec::new();
+        for mut line in self.input.non_empty_lines().skip(1) {
+            if let Some((name, left, right)) = errors.check(parse_node(&mut line)) {
                 self.nodes.insert(
-                    name.clone(),
+                    name.to_string(),
                     Node {
-                        name: name.clone(),
-                        left,
-                        right,
+                        left: left.1.to_string(),
+                        right: right.1.to_string(),
                     },
                 );
+                targets.push((line.clone(), left.0, left.1));
+                targets.push((line, right.0, right.1));
+            }
+        }
+        for (line, pos, target) in targets {
+            if !self.nodes.contains_key(target) {
+                errors.push(line.error_at(pos, "the name of a defined node"));
+            }
+        }
+        let end_of_input = self.input.line(self.input.raw_lines().len());
+        for (name, expected) in [
+            ("AAA", "a start node 'AAA'"),
+            ("ZZZ", "a target node 'ZZZ'"),
+        ] {
+            if !self.nodes.contains_key(name) {
+                errors.push(end_of_input.error(expected));
+            }
+        }
+        errors.into_result()
+    }
+
+    /// Walks from the start node until a (node, instruction index) state repeats.
+    /// Returns all states up to then, and the cycle.
+    fn walk<'a>(&'a self, start: &'a str) -> (Vec<(&'a str, usize)>, Cycle) {
+        let dirs: Vec<char> = self.dirs.chars().collect();
+        let step = |(node, dir_index): &(&'a str, usize)| {
+            let node = &self.nodes[*node];
+            let next = match dirs[*dir_index] {
+                'L' => node.left.as_str(),
+                _ => node.right.as_str(),
+            };
+            (next, (dir_index + 1) % dirs.len())
+        };
+        cycles::find_cycle_with_states((start, 0), step)
+    }
+
+    fn ghost_path(&self, start: &str) -> GhostPath {
+        let (states, cycle) = self.walk(start);
+        GhostPath {
+            start: start.to_string(),
+            cycle,
+            on_z: states.iter().map(|(node, _)| node.ends_with('Z')).collect(),
+        }
+    }
+
+    /// The walks of all ghosts, one for each 'xxA' start node, sorted by start node
+    fn ghost_paths(&self) -> Vec<GhostPath> {
+        let mut start_nodes: Vec<&String> = self
+            .nodes
+            .keys()
+            .filter(|name| name.ends_with('A'))
+            .collect();
+        start_nodes.sort();
+        start_nodes
+            .iter()
+            .map(|start| self.ghost_path(start))
+            .collect()
+    }
+}
+
+/// The first step at which all ghosts are on a Z node, if there is one.
+/// Before all ghosts are within their cycles, the steps are simulated. After that, each ghost
+/// is on a Z node at the steps ≡ hit (mod period), for one of its hits: the congruences of all
+/// ghosts are combined with the (generalised) Chinese Remainder Theorem, for each combination
+/// of hits. If there are too many combinations, or the combined period gets too large,
+/// the candidate steps of the ghost with the longest period are simulated.
+fn first_common_z(paths: &[GhostPath]) -> Option<u64> {
+    let prefix = paths
+        .iter()
+        .map(|path| path.cycle.prefix_len as u64)
+        .max()?;
+    if let Some(step) = (0..prefix).find(|step| paths.iter().all(|p| p.is_on_z(*step))) {
+        return Some(step);
+    }
+
+    let combinations = paths
+        .iter()
+        .map(|path| path.cycle_hits().len())
+        .try_fold(1_usize, |count, hits| count.checked_mul(hits));
+    if combinations.is_some_and(|count| count <= MAX_COMBINATIONS) {
+        if let Ok(step) = first_common_z_by_congruences(paths, prefix) {
+            return step;
+        }
+    }
+
+    // all ghosts repeat after the lcm of the periods, so that is the search limit:
+    let longest = paths.iter().max_by_key(|path| path.period())?;
+    let limit = paths
+        .iter()
+        .try_fold(1_u64, |l, path| {
+            l.checked_mul(path.period() / num::integer::gcd(l, path.period()))
+        })
+        .unwrap_or(u64::MAX);
+    let hits = longest.cycle_hits();
+    (0..)
+        .map(|k| k * longest.period())
+        .take_while(|offset| *offset <= limit)
+        .flat_map(|offset| hits.iter().map(move |hit| hit + offset))
+        .filter(|step| *step >= prefix)
+        .find(|step| paths.iter().all(|p| p.is_on_z(*step)))
+}
+
+/// The CRT part of first_common_z(): combines the congruences (step mod lcm of the periods
+/// so far) of all combinations of hits, and returns the first step >= prefix.
+/// Fails if the combined period does not fit in a u64.
+fn first_common_z_by_congruences(
+    paths: &[GhostPath],
+    prefix: u64,
+) -> Result<Option<u64>, ModulusOverflow> {
+    let mut congruences: Vec<(u64, u64)> = vec![(0, 1)];
+    for path in paths {
+        let mut combined: Vec<(u64, u64)> = Vec::new();
+        for congruence in congruences.iter() {
+            for hit in path.cycle_hits() {
+                let hit = (hit % path.period(), path.period());
+                if let Some(c) = cycles::combine_congruences(*congruence, hit)? {
+                    combined.push(c);
+                }
             }
         }
+        congruences = combined;
+    }
+    // the first step >= prefix with step ≡ a (mod m), if it fits in a u64:
+    let first_after_prefix = |(a, m): (u64, u64)| match a >= prefix {
+        true => Some(a),
+        false => (prefix - a).div_ceil(m).checked_mul(m)?.checked_add(a),
+    };
+    Ok(congruences.into_iter().filter_map(first_after_prefix).min())
+}
+
+/// The first line: a sequence of 'L' or 'R' directions
+fn parse_dirs<'a>(line: &mut LineParser<'a>) -> ParseResult<&'a str> {
+    line.skip_whitespace();
+    let start = line.mark();
+    let dirs = line.word()?;
+    if let Some(pos) = dirs.find(|c| c != 'L' && c != 'R') {
+        return Err(line.error_at(start + pos, "only 'L' or 'R' directions"));
     }
+    line.end()?;
+    Ok(dirs)
+}
+
+type Target<'a> = (usize, &'a str);
+
+/// A node line, like 'AAA = (BBB, CCC)': returns the name, and the left and right
+/// target names with their position in the line.
+fn parse_node<'a>(line: &mut LineParser<'a>) -> ParseResult<(&'a str, Target<'a>, Target<'a>)> {
+    let (name, targets) = line.name_tuple(2)?;
+    line.end()?;
+    Ok((name, targets[0], targets[1]))
 }
 
 impl Day for Day08 {
@@ -61,68 +294,288 @@ impl Day for Day08 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day08.txt");
-        // let input = read_lines("data/day08-test.txt");
-        // let input = read_lines("data/day08-test2.txt");
-        // Test Input for part 2:
-        // let input = read_lines("data/day08-test1-2.txt");
+        let input = Input::read("data/day08.txt");
+        // let input = Input::read("data/day08-test.txt");
+        // let input = Input::read("data/day08-test2.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
+    /// The walk from AAA repeats as soon as a (node, instruction index) state repeats:
+    /// if ZZZ is not reached until then, it is never reached.
     fn solve1(&mut self) -> String {
-        let mut solution: u64 = 0;
-        let mut dir_index = 0;
-
-        let mut act_node = self.nodes.get("AAA").unwrap();
-        while act_node.name != "ZZZ" {
-            let dir = self.dirs.chars().nth(dir_index).unwrap();
-            match dir {
-                'R' => act_node = self.nodes.get(&act_node.right).unwrap(),
-                'L' => act_node = self.nodes.get(&act_node.left).unwrap(),
-                _ => panic!("Unknown direction"),
-            }
-            dir_index = (dir_index + 1) % self.dirs.len();
-            solution += 1;
+        let (states, _) = self.walk("AAA");
+        match states.iter().position(|(node, _)| *node == "ZZZ") {
+            Some(steps) => steps.to_string(),
+            None => String::from("never"),
         }
-
-        String::from(format!("{0}", solution))
     }
 
     fn solve2(&mut self) -> String {
-        // Idea:
-        // Calc each 'xxA' node's path length separately,
-        // then find the smallest common multiplier
-        let mut start_nodes: Vec<String> = Vec::new();
-        let mut path_lengths: Vec<u64> = Vec::new();
-
-        // find all 'xxA' start nodes:
-        for name in self.nodes.keys() {
-            if name.chars().last().unwrap() == 'A' {
-                start_nodes.push(name.clone());
-            }
+        // Analyse each 'xxA' ghost's walk separately, then combine them:
+        let paths = self.ghost_paths();
+        let weakest = paths.iter().map(|path| path.assumption()).max();
+        match weakest {
+            // each ghost hits Z after exactly n periods:
+            Some(Assumption::Lcm) => paths
+                .iter()
+                .map(|path| path.period())
+                .fold(1, lcm)
+                .to_string(),
+            _ => match first_common_z(&paths) {
+                Some(step) => step.to_string(),
+                None => String::from("never"),
+            },
         }
+    }
+}
 
-        for node_name in start_nodes {
-            let mut act_node = self.nodes.get(&node_name).unwrap();
-            let mut path_length = 0;
-            let mut dir_index = 0;
-            while act_node.name.chars().last().unwrap() != 'Z' {
-                let dir = self.dirs.chars().nth(dir_index).unwrap();
-                match dir {
-                    'R' => act_node = self.nodes.get(&act_node.right).unwrap(),
-                    'L' => act_node = self.nodes.get(&act_node.left).unwrap(),
-                    _ => panic!("Unknown direction"),
-                }
-                dir_index = (dir_index + 1) % self.dirs.len();
-                path_length += 1;
-            }
-            path_lengths.push(path_length);
+/// The `ghosts <file>` command: prints how each ghost walks through the network:
+/// when it enters its cycle, the cycle length, its Z steps, and which assumption
+/// about them it satisfies (the weakest one decides how part 2 is solved).
+pub fn run(args: &[String]) -> Result<(), String> {
+    let file = match args {
+        [file] => file,
+        _ => return Err(String::from("usage: ghosts <file>")),
+    };
+    if !Path::new(file).is_file() {
+        return Err(format!("cannot read {0}", file));
+    }
+    let mut day = Day08::new();
+    day.parse(Input::read(file)).map_err(|e| e.to_string())?;
+    for path in day.ghost_paths() {
+        println!("{0}", path);
+    }
+    Ok(())
+}
+
+/// A new random node name, ending with the given char (or a random one, but not 'A' or 'Z')
+fn random_node_name(rng: &mut StdRng, last: Option<char>, used: &mut HashSet<String>) -> String {
+    loop {
+        let last = last.unwrap_or(char::from(rng.gen_range(b'B'..=b'Y')));
+        let name: String = [
+            char::from(rng.gen_range(b'A'..=b'Z')),
+            char::from(rng.gen_range(b'A'..=b'Z')),
+            last,
+        ]
+        .iter()
+        .collect();
+        if used.insert(name.clone()) {
+            return name;
+        }
+    }
+}
+
+/// Random input with the structure of the real puzzle: each ghost start (xxA) leads through
+/// a chain of nodes to its end (xxZ), which continues like its start node. So the steps from
+/// start to end and from end to end are the same multiple of the number of instructions.
+/// AAA / ZZZ is the first ghost. Off-chain edges lead to junk nodes.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let dirs: Vec<char> = (0..size.max(2))
+        .map(|_| *['L', 'R'].choose(rng).unwrap())
+        .collect();
+    let mut used: HashSet<String> = HashSet::new();
+    used.insert(String::from("AAA"));
+    used.insert(String::from("ZZZ"));
+    let junk: Vec<String> = (0..3)
+        .map(|_| random_node_name(rng, None, &mut used))
+        .collect();
+    let mut nodes: Vec<String> = junk
+        .iter()
+        .map(|j| format!("{0} = ({0}, {0})", j))
+        .collect();
+
+    let mut primes = [3, 5, 7, 11, 13, 17, 19];
+    primes.shuffle(rng);
+    for (ghost, prime) in primes.iter().take(rng.gen_range(2..=4)).enumerate() {
+        let len = dirs.len() * prime;
+        let mut chain: Vec<String> = Vec::new();
+        for i in 0..=len {
+            chain.push(match (ghost, i) {
+                (0, 0) => String::from("AAA"),
+                (0, i) if i == len => String::from("ZZZ"),
+                (_, 0) => random_node_name(rng, Some('A'), &mut used),
+                (_, i) if i == len => random_node_name(rng, Some('Z'), &mut used),
+                _ => random_node_name(rng, None, &mut used),
+            });
+        }
+        for i in 0..=len {
+            // the end node continues like the start node:
+            let next = match i == len {
+                true => &chain[1],
+                false => &chain[i + 1],
+            };
+            let other = junk.choose(rng).unwrap();
+            nodes.push(match dirs[i % dirs.len()] {
+                'L' => format!("{0} = ({1}, {2})", chain[i], next, other),
+                _ => format!("{0} = ({1}, {2})", chain[i], other, next),
+            });
         }
-        let mut a = path_lengths[0];
-        for i in 1..path_lengths.len() {
-            a = lcm(a, path_lengths[i]);
+    }
+    nodes.shuffle(rng);
+
+    let mut lines = vec![dirs.into_iter().collect::<String>(), String::new()];
+    lines.append(&mut nodes);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    /// Parses a part 2 input, with the AAA and ZZZ nodes that every input needs:
+    /// the AAA ghost is on a Z node at each step from 1 on, so it does not change the result.
+    fn parse(lines: &[&str]) -> Day08 {
+        let mut day = Day08::new();
+        let mut lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        lines.extend([
+            String::from("AAA = (ZZZ, ZZZ)"),
+            String::from("ZZZ = (ZZZ, ZZZ)"),
+        ]);
+        day.parse(Input::from_lines("test.txt", lines)).unwrap();
+        day
+    }
+
+    /// The first common Z step, by walking all ghosts step by step
+    fn simulate(day: &Day08, max_steps: u64) -> Option<u64> {
+        let mut starts: Vec<&String> = day.nodes.keys().filter(|n| n.ends_with('A')).collect();
+        starts.sort();
+        let paths: Vec<GhostPath> = starts.iter().map(|s| day.ghost_path(s)).collect();
+        (0..max_steps).find(|step| paths.iter().all(|p| p.is_on_z(*step)))
+    }
+
+    #[test]
+    fn test_part2_example() {
+        let input = Input::read("data/day08-test1-2.txt");
+        let lines: Vec<&str> = input.raw_lines().iter().map(|l| l.as_str()).collect();
+        let mut day = parse(&lines);
+        assert_eq!(day.solve2(), "6");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let errors = |lines: &[&str]| -> Vec<String> {
+            let mut day = Day08::new();
+            let lines = lines.iter().map(|l| l.to_string()).collect();
+            let result = day.parse(Input::from_lines("test.txt", lines));
+            result
+                .unwrap_err()
+                .errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect()
+        };
+        assert_eq!(
+            errors(&["", "", "BBB = (BBB, BBB)"]),
+            vec![
+                "test.txt:1:1: expected a name, found end of line",
+                "test.txt:4:1: expected a start node 'AAA', found end of line",
+                "test.txt:4:1: expected a target node 'ZZZ', found end of line",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_zzz() {
+        let mut day = Day08::new();
+        let lines = [
+            "LR",
+            "",
+            "AAA = (BBB, AAA)",
+            "BBB = (AAA, BBB)",
+            "ZZZ = (ZZZ, AAA)",
+        ];
+        let lines = lines.iter().map(|l| l.to_string()).collect();
+        day.parse(Input::from_lines("test.txt", lines)).unwrap();
+        assert_eq!(day.solve1(), "never");
+    }
+
+    #[test]
+    fn test_z_with_offset() {
+        // 1A: Z at steps 2, 5, 8, ... (after a prefix of 1 step), 2A: Z at 3, 7, 11, ...
+        // The lcm of the first Z steps (6) is wrong:
+        let mut day = parse(&[
+            "L",
+            "",
+            "1A = (1B, 1B)",
+            "1B = (1Z, 1Z)",
+            "1Z = (1C, 1C)",
+            "1C = (1B, 1B)",
+            "2A = (2B, 2B)",
+            "2B = (2C, 2C)",
+            "2C = (2Z, 2Z)",
+            "2Z = (2A, 2A)",
+        ]);
+        let path = day.ghost_path("1A");
+        assert_eq!(
+            path.cycle,
+            Cycle {
+                prefix_len: 1,
+                period: 3
+            }
+        );
+        assert_eq!(path.cycle_hits(), vec![2]);
+        assert_eq!(path.assumption(), Assumption::SingleZ);
+        assert_eq!(day.ghost_path("2A").assumption(), Assumption::SingleZ);
+        assert_eq!(day.solve2(), "11");
+    }
+
+    #[test]
+    fn test_multiple_z_and_no_solution() {
+        // 1A: Z at 1, 2, 4, 5, ... (not 3, 6, ...), 2A: Z at 3, 7, 11, ...
+        let mut day = parse(&[
+            "L",
+            "",
+            "1A = (1Z, 1Z)",
+            "1Z = (3Z, 3Z)",
+            "3Z = (1A, 1A)",
+            "2A = (2B, 2B)",
+            "2B = (2C, 2C)",
+            "2C = (2Z, 2Z)",
+            "2Z = (2A, 2A)",
+        ]);
+        assert_eq!(day.ghost_path("1A").assumption(), Assumption::MultipleZ);
+        assert_eq!(simulate(&day, 1000), Some(7));
+        assert_eq!(day.solve2(), "7");
+
+        // no common step: ghost 1 is on Z at odd steps, ghost 2 at even ones
+        let mut day = parse(&[
+            "L",
+            "",
+            "1A = (1Z, 1Z)",
+            "1Z = (1A, 1A)",
+            "2A = (2B, 2B)",
+            "2B = (2Z, 2Z)",
+            "2Z = (2B, 2B)",
+        ]);
+        assert_eq!(day.solve2(), "never");
+    }
+
+    #[test]
+    fn test_same_as_simulation() {
+        // random graphs with 2 ghosts, all Z steps below the lcm of the periods are found:
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..200 {
+            let names = ["1A", "2A", "1Z", "2Z", "BB", "CC", "DD", "EZ"];
+            let mut lines = vec![String::from(if rng.gen_bool(0.5) { "LRL" } else { "RL" })];
+            lines.push(String::new());
+            for name in names {
+                let left = names.choose(&mut rng).unwrap();
+                let right = names.choose(&mut rng).unwrap();
+                lines.push(format!("{0} = ({1}, {2})", name, left, right));
+            }
+            let lines: Vec<&str> = lines.iter().map(|l| l.as_str()).collect();
+            let mut day = parse(&lines);
+            let expected = match simulate(&day, 10000) {
+                Some(step) => step.to_string(),
+                None => String::from("never"),
+            };
+            assert_eq!(day.solve2(), expected, "{:?}", lines);
         }
-        String::from(format!("{0}", a))
     }
 }
@@ -1,31 +1,101 @@
+use std::path::Path;
+
 use super::Day;
-use alex_lib::read_lines;
+use crate::generate;
+use crate::parser::{self, Input, ParseErrors};
+use num::{BigInt, One, Zero};
+use rand::{rngs::StdRng, Rng};
+
+/// The polynomial through the values of a sequence, in Newton's forward difference form:
+/// value(x) = Σ Δ^k(0) * binomial(x, k), where Δ^k(0) is the first value of the k-th difference
+/// row. The coefficients and the binomials are integers, so the values are exact for any x.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial {
+    // the first value of each difference row, up to the last non-zero row:
+    differences: Vec<BigInt>,
+    // the number of values it was fitted to:
+    len: usize,
+    reaches_zero_row: bool,
+}
+
+impl Polynomial {
+    /// Fits the polynomial to the values, at x = 0, 1, 2, ... . If the difference rows never
+    /// become all zeros, this is the interpolating polynomial of degree len - 1.
+    pub fn fit(values: &[i64]) -> Polynomial {
+        let mut differences: Vec<BigInt> = Vec::new();
+        let mut row: Vec<BigInt> = values.iter().map(|v| BigInt::from(*v)).collect();
+        let mut reaches_zero_row = false;
+        while let Some(first) = row.first() {
+            if row.iter().all(|v| v.is_zero()) {
+                reaches_zero_row = true;
+                break;
+            }
+            differences.push(first.clone());
+            row = row.windows(2).map(|pair| &pair[1] - &pair[0]).collect();
+        }
+        Polynomial {
+            differences,
+            len: values.len(),
+            reaches_zero_row,
+        }
+    }
+
+    /// The degree of the polynomial (0 for a constant sequence, also for the zero sequence)
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// false if the sequence ends before its difference rows become all zeros: then the
+    /// polynomial is not determined by the sequence, but just the one of the lowest degree.
+    pub fn reaches_zero_row(&self) -> bool {
+        self.reaches_zero_row
+    }
+
+    /// The value at x, which may lie outside the sequence, also before it (x < 0)
+    pub fn value_at(&self, x: i64) -> BigInt {
+        let x = BigInt::from(x);
+        let mut value = BigInt::zero();
+        // binomial(x, k), also for negative x: binomial(x, k + 1) = binomial(x, k) * (x - k) / (k + 1)
+        let mut binomial = BigInt::one();
+        for (k, difference) in self.differences.iter().enumerate() {
+            value += difference * &binomial;
+            binomial = binomial * (&x - k) / (k + 1);
+        }
+        value
+    }
+
+    /// The value `steps` steps after the last value of the sequence (`steps` > 0),
+    /// or before its first value (`steps` < 0)
+    pub fn extrapolate(&self, steps: i64) -> BigInt {
+        match steps >= 0 {
+            true => self.value_at(self.len as i64 - 1 + steps),
+            false => self.value_at(steps),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Day09 {
-    input: Vec<String>,
-    data: Vec<Vec<i64>>,
+    input: Input,
+    polynomials: Vec<Polynomial>,
 }
 
 impl Day09 {
     pub fn new() -> Day09 {
         Day09 {
-            input: Vec::new(),
-            data: Vec::new(),
+            input: Input::new(),
+            polynomials: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        for line in self.input.iter() {
-            let mut entries = Vec::new();
-            for part in line.split_ascii_whitespace() {
-                let nr = part.parse::<i64>().unwrap();
-                entries.push(nr);
-            }
-            if entries.len() > 0 {
-                self.data.push(entries);
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for mut line in self.input.non_empty_lines() {
+            if let Some(numbers) = errors.check(line.numbers::<i64>()) {
+                self.polynomials.push(Polynomial::fit(&numbers));
             }
         }
+        errors.into_result()
     }
 }
 
@@ -38,50 +108,110 @@ impl Day for Day09 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day09.txt");
-        // let input = read_lines("data/day09-test.txt");
+        let input = Input::read("data/day09.txt");
+        // let input = Input::read("data/day09-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
-        let mut solution: i64 = 0;
-        for data_line in &self.data {
-            solution += calc_next_nr(data_line);
-        }
+        let solution: BigInt = self.polynomials.iter().map(|p| p.extrapolate(1)).sum();
         String::from(format!("{0}", solution))
     }
 
     fn solve2(&mut self) -> String {
-        let mut solution: i64 = 0;
-        // same as 1, but just with reversed input:
-        for data_line in &self.data {
-            let rev: Vec<i64> = data_line.iter().rev().cloned().collect();
-            solution += calc_next_nr(&rev);
-        }
+        // same as 1, but one step before the first value:
+        let solution: BigInt = self.polynomials.iter().map(|p| p.extrapolate(-1)).sum();
         String::from(format!("{0}", solution))
     }
 }
 
-fn calc_next_nr(line: &Vec<i64>) -> i64 {
-    let (diff_line, only_zeros) = calc_diff_line(line);
-    let last = *line.last().unwrap();
-    if only_zeros {
-        return last;
-    } else {
-        return last + calc_next_nr(&diff_line);
+/// The `sequences <file>` command: prints the highest polynomial degree of the sequences,
+/// and how many of them end before their difference rows become all zeros.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let file = match args {
+        [file] => file,
+        _ => return Err(String::from("usage: sequences <file>")),
+    };
+    if !Path::new(file).is_file() {
+        return Err(format!("cannot read {0}", file));
     }
+    let mut day = Day09::new();
+    day.parse(Input::read(file)).map_err(|e| e.to_string())?;
+    let max_degree = day.polynomials.iter().map(|p| p.degree()).max();
+    let no_zero_row = day
+        .polynomials
+        .iter()
+        .filter(|p| !p.reaches_zero_row())
+        .count();
+    println!(
+        "Max. degree: {0:?}, sequences without zero row: {1}",
+        max_degree, no_zero_row
+    );
+    Ok(())
 }
 
-fn calc_diff_line(line: &Vec<i64>) -> (Vec<i64>, bool) {
-    let mut res = Vec::new();
-    let mut only_zeros = true;
-    for i in 1..line.len() {
-        let diff = line[i] - line[i - 1];
-        if diff != 0 {
-            only_zeros = false;
+/// Random input: `size` sequences of 21 values of a random polynomial of degree 0 to 4
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for _ in 0..size {
+        let coefficients: Vec<i64> = (0..rng.gen_range(1..=5))
+            .map(|_| rng.gen_range(-5..=5))
+            .collect();
+        let values: Vec<i64> = (0..21_i64)
+            .map(|x| coefficients.iter().rev().fold(0, |acc, c| acc * x + c))
+            .collect();
+        lines.push(generate::join(&values, " "));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extrapolate_many_steps() {
+        let p = |x: i64| x * x * x - 2 * x + 5;
+        let values: Vec<i64> = (0..10).map(p).collect();
+        let polynomial = Polynomial::fit(&values);
+        assert_eq!(polynomial.degree(), 3);
+        assert!(polynomial.reaches_zero_row());
+        for steps in -20..20 {
+            let expected = match steps >= 0 {
+                true => p(9 + steps),
+                false => p(steps),
+            };
+            assert_eq!(polynomial.extrapolate(steps), BigInt::from(expected));
         }
-        res.push(diff);
+        // far away, beyond i64:
+        let x = BigInt::from(10_i64.pow(12));
+        let expected = &x * &x * &x - 2 * &x + 5;
+        assert_eq!(polynomial.value_at(10_i64.pow(12)), expected);
+    }
+
+    #[test]
+    fn test_degrees() {
+        assert_eq!(Polynomial::fit(&[0, 0, 0]).degree(), 0);
+        assert_eq!(Polynomial::fit(&[7, 7, 7]).degree(), 0);
+        assert_eq!(Polynomial::fit(&[7, 7, 7]).extrapolate(-3), BigInt::from(7));
+        assert_eq!(Polynomial::fit(&[1, 3, 5]).degree(), 1);
+    }
+
+    #[test]
+    fn test_no_zero_row() {
+        // 1 2 4 8 16 / 1 2 4 8 / 1 2 4 / 1 2 / 1: the last row is not zero
+        let polynomial = Polynomial::fit(&[1, 2, 4, 8, 16]);
+        assert!(!polynomial.reaches_zero_row());
+        assert_eq!(polynomial.degree(), 4);
+        assert_eq!(polynomial.extrapolate(1), BigInt::from(31));
+        // a single value is a constant, but it does not prove it:
+        let polynomial = Polynomial::fit(&[5]);
+        assert!(!polynomial.reaches_zero_row());
+        assert_eq!(polynomial.extrapolate(2), BigInt::from(5));
     }
-    (res, only_zeros)
 }
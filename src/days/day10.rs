@@ -1,221 +1,297 @@
-use core::panic;
-use std::collections::HashSet;
+use std::{collections::HashSet, path::Path};
 
-use alex_lib::{types::{Coord2dMap, Coord2d, Direction}, read_lines};
+use alex_lib::types::{Coord2d, Direction};
 
 use super::Day;
+use crate::generate;
+use crate::grid::{Grid, Tile};
+use crate::parser::{self, Input, ParseErrors, ParseResult};
+use crate::polygon::Polygon;
+use rand::{rngs::StdRng, seq::SliceRandom};
 
-#[derive(Debug)]
-pub struct Day10 {
-    input: Vec<String>,
-    pipe_map: Coord2dMap<char>,
-    start: Option<Coord2d>,
-    circle_pipe: HashSet<Coord2d>,
+/// A single tile of the pipe maze. The pipes are named after the
+/// directions they connect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pipe {
+    Vertical,
+    Horizontal,
+    NorthEast,
+    NorthWest,
+    SouthWest,
+    SouthEast,
+    Ground,
 }
 
-impl Day10 {
-    pub fn new() -> Day10 {
-        Day10 {
-            input: Vec::new(),
-            pipe_map: Coord2dMap::new(),
-            start: None,
-            circle_pipe: HashSet::new(),
+impl Tile for Pipe {
+    const CHARS: &'static str = "|-LJ7F.";
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '|' => Some(Pipe::Vertical),
+            '-' => Some(Pipe::Horizontal),
+            'L' => Some(Pipe::NorthEast),
+            'J' => Some(Pipe::NorthWest),
+            '7' => Some(Pipe::SouthWest),
+            'F' => Some(Pipe::SouthEast),
+            '.' => Some(Pipe::Ground),
+            _ => None,
         }
     }
 
-    fn parse_input(&mut self) {
-        let lines: i64 = self.input.len().try_into().unwrap();
-        for (y, line) in self.input.iter().enumerate() {
-            let y: i64 = y.try_into().unwrap();
-            let line_len: i64 = line.len().try_into().unwrap();
-            if y == 0 {
-                // fill 4 corners with '.':
-                self.pipe_map.insert(Coord2d { x: -1, y: -1 }, '.');
-                self.pipe_map.insert(Coord2d { x: -1, y: lines }, '.');
-                self.pipe_map.insert(Coord2d { x: line_len, y: -1 }, '.');
-                self.pipe_map.insert(
-                    Coord2d {
-                        x: line_len,
-                        y: lines,
-                    },
-                    '.',
-                );
-            }
-            // insert a border on the left/right:
-            self.pipe_map.insert(Coord2d { x: -1, y }, '.');
-            self.pipe_map.insert(Coord2d { x: line_len, y }, '.');
-            for (x, chr) in line.chars().enumerate() {
-                let x: i64 = x.try_into().unwrap();
-                // insert a border on the top:
-                if y == 0 {
-                    self.pipe_map.insert(Coord2d { x, y: -1 }, '.');
-                }
-                // Fill one below (will be overridden with real content, if not the last line):
-                self.pipe_map.insert(Coord2d { x, y: y + 1 }, '.');
+    fn to_char(&self) -> char {
+        match self {
+            Pipe::Vertical => '|',
+            Pipe::Horizontal => '-',
+            Pipe::NorthEast => 'L',
+            Pipe::NorthWest => 'J',
+            Pipe::SouthWest => '7',
+            Pipe::SouthEast => 'F',
+            Pipe::Ground => '.',
+        }
+    }
+}
 
-                // fill in the real thing:
-                let pos = Coord2d { x, y };
-                if chr == 'S' {
-                    self.start = Some(pos);
-                }
-                self.pipe_map.insert(pos, chr);
+impl Pipe {
+    /// true if the pipe has an opening to the given direction
+    fn connects(&self, dir: Direction) -> bool {
+        match dir {
+            Direction::UP => matches!(self, Pipe::Vertical | Pipe::NorthEast | Pipe::NorthWest),
+            Direction::RIGHT => {
+                matches!(self, Pipe::Horizontal | Pipe::NorthEast | Pipe::SouthEast)
             }
+            Direction::DOWN => matches!(self, Pipe::Vertical | Pipe::SouthWest | Pipe::SouthEast),
+            Direction::LEFT => matches!(self, Pipe::Horizontal | Pipe::NorthWest | Pipe::SouthWest),
         }
-
-        self.circle_pipe.insert(self.start.unwrap());
-        self.insert_start_pipe(self.start.unwrap());
     }
 
-    fn insert_start_pipe(&mut self, start_pos: Coord2d) {
-        // Inserts the correct start pipe tile: The start now is a 'S',
-        // but it need to be replaced by the pipe fitting in the start position:
-        // we look at the surrounding pipes, and decide based on them.
-        let top = self.pipe_map.get(&start_pos.up()).unwrap();
-        let right = self.pipe_map.get(&start_pos.right()).unwrap();
-        let bottom = self.pipe_map.get(&start_pos.down()).unwrap();
-        let left = self.pipe_map.get(&start_pos.left()).unwrap();
-        let can_up = ['|', '7', 'F'].contains(top);
-        let can_right = ['-', '7', 'J'].contains(right);
-        let can_down = ['|', 'J', 'L'].contains(bottom);
-        let can_left = ['-', 'F', 'L'].contains(left);
-        if can_up && can_down {
-            self.pipe_map.insert(start_pos, '|');
-        } else if can_up && can_left {
-            self.pipe_map.insert(start_pos, 'J');
-        } else if can_up && can_right {
-            self.pipe_map.insert(start_pos, 'L');
-        } else if can_right && can_left {
-            self.pipe_map.insert(start_pos, '-');
-        } else if can_right && can_down {
-            self.pipe_map.insert(start_pos, 'F');
-        } else if can_down && can_left {
-            self.pipe_map.insert(start_pos, '7');
-        } else {
-            panic!("Unknown start tile!");
+    /// The direction in which we leave the pipe, after entering it heading in the given
+    /// direction. None if the pipe has no opening on the side we enter it.
+    fn exit(&self, heading: Direction) -> Option<Direction> {
+        match (self, heading) {
+            (Pipe::Vertical, Direction::UP) => Some(Direction::UP),
+            (Pipe::Vertical, Direction::DOWN) => Some(Direction::DOWN),
+            (Pipe::Horizontal, Direction::RIGHT) => Some(Direction::RIGHT),
+            (Pipe::Horizontal, Direction::LEFT) => Some(Direction::LEFT),
+            (Pipe::NorthEast, Direction::DOWN) => Some(Direction::RIGHT),
+            (Pipe::NorthEast, Direction::LEFT) => Some(Direction::UP),
+            (Pipe::NorthWest, Direction::DOWN) => Some(Direction::LEFT),
+            (Pipe::NorthWest, Direction::RIGHT) => Some(Direction::UP),
+            (Pipe::SouthWest, Direction::UP) => Some(Direction::LEFT),
+            (Pipe::SouthWest, Direction::RIGHT) => Some(Direction::DOWN),
+            (Pipe::SouthEast, Direction::UP) => Some(Direction::RIGHT),
+            (Pipe::SouthEast, Direction::LEFT) => Some(Direction::DOWN),
+            _ => None,
         }
     }
 
-    fn get_left_of(&self, act_pos: Coord2d, act_dir: Direction) -> Coord2d {
-        match act_dir {
-            Direction::UP => act_pos.left(),
-            Direction::RIGHT => act_pos.up(),
-            Direction::DOWN => act_pos.right(),
-            Direction::LEFT => act_pos.down(),
+    /// The pipe with openings to both directions
+    fn connecting(a: Direction, b: Direction) -> Pipe {
+        [
+            Pipe::Vertical,
+            Pipe::Horizontal,
+            Pipe::NorthEast,
+            Pipe::NorthWest,
+            Pipe::SouthWest,
+            Pipe::SouthEast,
+        ]
+        .into_iter()
+        .find(|pipe| pipe.connects(a) && pipe.connects(b))
+        .unwrap_or(Pipe::Ground)
+    }
+
+    fn box_char(&self) -> char {
+        match self {
+            Pipe::Vertical => '│',
+            Pipe::Horizontal => '─',
+            Pipe::NorthEast => '└',
+            Pipe::NorthWest => '┘',
+            Pipe::SouthWest => '┐',
+            Pipe::SouthEast => '┌',
+            Pipe::Ground => ' ',
         }
     }
-    fn get_right_of(&self, act_pos: Coord2d, act_dir: Direction) -> Coord2d {
-        match act_dir {
-            Direction::UP => act_pos.right(),
-            Direction::RIGHT => act_pos.down(),
-            Direction::DOWN => act_pos.left(),
-            Direction::LEFT => act_pos.up(),
+}
+
+fn opposite(dir: Direction) -> Direction {
+    match dir {
+        Direction::UP => Direction::DOWN,
+        Direction::RIGHT => Direction::LEFT,
+        Direction::DOWN => Direction::UP,
+        Direction::LEFT => Direction::RIGHT,
+    }
+}
+
+fn step(pos: Coord2d, dir: Direction) -> Coord2d {
+    match dir {
+        Direction::UP => pos.up(),
+        Direction::RIGHT => pos.right(),
+        Direction::DOWN => pos.down(),
+        Direction::LEFT => pos.left(),
+    }
+}
+
+/// Follows the pipes, leaving the start in the given direction, until it gets back to the
+/// start. Returns the tiles in walking order (starting with the start tile), and the
+/// direction in which we arrive at the start again. None if the pipes do not lead back.
+fn walk_loop(
+    pipe_map: &Grid<Pipe>,
+    start: Coord2d,
+    first_dir: Direction,
+) -> Option<(Vec<Coord2d>, Direction)> {
+    let mut tiles = vec![start];
+    let mut pos = start;
+    let mut dir = first_dir;
+    loop {
+        pos = step(pos, dir);
+        if pos == start {
+            return Some((tiles, dir));
+        }
+        // each tile has only 2 openings, so a path that does not return to the start
+        // ends somewhere, it cannot run in circles. Just to be sure:
+        if tiles.len() > pipe_map.width() * pipe_map.height() {
+            return None;
         }
+        dir = pipe_map.get(&pos)?.exit(dir)?;
+        tiles.push(pos);
     }
+}
 
-    /// Returns the new direction after moving onto a tile from a
-    /// specific direction.
-    /// act_pos is the tile we're moved to (so where we are now),
-    /// act_dir is the direction in which we were heading while moving.
-    /// The function returns the direction in which we can move away from the tile.
-    fn get_new_dir(&self, act_pos: Coord2d, act_dir: Direction) -> Direction {
-        let act_pipe = *self.pipe_map.get(&act_pos).unwrap();
-        match act_pipe {
-            '|' => match act_dir {
-                Direction::UP => Direction::UP,
-                Direction::RIGHT => Direction::UP,
-                Direction::LEFT => Direction::UP,
-                Direction::DOWN => Direction::DOWN,
-            },
-            '-' => match act_dir {
-                Direction::UP => Direction::RIGHT,
-                Direction::RIGHT => Direction::RIGHT,
-                Direction::LEFT => Direction::LEFT,
-                Direction::DOWN => Direction::RIGHT,
-            },
-            'F' => match act_dir {
-                Direction::UP => Direction::RIGHT,
-                Direction::RIGHT => Direction::RIGHT,
-                Direction::LEFT => Direction::DOWN,
-                Direction::DOWN => Direction::DOWN,
-            },
-            'J' => match act_dir {
-                Direction::UP => Direction::UP,
-                Direction::RIGHT => Direction::UP,
-                Direction::LEFT => Direction::LEFT,
-                Direction::DOWN => Direction::LEFT,
-            },
-            '7' => match act_dir {
-                Direction::UP => Direction::LEFT,
-                Direction::RIGHT => Direction::DOWN,
-                Direction::LEFT => Direction::LEFT,
-                Direction::DOWN => Direction::DOWN,
-            },
-            'L' => match act_dir {
-                Direction::UP => Direction::UP,
-                Direction::RIGHT => Direction::RIGHT,
-                Direction::LEFT => Direction::UP,
-                Direction::DOWN => Direction::RIGHT,
-            },
-            _ => {
-                panic!("Cannot determine new direction at {0}", act_pos);
+/// Finds the loop through the start tile, whose pipe is unknown: each pair of directions
+/// is tried as the start pipe's openings, as a start may have more than 2 connecting
+/// neighbours. If several pairs form a loop, the longest loop wins.
+/// Returns the loop tiles in walking order, starting at the start tile, and the start pipe.
+fn find_loop(pipe_map: &Grid<Pipe>, start: Coord2d) -> Option<(Vec<Coord2d>, Pipe)> {
+    let mut best: Option<(Vec<Coord2d>, Pipe)> = None;
+    for first_dir in [Direction::UP, Direction::RIGHT, Direction::DOWN] {
+        if let Some((tiles, last_dir)) = walk_loop(pipe_map, start, first_dir) {
+            let start_pipe = Pipe::connecting(first_dir, opposite(last_dir));
+            let longer = match &best {
+                Some((best_tiles, _)) => tiles.len() > best_tiles.len(),
+                None => true,
+            };
+            if longer {
+                best = Some((tiles, start_pipe));
             }
         }
     }
+    best
+}
+
+#[derive(Debug)]
+pub struct Day10 {
+    input: Input,
+    pipe_map: Grid<Pipe>,
+    start: Option<Coord2d>,
+    // the main loop, in walking order, starting at the start tile:
+    pipe_loop: Vec<Coord2d>,
+    loop_tiles: HashSet<Coord2d>,
+}
 
-    /// This function is called after all flood-filling is done: all non-circle tiles are now
-    /// marked with either 'l' or 'r', depending on their position relative to the circle.
-    /// Because the tile a -1, -1 is always the outer tile, we can determine which of 'r' or 'l'
-    /// contains inner tile (just the opposite of the -1,-1 mark.)
-    /// It returns the number of tiles that are contained by the circle pipe.
-    fn count_inner_tiles(&self) -> usize {
-        let inner_marker = match self.pipe_map.get(&Coord2d { x: -1, y: -1 }).unwrap() {
-            'r' => 'l',
-            'l' => 'r',
-            _ => panic!("Cannot determine inner marker"),
+impl Day10 {
+    pub fn new() -> Day10 {
+        Day10 {
+            input: Input::new(),
+            pipe_map: Grid::from_rows(Vec::new()),
+            start: None,
+            pipe_loop: Vec::new(),
+            loop_tiles: HashSet::new(),
+        }
+    }
+
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        // The start is a pipe of unknown shape: it is replaced later, see insert_start_pipe()
+        let start_marker = [('S', Pipe::Ground)];
+        let grid = Grid::parse(self.input.non_empty_lines(), &start_marker, &mut errors);
+        let grid = match grid {
+            Some(grid) => grid,
+            None => return errors.into_result(),
+        };
+
+        let start_pos = match grid.marker('S') {
+            Some(pos) => pos,
+            // an empty input has no grid row to report the missing marker in:
+            None => return Err(self.input.line(0).error("a grid with a 'S' marker").into()),
         };
-        self.pipe_map
-            .iter()
-            .filter(|(_, tile)| **tile == inner_marker)
-            .count()
+        self.pipe_map = grid;
+        self.start = Some(start_pos);
+        self.insert_start_pipe(start_pos)?;
+        Ok(())
     }
 
-    fn fill_neighbour_areas(&mut self, act_pos: Coord2d, act_dir: Direction) {
-        // flood-fill left and right side of the coordinate. Here, left/right is relative to the
-        // actual heading direction:
-        let left_coord = self.get_left_of(act_pos, act_dir);
-        let right_coord = self.get_right_of(act_pos, act_dir);
-        self.fill_area(left_coord, 'l');
-        self.fill_area(right_coord, 'r');
+    /// Finds the loop through the start, and replaces the 'S' (ground) placeholder
+    /// by the pipe that closes the loop.
+    fn insert_start_pipe(&mut self, start_pos: Coord2d) -> ParseResult<()> {
+        let (pipe_loop, start_pipe) = match find_loop(&self.pipe_map, start_pos) {
+            Some(found) => found,
+            None => {
+                let line = self
+                    .input
+                    .lines()
+                    .find(|l| l.remaining().contains('S'))
+                    .unwrap();
+                let pos = line.remaining().find('S').unwrap();
+                return Err(line.char_error_at(pos, "a start on a pipe loop"));
+            }
+        };
+        self.pipe_map.set(&start_pos, start_pipe);
+        self.loop_tiles = pipe_loop.iter().copied().collect();
+        self.pipe_loop = pipe_loop;
+        Ok(())
     }
 
-    // Flood fill given area, starting at coord.
-    // Stop if:
-    // - the coord is outside the map
-    // - the coord contains a pipe from the circle
-    // - the coord is already filled
-    // else, recursively fill the area
-    fn fill_area(&mut self, coord: Coord2d, marker: char) {
-        // check if we're outside the map:
-        if let None = self.pipe_map.get(&coord) {
-            return;
-        }
-        // check if the actual coord is a circle pipe position:
-        if self.circle_pipe.contains(&coord) {
-            return;
+    /// Counts the tiles inside the loop, scanning each row from the left: each loop tile
+    /// with an opening to the north crosses the loop's boundary (a horizontal run of the
+    /// loop like 'L--7' crosses it once, 'L--J' does not).
+    fn count_inside_by_parity(&self) -> usize {
+        let mut count = 0;
+        for row in 0..self.pipe_map.height() {
+            let mut inside = false;
+            for x in 0..self.pipe_map.width() {
+                let coord = Coord2d {
+                    x: x as i64,
+                    y: row as i64,
+                };
+                if !self.loop_tiles.contains(&coord) {
+                    count += inside as usize;
+                } else if self.pipe_map.get(&coord).unwrap().connects(Direction::UP) {
+                    inside = !inside;
+                }
+            }
         }
-        // already processed?
-        let pipe = self.pipe_map.get(&coord).unwrap();
-        if ['r', 'l'].contains(pipe) {
-            return;
+        count
+    }
+
+    /// Counts the tiles inside the loop: the loop is a polygon, the inside tiles are its
+    /// interior lattice points (Shoelace + Pick's Theorem, see polygon.rs).
+    fn count_inside_by_area(&self) -> i64 {
+        Polygon::from_vertices(self.pipe_loop.clone()).interior_points()
+    }
+
+    /// The map with the loop drawn in box-drawing characters, the tiles inside
+    /// the loop as 'I', all other tiles as '.'.
+    pub fn render_loop(&self) -> String {
+        let mut out = String::new();
+        for row in 0..self.pipe_map.height() {
+            let mut inside = false;
+            for x in 0..self.pipe_map.width() {
+                let coord = Coord2d {
+                    x: x as i64,
+                    y: row as i64,
+                };
+                let pipe = self.pipe_map.get(&coord).unwrap();
+                if self.loop_tiles.contains(&coord) {
+                    out.push(pipe.box_char());
+                    inside ^= pipe.connects(Direction::UP);
+                } else if inside {
+                    out.push('I');
+                } else {
+                    out.push('.');
+                }
+            }
+            out.push('\n');
         }
-        // mark myself:
-        self.pipe_map.insert(coord, marker);
-
-        // fill my surroundings:
-        self.fill_area(coord.up(), marker);
-        self.fill_area(coord.down(), marker);
-        self.fill_area(coord.left(), marker);
-        self.fill_area(coord.right(), marker);
+        out
     }
 }
 
@@ -228,57 +304,24 @@ impl Day for Day10 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day10.txt");
-        // let input = read_lines("data/day10-test1.txt");
-        // let input = read_lines("data/day10-test2.txt");
-        // let input = read_lines("data/day10-test2-1.txt");
-        // let input = read_lines("data/day10-test2-2.txt");
-        // let input = read_lines("data/day10-test2-3.txt");
-        // let input = read_lines("data/day10-test2-4.txt");
-        self.input = input
-            .iter()
-            .map(|l| l.trim().to_string())
-            .filter(|l| !l.is_empty())
-            .collect();
-        self.parse_input();
+        let input = Input::read("data/day10.txt");
+        // let input = Input::read("data/day10-test1.txt");
+        // let input = Input::read("data/day10-test2.txt");
+        // let input = Input::read("data/day10-test2-1.txt");
+        // let input = Input::read("data/day10-test2-2.txt");
+        // let input = Input::read("data/day10-test2-3.txt");
+        // let input = Input::read("data/day10-test2-4.txt");
+        parser::exit_on_error(self.parse(input));
     }
 
-    fn solve1(&mut self) -> String {
-        let mut solution: usize = 0;
-        let start_coord = self.start.unwrap();
-
-        // find start direction
-        let mut act_dir = self.get_new_dir(start_coord, Direction::DOWN);
-
-        // println!("Start: {0}", self.start.unwrap());
-        // println!("{0}", self.pipe_map);
-
-        // walk the pipes!
-        // Note that we don't need to check if the next tile is valid: The data is always
-        // valid, so if we start from the given Start position, it's sure that we're on a circle path.
-        let mut act_pos = start_coord;
-        loop {
-            self.circle_pipe.insert(act_pos);
-            // do I need to turn, because I'm im a corner?
-            act_dir = self.get_new_dir(act_pos, act_dir);
-            // now, move forward
-            match act_dir {
-                Direction::UP => act_pos = act_pos.up(),
-                Direction::RIGHT => act_pos = act_pos.right(),
-                Direction::DOWN => act_pos = act_pos.down(),
-                Direction::LEFT => act_pos = act_pos.left(),
-            }
-            if act_pos == start_coord {
-                break;
-            }
-        }
-
-        solution = match self.circle_pipe.len() % 2 {
-            0 => self.circle_pipe.len() / 2,
-            1 => self.circle_pipe.len() / 2 + 1,
-            _ => panic!("Invalid solution: {0}", solution),
-        };
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
+    }
 
+    fn solve1(&mut self) -> String {
+        // the farthest tile is half way round the loop:
+        let solution = self.pipe_loop.len() / 2;
         String::from(format!("{0}", solution))
     }
 
@@ -288,46 +331,179 @@ impl Day for Day10 {
      * - markieren der Nachbar-Flächen als "links" oder "rechts" der Röhre
      * - dann Herausfinden der Aussenfläche: links oder rechts?
      * - somit gilt: alle "links"-Flächen sind innen, alle "rechts" flächen aussen (oder umgekehrt)
+     *
+     * Das hat funktioniert, ist aber aufwändig. Seit Tag 18 kennen wir einen einfacheren Weg:
+     * Die Röhre ist ein Polygon, die inneren Felder sind die Gitterpunkte innerhalb
+     * des Polygons (Shoelace + Pick's Theorem, siehe polygon.rs).
+     * Alternativ: Zeilenweise Paritäts-Zählung, siehe count_inside_by_parity().
      */
     fn solve2(&mut self) -> String {
-        let start_coord = self.start.unwrap();
-
-        // find start direction
-        let mut act_dir = self.get_new_dir(start_coord, Direction::DOWN);
-        // println!("Start: {0}, dir: {1:?}", start_coord, act_dir);
-
-        // Walk the pipes, fill left / right areas
-        let mut act_pos = start_coord;
-        loop {
-            // flood-fill left and right side of the pipe. Here, left/right is relative to the
-            // actual heading direction:
-            self.fill_neighbour_areas(act_pos, act_dir);
-
-            // do I need to turn, because I'm im a corner?
-            let next_dir = self.get_new_dir(act_pos, act_dir);
-            if next_dir != act_dir {
-                // flood-fill again after turn, left and right side of the pipe. Here, left/right is relative to the
-                // actual heading direction:
-                act_dir = next_dir;
-                self.fill_neighbour_areas(act_pos, act_dir);
-            }
+        let solution = self.count_inside_by_area();
+        debug_assert_eq!(solution, self.count_inside_by_parity() as i64);
+        String::from(format!("{0}", solution))
+    }
+}
 
-            // now, move forward
-            match act_dir {
-                Direction::UP => act_pos = act_pos.up(),
-                Direction::RIGHT => act_pos = act_pos.right(),
-                Direction::DOWN => act_pos = act_pos.down(),
-                Direction::LEFT => act_pos = act_pos.left(),
-            }
+/// The `pipes <file>` command: prints the loop of a pipe maze in box-drawing characters,
+/// with the tiles inside the loop marked as 'I'.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let file = match args {
+        [file] => file,
+        _ => return Err(String::from("usage: pipes <file>")),
+    };
+    if !Path::new(file).is_file() {
+        return Err(format!("cannot read {0}", file));
+    }
+    let mut day = Day10::new();
+    day.parse(Input::read(file)).map_err(|e| e.to_string())?;
+    print!("{0}", day.render_loop());
+    println!("{0} tiles inside the loop", day.count_inside_by_area());
+    Ok(())
+}
 
-            if act_pos == start_coord {
-                break;
+/// Random input: the outline of a random shape is the loop, all other tiles are random junk.
+/// The loop's tiles are the shape's corner points, so the maze has (`size` + 3)² tiles.
+/// 'S' is placed on a random loop tile, its neighbours off the loop are ground, so that
+/// the start pipe is unambiguous.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let size = size.max(2) as i64;
+    let shape = generate::random_shape(size, size, (size * size / 2) as usize, rng);
+    let (start, moves) = generate::outline(&shape);
+
+    // a border of one tile around the shape's corner points:
+    let tiles = size as usize + 3;
+    let junk = [
+        Pipe::Vertical,
+        Pipe::Horizontal,
+        Pipe::NorthEast,
+        Pipe::NorthWest,
+        Pipe::SouthWest,
+        Pipe::SouthEast,
+        Pipe::Ground,
+    ];
+    let rows: Vec<Vec<Pipe>> = (0..tiles)
+        .map(|_| (0..tiles).map(|_| *junk.choose(rng).unwrap()).collect())
+        .collect();
+    let mut grid = Grid::from_rows(rows);
+
+    // walk the outline, and place the pipe connecting the previous and next direction:
+    let steps: Vec<Direction> = moves
+        .iter()
+        .flat_map(|(dir, steps)| (0..*steps).map(move |_| *dir))
+        .collect();
+    let mut pos = Coord2d {
+        x: start.x + 1,
+        y: start.y + 1,
+    };
+    let mut circle: Vec<Coord2d> = Vec::new();
+    for (i, dir) in steps.iter().enumerate() {
+        let prev = steps[(i + steps.len() - 1) % steps.len()];
+        let pipe = match (prev, dir) {
+            (Direction::UP, Direction::UP) | (Direction::DOWN, Direction::DOWN) => Pipe::Vertical,
+            (Direction::LEFT, Direction::LEFT) | (Direction::RIGHT, Direction::RIGHT) => {
+                Pipe::Horizontal
+            }
+            (Direction::DOWN, Direction::RIGHT) | (Direction::LEFT, Direction::UP) => {
+                Pipe::NorthEast
             }
+            (Direction::DOWN, Direction::LEFT) | (Direction::RIGHT, Direction::UP) => {
+                Pipe::NorthWest
+            }
+            (Direction::UP, Direction::LEFT) | (Direction::RIGHT, Direction::DOWN) => {
+                Pipe::SouthWest
+            }
+            (Direction::UP, Direction::RIGHT) | (Direction::LEFT, Direction::DOWN) => {
+                Pipe::SouthEast
+            }
+            _ => unreachable!(),
+        };
+        grid.set(&pos, pipe);
+        circle.push(pos);
+        pos = match dir {
+            Direction::UP => pos.up(),
+            Direction::RIGHT => pos.right(),
+            Direction::DOWN => pos.down(),
+            Direction::LEFT => pos.left(),
+        };
+    }
+
+    let start = *circle.choose(rng).unwrap();
+    for neighbour in [start.up(), start.right(), start.down(), start.left()] {
+        if !circle.contains(&neighbour) {
+            grid.set(&neighbour, Pipe::Ground);
         }
-        // println!("Start: {0}", start_coord);
-        // println!("{0}", self.pipe_map);
+    }
+    let mut lines: Vec<String> = grid.to_string().lines().map(|l| l.to_string()).collect();
+    lines[start.y as usize].replace_range(start.x as usize..start.x as usize + 1, "S");
+    lines
+}
 
-        let solution: usize = self.count_inner_tiles();
-        String::from(format!("{0}", solution))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn parse(lines: &[&str]) -> Result<Day10, ParseErrors> {
+        let mut day = Day10::new();
+        let lines = lines.iter().map(|l| l.to_string()).collect();
+        day.parse(Input::from_lines("test.txt", lines))?;
+        Ok(day)
+    }
+
+    #[test]
+    fn test_inside_by_parity_and_area() {
+        let expected = [
+            ("data/day10-test1.txt", 1),
+            ("data/day10-test2.txt", 1),
+            ("data/day10-test2-1.txt", 4),
+            ("data/day10-test2-3.txt", 8),
+            ("data/day10-test2-4.txt", 10),
+        ];
+        for (file, inside) in expected {
+            let mut day = Day10::new();
+            day.parse(Input::read(file)).unwrap();
+            assert_eq!(day.count_inside_by_parity(), inside, "{0}", file);
+            assert_eq!(day.count_inside_by_area(), inside as i64, "{0}", file);
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_start() {
+        // the start connects up, right and down, but only up and right form the loop:
+        let mut day = parse(&[".....", "F-7..", "|.|..", "S-J-.", "|...."]).unwrap();
+        assert_eq!(
+            day.pipe_map.get(&Coord2d { x: 0, y: 3 }),
+            Some(Pipe::NorthEast)
+        );
+        assert_eq!(day.pipe_loop.len(), 8);
+        assert_eq!(day.pipe_loop[0], Coord2d { x: 0, y: 3 });
+        assert_eq!(day.solve1(), "4");
+        assert_eq!(day.solve2(), "1");
+    }
+
+    #[test]
+    fn test_start_without_loop() {
+        assert!(parse(&["S-7", "|.|", "L-."]).is_err());
+        assert!(parse(&["S"]).is_err());
+    }
+
+    #[test]
+    fn test_render_loop() {
+        let day = parse(&["-L|F7", "7S-7|", "L|7||", "-L-J|", "L|-JF"]).unwrap();
+        assert_eq!(day.render_loop(), ".....\n.┌─┐.\n.│I│.\n.└─┘.\n.....\n");
+    }
+
+    #[test]
+    fn test_generated_loops() {
+        for seed in 0..20 {
+            let rng = &mut StdRng::seed_from_u64(seed);
+            let mut day = Day10::new();
+            day.parse(Input::from_lines("generated", generate(6, rng)))
+                .unwrap();
+            assert_eq!(
+                day.count_inside_by_parity() as i64,
+                day.count_inside_by_area()
+            );
+        }
     }
 }
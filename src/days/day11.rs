@@ -1,7 +1,9 @@
 use std::collections::HashSet;
 
 use super::Day;
-use alex_lib::{read_lines, types::Coord2d};
+use crate::parser::{self, Input, ParseErrors};
+use alex_lib::types::Coord2d;
+use rand::{rngs::StdRng, Rng};
 
 /// A Galaxy is a set of unique galaxy coordinates,
 /// and the maximal x and y coordinates.
@@ -14,14 +16,14 @@ struct Galaxy {
 
 #[derive(Debug)]
 pub struct Day11 {
-    input: Vec<String>,
+    input: Input,
     initial_galaxy: Galaxy,
 }
 
 impl Day11 {
     pub fn new() -> Day11 {
         Day11 {
-            input: Vec::new(),
+            input: Input::new(),
             initial_galaxy: Galaxy {
                 max_x: 0,
                 max_y: 0,
@@ -30,13 +32,16 @@ impl Day11 {
         }
     }
 
-    fn parse_input(&mut self) {
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        let image = parser::parse_grid(self.input.non_empty_lines(), ".#", &mut errors);
+
         // 1st, fill galaxy as-is:
         // Only store galaxies, no empty entries.
         // As this is a sparse universe, we need a minimum amount of memory.
-        for (y, line) in self.input.iter().enumerate() {
+        for (y, line) in image.iter().enumerate() {
             let y = y as i64;
-            for (x, c) in line.chars().enumerate() {
+            for (x, c) in line.iter().copied().enumerate() {
                 let x = x as i64;
                 if c == '#' {
                     self.initial_galaxy.entries.insert(Coord2d { x, y });
@@ -47,6 +52,7 @@ impl Day11 {
         }
 
         // self._print_galaxy(&self.initial_galaxy);
+        errors.into_result()
     }
 
     fn _print_galaxy(&self, galaxy: &Galaxy) {
@@ -63,77 +69,49 @@ impl Day11 {
         println!();
     }
 
-    /// Insert n empty rows below the given y row, moving all
-    /// lower entries down by n.
-    fn insert_empty_rows(&self, galaxy: &mut Galaxy, y: i64, count: i64) {
-        let to_move: Vec<Coord2d> = galaxy
-            .entries
-            .iter()
-            .filter(|c| c.y >= y)
-            .map(|c| c.clone())
-            .collect();
-        for c in to_move {
-            galaxy.entries.remove(&c);
-            galaxy.entries.insert(Coord2d {
-                x: c.x,
-                y: c.y + count,
-            });
-            galaxy.max_y = galaxy.max_y.max(c.y + count);
-        }
+    /// The sum of the distances between all pairs of galaxies, after expanding each empty
+    /// row and column to `factor` rows / columns.
+    /// The 'minimum distance' is simply the Manhattan Distance :-), so the x and y
+    /// distances can be summed up separately.
+    fn expanded_dist_sum(&self, galaxy: &Galaxy, factor: i128) -> i128 {
+        let xs: Vec<i64> = galaxy.entries.iter().map(|c| c.x).collect();
+        let ys: Vec<i64> = galaxy.entries.iter().map(|c| c.y).collect();
+        let xs = expand(&xs, galaxy.max_x, factor);
+        let ys = expand(&ys, galaxy.max_y, factor);
+        pairwise_dist_sum(xs) + pairwise_dist_sum(ys)
     }
+}
 
-    /// Insert n empty cols right of the given x col, moving all
-    /// farther right entries by n.
-    fn insert_empty_cols(&mut self, galaxy: &mut Galaxy, x: i64, count: i64) {
-        let to_move: Vec<Coord2d> = galaxy
-            .entries
-            .iter()
-            .filter(|c| c.x >= x)
-            .map(|c| c.clone())
-            .collect();
-        for c in to_move {
-            galaxy.entries.remove(&c);
-            galaxy.entries.insert(Coord2d {
-                x: c.x + count,
-                y: c.y,
-            });
-            galaxy.max_x = galaxy.max_x.max(c.x + count);
-        }
+/// Maps the coordinates of one axis (0..=max) to the expanded universe: each empty
+/// line before a coordinate (one without any galaxy) moves it by factor - 1.
+/// The empty lines before each coordinate are counted once, as prefix sums.
+fn expand(coords: &[i64], max: i64, factor: i128) -> Vec<i128> {
+    let mut occupied = vec![false; max as usize + 1];
+    for c in coords {
+        occupied[*c as usize] = true;
     }
-
-    fn is_empty_row(&self, galaxy: &Galaxy, y: i64) -> bool {
-        for x in 0..=galaxy.max_x {
-            if galaxy.entries.contains(&Coord2d { x, y }) {
-                return false;
-            }
-        }
-        true
+    // empty_before[i]: number of empty lines in 0..i
+    let mut empty_before: Vec<i128> = vec![0; occupied.len() + 1];
+    for (i, occupied) in occupied.iter().enumerate() {
+        empty_before[i + 1] = empty_before[i] + !occupied as i128;
     }
-    fn is_empty_col(&self, galaxy: &Galaxy, x: i64) -> bool {
-        for y in 0..=galaxy.max_y {
-            if galaxy.entries.contains(&Coord2d { x, y }) {
-                return false;
-            }
-        }
-        true
-    }
-
-    /// Calculates the minimum distance between all pairs of galaxies.
-    /// The 'minimum distance' is simply the Manhattan Distance :-)
-    fn calc_dist_sum(&self, galaxy: &Galaxy) -> i64 {
-        let coords: Vec<Coord2d> = galaxy.entries.iter().map(|c| c.clone()).collect();
+    coords
+        .iter()
+        .map(|c| *c as i128 + empty_before[*c as usize] * (factor - 1))
+        .collect()
+}
 
-        let mut sum = 0;
-        for i1 in 0..coords.len() - 1 {
-            for i2 in i1 + 1..coords.len() {
-                let c1 = coords[i1];
-                let c2 = coords[i2];
-                let dist = c1.manhattan_dist(&c2);
-                sum += dist;
-            }
-        }
-        sum
+/// Sums |a - b| over all pairs of the values: after sorting, the i-th value is larger than
+/// the i values before it, so it adds i * value - (sum of the values before it).
+fn pairwise_dist_sum(mut values: Vec<i128>) -> i128 {
+    values.sort_unstable();
+    let mut sum = 0;
+    let mut prefix_sum = 0;
+    for (i, value) in values.iter().enumerate() {
+        sum += i as i128 * value - prefix_sum;
+        prefix_sum += value;
     }
+    sum
 }
 
 impl Day for Day11 {
@@ -145,61 +123,114 @@ impl Day for Day11 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day11.txt");
-        // let input = read_lines("data/day11-test.txt");
-        self.input = input
-            .iter()
-            .filter(|l| !l.is_empty())
-            .map(|l| l.to_string())
-            .collect();
-        self.parse_input();
+        let input = Input::read("data/day11.txt");
+        // let input = Input::read("data/day11-test.txt");
+        parser::exit_on_error(self.parse(input));
     }
 
-    fn solve1(&mut self) -> String {
-        let mut galaxy = self.initial_galaxy.clone();
-
-        // double empty rows:
-        // work from bottom to top, insert an empty row below the actual row, if it's empty
-        for y in (0..=galaxy.max_y).rev() {
-            if self.is_empty_row(&self.initial_galaxy, y) {
-                self.insert_empty_rows(&mut galaxy, y + 1, 1);
-            }
-        }
-
-        // double empty cols:
-        // work from right to left, insert an empty row right of the actual row, if it's empty
-        for x in (0..=galaxy.max_x).rev() {
-            if self.is_empty_col(&self.initial_galaxy, x) {
-                self.insert_empty_cols(&mut galaxy, x + 1, 1);
-            }
-        }
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
+    }
 
-        let solution = self.calc_dist_sum(&galaxy);
+    fn solve1(&mut self) -> String {
+        // each empty row / col is doubled:
+        let solution = self.expanded_dist_sum(&self.initial_galaxy, 2);
         String::from(format!("{0}", solution))
     }
 
     fn solve2(&mut self) -> String {
-        let mut galaxy = self.initial_galaxy.clone();
+        let solution = self.expanded_dist_sum(&self.initial_galaxy, 1000000);
+        String::from(format!("{0}", solution))
+    }
+}
+
+/// Random input: a `size` x `size` image with sparse galaxies and some empty rows and columns
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let size = size.max(2);
+    let empty_rows: Vec<bool> = (0..size).map(|_| rng.gen_bool(0.2)).collect();
+    let empty_cols: Vec<bool> = (0..size).map(|_| rng.gen_bool(0.2)).collect();
+    let mut image: Vec<Vec<char>> = (0..size)
+        .map(|y| {
+            (0..size)
+                .map(
+                    |x| match !empty_rows[y] && !empty_cols[x] && rng.gen_bool(0.1) {
+                        true => '#',
+                        false => '.',
+                    },
+                )
+                .collect()
+        })
+        .collect();
+    // at least 2 galaxies, to have a pair:
+    image[0][0] = '#';
+    image[size - 1][size - 1] = '#';
+    image
+        .into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
 
-        let enlarge = 1000000 - 1;
+    fn parse(lines: Vec<String>) -> Day11 {
+        let mut day = Day11::new();
+        day.parse(Input::from_lines("test.txt", lines)).unwrap();
+        day
+    }
 
-        // enlarge empty rows:
-        // work from bottom to top, insert empty rows below the actual row, if it's empty
-        for y in (0..=galaxy.max_y).rev() {
-            if self.is_empty_row(&self.initial_galaxy, y) {
-                self.insert_empty_rows(&mut galaxy, y + 1, enlarge);
+    /// The sum over all pairs, on the expanded coordinates
+    fn brute_force_dist_sum(day: &Day11, factor: i128) -> i128 {
+        let galaxy = &day.initial_galaxy;
+        let empty_rows: Vec<i64> = (0..=galaxy.max_y)
+            .filter(|y| galaxy.entries.iter().all(|c| c.y != *y))
+            .collect();
+        let empty_cols: Vec<i64> = (0..=galaxy.max_x)
+            .filter(|x| galaxy.entries.iter().all(|c| c.x != *x))
+            .collect();
+        let expanded: Vec<(i128, i128)> = galaxy
+            .entries
+            .iter()
+            .map(|c| {
+                let rows = empty_rows.iter().filter(|y| **y < c.y).count() as i128;
+                let cols = empty_cols.iter().filter(|x| **x < c.x).count() as i128;
+                (
+                    c.x as i128 + cols * (factor - 1),
+                    c.y as i128 + rows * (factor - 1),
+                )
+            })
+            .collect();
+        let mut sum = 0;
+        for (i, a) in expanded.iter().enumerate() {
+            for b in expanded.iter().skip(i + 1) {
+                sum += (a.0 - b.0).abs() + (a.1 - b.1).abs();
             }
         }
+        sum
+    }
+
+    #[test]
+    fn test_expansion_factors() {
+        let mut day = Day11::new();
+        day.parse(Input::read("data/day11-test.txt")).unwrap();
+        assert_eq!(day.expanded_dist_sum(&day.initial_galaxy, 10), 1030);
+        assert_eq!(day.expanded_dist_sum(&day.initial_galaxy, 100), 8410);
+    }
 
-        // enlarge empty cols:
-        // work from right to left, insert empty rows right of the actual row, if it's empty
-        for x in (0..=galaxy.max_x).rev() {
-            if self.is_empty_col(&self.initial_galaxy, x) {
-                self.insert_empty_cols(&mut galaxy, x + 1, enlarge);
+    #[test]
+    fn test_same_as_brute_force() {
+        let rng = &mut StdRng::seed_from_u64(11);
+        for size in 2..30 {
+            let day = parse(generate(size, rng));
+            for factor in [1, 2, 10, 1000000, 10_i128.pow(20)] {
+                assert_eq!(
+                    day.expanded_dist_sum(&day.initial_galaxy, factor),
+                    brute_force_dist_sum(&day, factor)
+                );
             }
         }
-
-        let solution = self.calc_dist_sum(&galaxy);
-        String::from(format!("{0}", solution))
     }
 }
@@ -1,14 +1,71 @@
 use super::Day;
-use alex_lib::read_lines;
+use crate::generate;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, Rng};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Entry {
     springs: String,
     groups: Vec<u64>,
 }
+
+impl Entry {
+    /// The entry repeated `times` times: the springs joined by '?', the groups just repeated
+    fn unfold(&self, times: usize) -> Entry {
+        Entry {
+            springs: vec![self.springs.as_str(); times].join("?"),
+            groups: self.groups.repeat(times),
+        }
+    }
+
+    fn arrangements(&self) -> u128 {
+        count_arrangements(&self.springs, &self.groups)
+    }
+}
+
+/// Counts the arrangements of damaged springs ('#') that fit both the known springs
+/// ('.' or '#', '?' is unknown) and the groups of contiguous damaged springs.
+/// Dynamic programming over (position, group index), backwards from the end of the row:
+/// ways[i][g] is the number of arrangements of the groups g.. in the springs i...
+/// At each position, either the spring is operational (continue at i + 1), or the next group
+/// starts here: then its springs must not be operational, and it must be followed by
+/// an operational spring (or the end of the row).
+pub fn count_arrangements(springs: &str, groups: &[u64]) -> u128 {
+    let springs = springs.as_bytes();
+    let len = springs.len();
+    // operational_before[i]: number of '.' springs in 0..i, to check a group's springs at once
+    let mut operational_before = vec![0; len + 1];
+    for (i, spring) in springs.iter().enumerate() {
+        operational_before[i + 1] = operational_before[i] + (*spring == b'.') as usize;
+    }
+
+    let mut ways = vec![vec![0_u128; groups.len() + 1]; len + 2];
+    ways[len][groups.len()] = 1;
+    ways[len + 1][groups.len()] = 1;
+    for i in (0..len).rev() {
+        for g in 0..=groups.len() {
+            let mut count = 0;
+            if springs[i] != b'#' {
+                count += ways[i + 1][g];
+            }
+            if springs[i] != b'.' && g < groups.len() {
+                let end = i.saturating_add(groups[g] as usize);
+                let fits = end <= len
+                    && operational_before[end] == operational_before[i]
+                    && (end == len || springs[end] != b'#');
+                if fits {
+                    // skip the group, and the operational spring after it:
+                    count += ways[end + 1][g + 1];
+                }
+            }
+            ways[i][g] = count;
+        }
+    }
+    ways[0][0]
+}
 #[derive(Debug)]
 pub struct Day12 {
-    input: Vec<String>,
+    input: Input,
     data: Vec<Entry>,
 }
 
@@ -16,25 +73,28 @@ pub struct Day12 {
 /// my brute-force attempt (find all permutations) was working,
 /// but for Solution 2, this does not. So I need to implement a
 /// Nonogram solver!
+/// Update: no full solver needed, counting the arrangements of a single row
+/// with dynamic programming is fast enough, see count_arrangements().
 impl Day12 {
     pub fn new() -> Day12 {
         Day12 {
-            input: Vec::new(),
+            input: Input::new(),
             data: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        for line in &self.input {
-            let parts: Vec<&str> = line.split(' ').collect();
-            let springs = parts[0];
-            let groups = parts[1].split(',').map(|s| s.parse::<u64>().unwrap());
-            self.data.push(Entry {
-                springs: springs.to_string(),
-                groups: groups.collect(),
-            });
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for mut line in self.input.non_empty_lines() {
+            if let Some(entry) = errors.check(parse_entry(&mut line)) {
+                self.data.push(entry);
+            }
         }
+        errors.into_result()
     }
+
+    // The brute force of part 1, used as reference in the tests:
+    #[cfg(test)]
     fn permute_line(&self, line: &str) -> Vec<String> {
         let mut permutations: Vec<String> = Vec::new();
         let first_unknown = line.chars().position(|c| c == '?');
@@ -63,6 +123,7 @@ impl Day12 {
         permutations
     }
 
+    #[cfg(test)]
     fn count_group_match(&self, springs: &Vec<String>, groups: &Vec<u64>) -> u64 {
         let mut count: u64 = 0;
         for spring in springs.iter() {
@@ -86,6 +147,17 @@ impl Day12 {
     }
 }
 
+/// Parses lines like '???.### 1,1,3'
+fn parse_entry(line: &mut LineParser) -> ParseResult<Entry> {
+    let springs = line.chars_of(".#?")?;
+    let groups = line.separated(",", |group| group.number::<u64>())?;
+    line.end()?;
+    Ok(Entry {
+        springs: springs.to_string(),
+        groups,
+    })
+}
+
 impl Day for Day12 {
     fn day_nr(&self) -> String {
         String::from("12")
@@ -95,31 +167,102 @@ impl Day for Day12 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day12.txt");
-        // let input = read_lines("data/day12-test.txt");
-        self.input = input
-            .iter()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-        self.parse_input();
+        let input = Input::read("data/day12.txt");
+        // let input = Input::read("data/day12-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
-        let mut solution: u64 = 0;
-        // println!("{:?}", self.data);
-
-        for entry in &self.data {
-            let permutations = self.permute_line(&entry.springs);
-            // println!("perms  : {:?}", permutations);
-            let match_count = self.count_group_match(&permutations, &entry.groups);
-            solution += match_count;
-        }
+        let solution: u128 = self.data.iter().map(|entry| entry.arrangements()).sum();
         String::from(format!("{0}", solution))
     }
 
     fn solve2(&mut self) -> String {
-        let mut solution: u64 = 0;
-        String::from(format!("--- No solutiono for now ---"))
+        let solution: u128 = self
+            .data
+            .iter()
+            .map(|entry| entry.unfold(5).arrangements())
+            .sum();
+        String::from(format!("{0}", solution))
+    }
+}
+
+/// Random input: `size` rows of random springs, where about half of them are unknown.
+/// The groups are taken from the springs before masking, so each row has a solution.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    while lines.len() < size {
+        let springs: Vec<bool> = (0..rng.gen_range(4..=12))
+            .map(|_| rng.gen_bool(0.5))
+            .collect();
+        let groups: Vec<usize> = springs
+            .split(|damaged| !damaged)
+            .map(|group| group.len())
+            .filter(|len| *len > 0)
+            .collect();
+        if groups.is_empty() {
+            continue;
+        }
+        let row: String = springs
+            .iter()
+            .map(|damaged| match (rng.gen_bool(0.5), damaged) {
+                (true, _) => '?',
+                (false, true) => '#',
+                (false, false) => '.',
+            })
+            .collect();
+        lines.push(format!("{0} {1}", row, generate::join(&groups, ",")));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difftest;
+
+    #[test]
+    fn test_count_arrangements() {
+        assert_eq!(count_arrangements("???.###", &[1, 1, 3]), 1);
+        assert_eq!(count_arrangements("?###????????", &[3, 2, 1]), 10);
+        assert_eq!(count_arrangements("", &[]), 1);
+        assert_eq!(count_arrangements("#", &[]), 0);
+        assert_eq!(count_arrangements("??", &[3]), 0);
+        // 100 unknown springs, 10 groups of 1: binomial(91, 10)
+        let springs = "?".repeat(100);
+        assert_eq!(count_arrangements(&springs, &[1; 10]), 6426898010533);
+    }
+
+    #[test]
+    fn test_same_as_brute_force() {
+        let parse = |input: &[String]| -> Option<Day12> {
+            let mut day = Day12::new();
+            day.parse(Input::from_lines("generated", input.to_vec()))
+                .ok()?;
+            Some(day)
+        };
+        let count = |day: &Day12, entry: &Entry| -> u128 {
+            let permutations = day.permute_line(&entry.springs);
+            day.count_group_match(&permutations, &entry.groups) as u128
+        };
+        // part 1, and part 2 unfolded twice (the brute force cannot do 5 times):
+        let reference = |input: &[String]| {
+            let day = parse(input)?;
+            let part1: u128 = day.data.iter().map(|e| count(&day, e)).sum();
+            let unfolded: u128 = day.data.iter().map(|e| count(&day, &e.unfold(2))).sum();
+            Some((part1.to_string(), unfolded))
+        };
+        let optimised = |input: &[String]| {
+            let mut day = parse(input)?;
+            let unfolded: u128 = day.data.iter().map(|e| e.unfold(2).arrangements()).sum();
+            Some((day.solve1(), unfolded))
+        };
+        let generate = |rng: &mut StdRng| generate(rng.gen_range(1..=3), rng);
+        difftest::assert_agree(100, generate, reference, optimised);
     }
 }
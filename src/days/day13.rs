@@ -1,10 +1,10 @@
 use super::Day;
-use alex_lib::read_lines;
-use itertools::Itertools;
+use crate::parser::{self, Input, ParseErrors};
+use rand::{rngs::StdRng, Rng};
 
 #[derive(Debug)]
 pub struct Day13 {
-    input: Vec<String>,
+    input: Input,
     fields: Vec<Vec<Vec<char>>>,
     field_results_part1: Vec<(i64, i64)>,
 }
@@ -12,27 +12,19 @@ pub struct Day13 {
 impl Day13 {
     pub fn new() -> Day13 {
         Day13 {
-            input: Vec::new(),
+            input: Input::new(),
             fields: Vec::new(),
             field_results_part1: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let mut act_field = Vec::new();
-        for line in &self.input {
-            if line.trim().is_empty() {
-                if act_field.len() > 0 {
-                    self.fields.push(act_field);
-                }
-                act_field = Vec::new();
-            } else {
-                act_field.push(line.chars().collect_vec());
-            }
-        }
-        if act_field.len() > 0 {
-            self.fields.push(act_field);
+    /// Each pattern is a rectangular block of '.' and '#', separated by empty lines.
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for section in self.input.sections() {
+            self.fields.push(section.grid(".#", &mut errors));
         }
+        errors.into_result()
     }
 
     fn find_mirror_lines(&self, field: &Vec<Vec<char>>, prev_res: (i64, i64)) -> (i64, i64) {
@@ -114,10 +106,14 @@ impl Day for Day13 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day13.txt");
-        // let input = read_lines("data/day13-test.txt");
+        let input = Input::read("data/day13.txt");
+        // let input = Input::read("data/day13-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -150,7 +146,8 @@ impl Day for Day13 {
                     new_field[line_idx][col_idx] = match val {
                         '.' => '#',
                         '#' => '.',
-                        _ => panic!("Unexpected value: {}", val),
+                        // the fields only contain '.' and '#', see parse_input():
+                        _ => unreachable!("Unexpected value: {}", val),
                     };
 
                     let prev_res = self.field_results_part1[field_idx];
@@ -183,3 +180,124 @@ impl Day for Day13 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Number of differing cells when mirroring the pattern between row `line - 1` and row `line`
+fn mirror_differences(pattern: &[Vec<bool>], line: usize) -> usize {
+    let pairs = line.min(pattern.len() - line);
+    (0..pairs)
+        .map(|i| {
+            let above = &pattern[line - 1 - i];
+            let below = &pattern[line + i];
+            above
+                .iter()
+                .zip(below.iter())
+                .filter(|(a, b)| a != b)
+                .count()
+        })
+        .sum()
+}
+
+fn transpose(pattern: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    (0..pattern[0].len())
+        .map(|x| pattern.iter().map(|row| row[x]).collect())
+        .collect()
+}
+
+/// Random input: `size` patterns. Each pattern has an exact horizontal mirror line (part 1),
+/// and a vertical mirror line that is off by a single smudge (part 2). Both lines are
+/// the only ones in the pattern. Half of the patterns are transposed.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut patterns = 0;
+    while patterns < size {
+        let (width, height) = (rng.gen_range(5..=size + 5), rng.gen_range(5..=size + 5));
+        let row_line = rng.gen_range(1..height);
+        let col_line = rng.gen_range(1..width);
+        // the smudge must be in a row that is not mirrored, to keep the horizontal mirror:
+        if row_line * 2 == height {
+            continue;
+        }
+        let mirror = |i: usize, line: usize, len: usize| match (2 * line).checked_sub(i + 1) {
+            Some(m) if m < len => Some(m),
+            _ => None,
+        };
+
+        // cells that mirror each other must be equal: fill the pattern from mirrored cells
+        let mut pattern: Vec<Vec<Option<bool>>> = vec![vec![None; width]; height];
+        for y in 0..height {
+            for x in 0..width {
+                if pattern[y][x].is_some() {
+                    continue;
+                }
+                let value = rng.gen_bool(0.5);
+                let mut todo = vec![(x, y)];
+                while let Some((x, y)) = todo.pop() {
+                    if pattern[y][x].is_some() {
+                        continue;
+                    }
+                    pattern[y][x] = Some(value);
+                    if let Some(my) = mirror(y, row_line, height) {
+                        todo.push((x, my));
+                    }
+                    if let Some(mx) = mirror(x, col_line, width) {
+                        todo.push((mx, y));
+                    }
+                }
+            }
+        }
+        let mut pattern: Vec<Vec<bool>> = pattern
+            .into_iter()
+            .map(|row| row.into_iter().map(|v| v.unwrap()).collect())
+            .collect();
+        let smudge_rows: Vec<usize> = (0..height)
+            .filter(|y| mirror(*y, row_line, height).is_none())
+            .collect();
+        let y = smudge_rows[rng.gen_range(0..smudge_rows.len())];
+        let x = rng.gen_range(col_line.saturating_sub(width - col_line)..col_line);
+        pattern[y][x] = !pattern[y][x];
+
+        // make sure there are no other (smudged) mirror lines:
+        let transposed = transpose(&pattern);
+        let mut differences: Vec<usize> = (1..height)
+            .map(|line| mirror_differences(&pattern, line))
+            .chain((1..width).map(|line| mirror_differences(&transposed, line)))
+            .collect();
+        differences.sort();
+        if differences[0] != 0 || differences[1] != 1 || differences[2] < 2 {
+            continue;
+        }
+
+        if rng.gen_bool(0.5) {
+            pattern = transposed;
+        }
+        if patterns > 0 {
+            lines.push(String::new());
+        }
+        patterns += 1;
+        for row in pattern {
+            lines.push(row.iter().map(|v| if *v { '#' } else { '.' }).collect());
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generated_patterns() {
+        let rng = &mut StdRng::seed_from_u64(13);
+        let mut day = Day13::new();
+        day.input = Input::from_lines("generated", generate(20, rng));
+        day.parse_input().unwrap();
+        assert_eq!(day.fields.len(), 20);
+        day.solve1();
+        day.solve2();
+        // each pattern has a single mirror line:
+        for (horiz_line, vert_line) in day.field_results_part1.iter() {
+            assert!((*horiz_line == -1) != (*vert_line == -1));
+        }
+    }
+}
@@ -1,109 +1,74 @@
-use std::collections::HashMap;
-
 use super::Day;
-use alex_lib::read_lines;
+use crate::cycles;
+use crate::grid::{Grid, Tile};
+use crate::parser::{self, Input, ParseErrors};
+use rand::{rngs::StdRng, Rng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Rock {
+    Round,
+    Cube,
+    Empty,
+}
+
+impl Tile for Rock {
+    const CHARS: &'static str = "O#.";
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'O' => Some(Rock::Round),
+            '#' => Some(Rock::Cube),
+            '.' => Some(Rock::Empty),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Rock::Round => 'O',
+            Rock::Cube => '#',
+            Rock::Empty => '.',
+        }
+    }
+}
+
+type Field = Vec<Vec<Rock>>;
 
 #[derive(Debug)]
 pub struct Day14 {
-    input: Vec<String>,
-    field: Vec<Vec<char>>,
-    field2: Vec<Vec<char>>,
-    field_hashes: HashMap<String, u64>,
+    input: Input,
+    field: Field,
+    field2: Field,
 }
 
 impl Day14 {
     pub fn new() -> Day14 {
         Day14 {
-            input: Vec::new(),
+            input: Input::new(),
             field: Vec::new(),
             field2: Vec::new(),
-            field_hashes: HashMap::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        for line in self.input.iter() {
-            let mut act_line = Vec::new();
-            for c in line.chars() {
-                act_line.push(c);
-            }
-            self.field.push(act_line);
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        if let Some(grid) = Grid::<Rock>::parse(self.input.non_empty_lines(), &[], &mut errors) {
+            self.field = grid.into_rows();
         }
         self.field2 = self.field.clone();
+        errors.into_result()
     }
 
     fn _print_field(&self) {
-        for line in self.field.iter() {
-            for c in line.iter() {
-                print!("{0}", c);
-            }
-            println!("")
-        }
+        print!("{0}", Grid::from_rows(self.field.clone()));
         println!("\n")
     }
 
-    fn move_stone_north(&mut self, x: usize, mut y: usize) -> usize {
-        let mut last_y = y;
-        while y > 0 {
-            y = y - 1;
-            if self.field[y][x] == '.' {
-                self.field[y + 1][x] = '.';
-                self.field[y][x] = 'O';
-                last_y = y;
-            } else {
-                break;
-            }
-        }
-        last_y
-    }
-    fn move_stone_south(&mut self, x: usize, mut y: usize) -> usize {
-        let mut last_y = y;
-        while y < self.field.len() - 1 {
-            y = y + 1;
-            if self.field[y][x] == '.' {
-                self.field[y - 1][x] = '.';
-                self.field[y][x] = 'O';
-                last_y = y;
-            } else {
-                break;
-            }
-        }
-        last_y
-    }
-    fn move_stone_west(&mut self, mut x: usize, y: usize) -> usize {
-        let mut last_x = x;
-        while x > 0 {
-            x = x - 1;
-            if self.field[y][x] == '.' {
-                self.field[y][x + 1] = '.';
-                self.field[y][x] = 'O';
-                last_x = x;
-            } else {
-                break;
-            }
-        }
-        last_x
-    }
-
-    fn move_stone_east(&mut self, mut x: usize, y: usize) -> usize {
-        let mut last_x = x;
-        while x < self.field[y].len() - 1 {
-            x = x + 1;
-            if self.field[y][x] == '.' {
-                self.field[y][x - 1] = '.';
-                self.field[y][x] = 'O';
-                last_x = x;
-            } else {
-                break;
-            }
-        }
-        last_x
-    }
-    fn calc_load(&self, field: &Vec<Vec<char>>) -> u64 {
+    fn calc_load(&self, field: &Field) -> u64 {
         let mut sum = 0;
         for y in 0..field.len() {
             for x in 0..field[y].len() {
-                if field[y][x] == 'O' {
+                if field[y][x] == Rock::Round {
                     sum += (field.len() - y) as u64;
                 }
             }
@@ -121,15 +86,14 @@ impl Day for Day14 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day14.txt");
-        // let input = read_lines("data/day14-test.txt");
-        self.input = input
-            .iter()
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-            .collect();
-        self.parse_input();
+        let input = Input::read("data/day14.txt");
+        // let input = Input::read("data/day14-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -139,10 +103,10 @@ impl Day for Day14 {
         // move all rocks:
         for y in 0..self.field.len() {
             for x in 0..self.field[y].len() {
-                if self.field[y][x] == 'O' {
-                    // let new_y = self.move_stone_north(x, y);
+                if self.field[y][x] == Rock::Round {
+                    // let new_y = move_stone_north(&mut self.field, x, y);
                     // solution += (self.field.len() - new_y) as u64;
-                    self.move_stone_north(x, y);
+                    move_stone_north(&mut self.field, x, y);
                 }
             }
         }
@@ -152,66 +116,133 @@ impl Day for Day14 {
         String::from(format!("{0}", solution))
     }
 
+    /// The spin cycle is repeated until a field state repeats: from there,
+    /// the fields repeat forever, so we can jump directly to the state after
+    /// 1000000000 cycles (see cycles::nth_state()).
     fn solve2(&mut self) -> String {
-        let solution: u64;
-        let mut cycle_count = 0;
-        let cycles = 1000000000;
-
-        self.field = self.field2.clone();
-
-        while cycle_count < cycles {
-            cycle_count += 1;
-            // move all rocks north:
-            for y in 0..self.field.len() {
-                for x in 0..self.field[y].len() {
-                    if self.field[y][x] == 'O' {
-                        self.move_stone_north(x, y);
-                    }
-                }
+        let cycle_count = 1000000000;
+
+        self.field = cycles::nth_state(self.field2.clone(), spin_cycle, cycle_count);
+        let solution = self.calc_load(&self.field);
+
+        String::from(format!("{0}", solution))
+    }
+}
+
+/// Executes a single spin cycle (tilt north, west, south, east),
+/// and returns the new field.
+fn spin_cycle(field: &Field) -> Field {
+    let mut field = field.clone();
+    // move all rocks north:
+    for y in 0..field.len() {
+        for x in 0..field[y].len() {
+            if field[y][x] == Rock::Round {
+                move_stone_north(&mut field, x, y);
             }
-            // move all rocks west:
-            for x in 0..self.field[0].len() {
-                for y in 0..self.field.len() {
-                    if self.field[y][x] == 'O' {
-                        self.move_stone_west(x, y);
-                    }
-                }
+        }
+    }
+    // move all rocks west:
+    for x in 0..field[0].len() {
+        for y in 0..field.len() {
+            if field[y][x] == Rock::Round {
+                move_stone_west(&mut field, x, y);
             }
-            // move all rocks south:
-            for y in (0..self.field.len()).rev() {
-                for x in 0..self.field[y].len() {
-                    if self.field[y][x] == 'O' {
-                        self.move_stone_south(x, y);
-                    }
-                }
+        }
+    }
+    // move all rocks south:
+    for y in (0..field.len()).rev() {
+        for x in 0..field[y].len() {
+            if field[y][x] == Rock::Round {
+                move_stone_south(&mut field, x, y);
             }
-            // move all rocks east:
-            for x in (0..self.field[0].len()).rev() {
-                for y in 0..self.field.len() {
-                    if self.field[y][x] == 'O' {
-                        self.move_stone_east(x, y);
-                    }
-                }
+        }
+    }
+    // move all rocks east:
+    for x in (0..field[0].len()).rev() {
+        for y in 0..field.len() {
+            if field[y][x] == Rock::Round {
+                move_stone_east(&mut field, x, y);
             }
+        }
+    }
+    field
+}
 
-            // calc hash of actual field:
-            let hash = format!("{:?}", self.field);
-            // if we detect a cycle, we fast-forward the pointer to the last position before the
-            // upper cycle limit, and only process the remaining part of the cycle:
-            match self.field_hashes.get(&hash) {
-                Some(first_seen) => {
-                    let cycle_len = cycle_count - *first_seen;
-                    cycle_count = (cycles - cycle_count) / cycle_len * cycle_len + cycle_count;
-                    self.field_hashes.clear();
-                }
-                None => {
-                    self.field_hashes.insert(hash, cycle_count);
-                }
-            }
+fn move_stone_north(field: &mut Field, x: usize, mut y: usize) -> usize {
+    let mut last_y = y;
+    while y > 0 {
+        y = y - 1;
+        if field[y][x] == Rock::Empty {
+            field[y + 1][x] = Rock::Empty;
+            field[y][x] = Rock::Round;
+            last_y = y;
+        } else {
+            break;
+        }
+    }
+    last_y
+}
+fn move_stone_south(field: &mut Field, x: usize, mut y: usize) -> usize {
+    let mut last_y = y;
+    while y < field.len() - 1 {
+        y = y + 1;
+        if field[y][x] == Rock::Empty {
+            field[y - 1][x] = Rock::Empty;
+            field[y][x] = Rock::Round;
+            last_y = y;
+        } else {
+            break;
         }
-        let load = self.calc_load(&self.field);
-        solution = load;
+    }
+    last_y
+}
+fn move_stone_west(field: &mut Field, mut x: usize, y: usize) -> usize {
+    let mut last_x = x;
+    while x > 0 {
+        x = x - 1;
+        if field[y][x] == Rock::Empty {
+            field[y][x + 1] = Rock::Empty;
+            field[y][x] = Rock::Round;
+            last_x = x;
+        } else {
+            break;
+        }
+    }
+    last_x
+}
 
-        String::from(format!("{0}", solution))
+fn move_stone_east(field: &mut Field, mut x: usize, y: usize) -> usize {
+    let mut last_x = x;
+    while x < field[y].len() - 1 {
+        x = x + 1;
+        if field[y][x] == Rock::Empty {
+            field[y][x - 1] = Rock::Empty;
+            field[y][x] = Rock::Round;
+            last_x = x;
+        } else {
+            break;
+        }
     }
+    last_x
+}
+
+/// Random input: a `size` x `size` platform with round and cube rocks
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let size = size.max(2);
+    let rows: Field = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| match rng.gen_range(0..100) {
+                    0..=19 => Rock::Round,
+                    20..=34 => Rock::Cube,
+                    _ => Rock::Empty,
+                })
+                .collect()
+        })
+        .collect();
+    Grid::from_rows(rows)
+        .to_string()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
 }
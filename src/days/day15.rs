@@ -1,34 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::Day;
-use alex_lib::read_lines;
+use crate::generate;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use regex::Regex;
 
 #[derive(Debug)]
 pub struct Day15 {
-    input: Vec<String>,
+    input: Input,
     input_data: Vec<String>,
 }
 
 impl Day15 {
     pub fn new() -> Day15 {
         Day15 {
-            input: Vec::new(),
+            input: Input::new(),
             input_data: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        self.input_data = self
-            .input
-            .iter()
-            .map(|s| s.trim().to_string())
-            .reduce(|acc, s| acc.to_string() + s.as_str())
-            .unwrap()
-            .split(',')
-            .map(|s| s.to_string())
-            .collect();
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for mut line in self.input.non_empty_lines() {
+            if let Some(steps) = errors.check(parse_steps(&mut line)) {
+                self.input_data.extend(steps.iter().map(|s| s.to_string()));
+            }
+        }
+        errors.into_result()
     }
 
     /// AoC Hash Algorithm:
@@ -52,6 +52,26 @@ impl Day15 {
     }
 }
 
+/// Parses a line of comma-separated steps, like 'rn=1,cm-,qp=3'
+fn parse_steps<'a>(line: &mut LineParser<'a>) -> ParseResult<Vec<&'a str>> {
+    let steps = line.separated(",", parse_step)?;
+    line.end()?;
+    Ok(steps)
+}
+
+/// Parses a single step like 'rn=1' or 'cm-', and returns it as written
+fn parse_step<'a>(line: &mut LineParser<'a>) -> ParseResult<&'a str> {
+    line.skip_whitespace();
+    let step = line.remaining();
+    let start = line.mark();
+    line.word()?;
+    if !line.try_literal("-") {
+        line.literal("=")?;
+        line.number::<u64>()?;
+    }
+    Ok(&step[..line.mark() - start])
+}
+
 impl Day for Day15 {
     fn day_nr(&self) -> String {
         String::from("15")
@@ -61,10 +81,14 @@ impl Day for Day15 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day15.txt");
-        // let input = read_lines("data/day15-test.txt");
+        let input = Input::read("data/day15.txt");
+        // let input = Input::read("data/day15-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -160,3 +184,39 @@ impl Day for Day15 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input: a single line of `4 * size` steps, using `2 * size` different labels
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    let labels: Vec<String> = (0..size * 2)
+        .map(|_| {
+            let len = rng.gen_range(2..=6);
+            generate::unique_name(rng, len, "abcdefghijklmnopqrstuvwxyz", &mut used)
+        })
+        .collect();
+    let steps: Vec<String> = (0..size * 4)
+        .map(|_| {
+            let label = labels.choose(rng).unwrap();
+            match rng.gen_bool(0.3) {
+                true => format!("{0}-", label),
+                false => format!("{0}={1}", label, rng.gen_range(1..=9)),
+            }
+        })
+        .collect();
+    vec![steps.join(",")]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_input() {
+        // found by the fuzzer (data/fuzz/day15-3.txt): an empty input has no steps
+        let mut day = Day15::new();
+        let input = Input::from_lines("test.txt", Vec::new());
+        day.parse(input).unwrap();
+        assert_eq!(day.solve1(), "0");
+        assert_eq!(day.solve2(), "0");
+    }
+}
@@ -5,12 +5,49 @@ use std::{
 };
 
 use alex_lib::{
-    read_lines,
     threads::ThreadPool,
     types::{Coord2d, Direction},
 };
 
 use super::Day;
+use crate::grid::{Grid, Tile};
+use crate::parser::{self, Input, ParseErrors};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+/// The contraption's floor tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Floor {
+    Empty,
+    HorizontalSplitter,
+    VerticalSplitter,
+    SlashMirror,
+    BackslashMirror,
+}
+
+impl Tile for Floor {
+    const CHARS: &'static str = ".-|/\\";
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Floor::Empty),
+            '-' => Some(Floor::HorizontalSplitter),
+            '|' => Some(Floor::VerticalSplitter),
+            '/' => Some(Floor::SlashMirror),
+            '\\' => Some(Floor::BackslashMirror),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Floor::Empty => '.',
+            Floor::HorizontalSplitter => '-',
+            Floor::VerticalSplitter => '|',
+            Floor::SlashMirror => '/',
+            Floor::BackslashMirror => '\\',
+        }
+    }
+}
 
 /// For each field cell, we need to keep track
 /// if the field was visited, and from which directions:
@@ -24,26 +61,24 @@ struct VisitedInfo {
 
 #[derive(Debug)]
 pub struct Day16 {
-    input: Vec<String>,
-    field: Vec<Vec<char>>,
+    input: Input,
+    field: Vec<Vec<Floor>>,
 }
 
 impl Day16 {
     pub fn new() -> Day16 {
         Day16 {
-            input: Vec::new(),
+            input: Input::new(),
             field: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        for line in self.input.iter() {
-            let mut l = Vec::new();
-            for c in line.chars() {
-                l.push(c);
-            }
-            self.field.push(l);
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        if let Some(grid) = Grid::parse(self.input.non_empty_lines(), &[], &mut errors) {
+            self.field = grid.into_rows();
         }
+        errors.into_result()
     }
 }
 
@@ -57,14 +92,14 @@ impl Day for Day16 {
 
     fn prepare(&mut self) {
         env_logger::init();
-        let input = read_lines("data/day16.txt");
-        // let input = read_lines("data/day16-test.txt");
-        self.input = input
-            .iter()
-            .filter(|f| !f.is_empty())
-            .map(|f| f.to_string())
-            .collect();
-        self.parse_input();
+        let input = Input::read("data/day16.txt");
+        // let input = Input::read("data/day16-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -145,7 +180,7 @@ fn execute_move(
     direction: Direction,
     move_q: &mut VecDeque<(Coord2d, Direction)>,
     visited: &mut HashMap<Coord2d, VisitedInfo>,
-    field: &Vec<Vec<char>>,
+    field: &Vec<Vec<Floor>>,
 ) {
     // we're out of the field, so we do nothing:
     if coord.x < 0
@@ -182,7 +217,7 @@ fn execute_move(
     let floor = field[coord.y as usize][coord.x as usize];
     match floor {
         // . --> move forward
-        '.' => match direction {
+        Floor::Empty => match direction {
             Direction::UP => {
                 move_q.push_back((coord.up(), direction));
             }
@@ -197,7 +232,7 @@ fn execute_move(
             }
         },
         // - --> move forward if headed horizontal, spawn 2 dirs if headed vertical
-        '-' => {
+        Floor::HorizontalSplitter => {
             if direction == Direction::UP || direction == Direction::DOWN {
                 move_q.push_back((coord.right(), Direction::RIGHT));
                 move_q.push_back((coord.left(), Direction::LEFT));
@@ -209,7 +244,7 @@ fn execute_move(
                 panic!("Unknown direction");
             }
         }
-        '|' => {
+        Floor::VerticalSplitter => {
             if direction == Direction::LEFT || direction == Direction::RIGHT {
                 move_q.push_back((coord.up(), Direction::UP));
                 move_q.push_back((coord.down(), Direction::DOWN));
@@ -221,7 +256,7 @@ fn execute_move(
                 panic!("Unknown direction");
             }
         }
-        '/' => match direction {
+        Floor::SlashMirror => match direction {
             Direction::UP => {
                 move_q.push_back((coord.right(), Direction::RIGHT));
             }
@@ -235,7 +270,7 @@ fn execute_move(
                 move_q.push_back((coord.down(), Direction::DOWN));
             }
         },
-        '\\' => match direction {
+        Floor::BackslashMirror => match direction {
             Direction::UP => {
                 move_q.push_back((coord.left(), Direction::LEFT));
             }
@@ -249,7 +284,6 @@ fn execute_move(
                 move_q.push_back((coord.up(), Direction::UP));
             }
         },
-        _ => panic!("Unknown floor: {}", floor),
     }
 }
 
@@ -260,7 +294,7 @@ fn execute_move(
 /// and calc the next steps until the queue is empty.
 /// Each move calc might add more steps to the queue (0, 1 or 2 more), depending
 /// on the mirror it steps on on its way.
-fn calc_energy(start_pos: Coord2d, start_dir: Direction, field: &Vec<Vec<char>>) -> u64 {
+fn calc_energy(start_pos: Coord2d, start_dir: Direction, field: &Vec<Vec<Floor>>) -> u64 {
     let mut move_q = VecDeque::new();
     // self.visited = HashMap::new();
     let mut visited = HashMap::new();
@@ -273,3 +307,28 @@ fn calc_energy(start_pos: Coord2d, start_dir: Direction, field: &Vec<Vec<char>>)
 
     visited.len() as u64
 }
+
+/// Random input: a `size` x `size` contraption, with mirrors and splitters on 10% of the tiles
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let devices = [
+        Floor::HorizontalSplitter,
+        Floor::VerticalSplitter,
+        Floor::SlashMirror,
+        Floor::BackslashMirror,
+    ];
+    let rows: Vec<Vec<Floor>> = (0..size.max(2))
+        .map(|_| {
+            (0..size.max(2))
+                .map(|_| match rng.gen_bool(0.1) {
+                    true => *devices.choose(rng).unwrap(),
+                    false => Floor::Empty,
+                })
+                .collect()
+        })
+        .collect();
+    Grid::from_rows(rows)
+        .to_string()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
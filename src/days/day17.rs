@@ -6,10 +6,9 @@ use std::{
 };
 
 use super::Day;
-use alex_lib::{
-    read_lines,
-    types::{Coord2d, Direction},
-};
+use crate::parser::{self, Input, ParseErrors};
+use alex_lib::types::{Coord2d, Direction};
+use rand::{rngs::StdRng, Rng};
 
 #[derive(Debug)]
 struct FieldInfo {
@@ -22,22 +21,26 @@ struct FieldInfo {
 
 #[derive(Debug)]
 pub struct Day17 {
-    input: Vec<String>,
+    input: Input,
     field: Vec<Vec<Rc<RefCell<FieldInfo>>>>,
 }
 
 impl Day17 {
     pub fn new() -> Day17 {
         Day17 {
-            input: Vec::new(),
+            input: Input::new(),
             field: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        for (y, line) in self.input.iter().enumerate() {
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        let costs = parser::parse_grid(self.input.non_empty_lines(), "0123456789", &mut errors);
+        errors.into_result()?;
+
+        for (y, line) in costs.iter().enumerate() {
             let mut l = Vec::new();
-            for (x, c) in line.chars().enumerate() {
+            for (x, c) in line.iter().enumerate() {
                 l.push(Rc::new(RefCell::new(FieldInfo {
                     coord: Coord2d {
                         x: x as i64,
@@ -51,6 +54,7 @@ impl Day17 {
             }
             self.field.push(l);
         }
+        Ok(())
     }
     fn print_field(&self) {
         for y in 0..self.field.len() {
@@ -161,14 +165,14 @@ impl Day for Day17 {
     }
 
     fn prepare(&mut self) {
-        // let input = read_lines("data/day17.txt");
-        let input = read_lines("data/day17-test.txt");
-        self.input = input
-            .iter()
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-            .collect();
-        self.parse_input();
+        // let input = Input::read("data/day17.txt");
+        let input = Input::read("data/day17-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -222,3 +226,14 @@ impl Day for Day17 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input: a `size` x `size` map of heat losses
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    (0..size.max(2))
+        .map(|_| {
+            (0..size.max(2))
+                .map(|_| char::from(b'0' + rng.gen_range(1..=9)))
+                .collect()
+        })
+        .collect()
+}
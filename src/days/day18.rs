@@ -1,14 +1,11 @@
-use std::collections::{HashSet, VecDeque};
-
 use super::Day;
-use alex_lib::{
-    read_lines,
-    types::{Coord2d, Coord2dMap, Direction},
-};
+use crate::generate;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use crate::polygon::Polygon;
+use alex_lib::types::{Coord2d, Direction};
+use rand::{rngs::StdRng, Rng};
 use regex::Regex;
 
-type FieldEntry = char;
-
 #[derive(Debug)]
 struct Instruction {
     dir: Direction,
@@ -18,193 +15,65 @@ struct Instruction {
 
 #[derive(Debug)]
 pub struct Day18 {
-    input: Vec<String>,
+    input: Input,
     instructions: Vec<Instruction>,
-    // contains the state of the field: initial hole ('#'), left flood area ('l'), right flood area ('r'), or empty (' ')
-    dig_field: Coord2dMap<FieldEntry>,
-    // contains a coordinate set of the initial circle area dig holes
-    circle_set: HashSet<Coord2d>,
-    // contains the colors per coordinate
-    colors: Coord2dMap<String>,
 }
 
 impl Day18 {
     pub fn new() -> Day18 {
         Day18 {
-            input: Vec::new(),
+            input: Input::new(),
             instructions: Vec::new(),
-            dig_field: Coord2dMap::new(),
-            circle_set: HashSet::new(),
-            colors: Coord2dMap::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let matcher = Regex::new(r"(R|D|L|U)\s+(\d+)\s+\((.*)\)").unwrap();
-        for line in self.input.iter() {
-            let captures = matcher.captures(line);
-            if let Some(groups) = captures {
-                let instr = Instruction {
-                    dir: Direction::from(groups[1].chars().next().unwrap()).unwrap(),
-                    steps: groups[2].parse::<usize>().unwrap(),
-                    color: groups[3].to_string(),
-                };
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for mut line in self.input.non_empty_lines() {
+            if let Some(instr) = errors.check(parse_instruction(&mut line)) {
                 self.instructions.push(instr);
             }
         }
-        // fill border line
-        let mut act_coord = Coord2d { x: 0, y: 0 };
-        for instr in self.instructions.iter() {
-            for _ in 0..instr.steps {
-                let new_coord = match instr.dir {
-                    Direction::UP => act_coord.up(),
-                    Direction::DOWN => act_coord.down(),
-                    Direction::LEFT => act_coord.left(),
-                    Direction::RIGHT => act_coord.right(),
-                };
-                self.dig_field.insert(new_coord, '#');
-                self.colors.insert(new_coord, instr.color.clone());
-                self.circle_set.insert(new_coord);
-                act_coord = new_coord;
-            }
-        }
-        // fill empty coords, including a 1-tile border around the field:
-        let min_x = self.dig_field.min_x();
-        let min_y = self.dig_field.min_y();
-        let max_x = self.dig_field.max_x();
-        let max_y = self.dig_field.max_y();
-        for y in min_y - 1..=max_y + 1 {
-            for x in min_x - 1..=max_x + 1 {
-                if let None = self.dig_field.get(&Coord2d { x, y }) {
-                    self.dig_field.insert(Coord2d { x, y }, '.');
-                }
-            }
-        }
-    }
-
-    fn print_field(&self) {
-        println!(
-            "Field dimensions: {}x{}, minx={}, miny={}, maxx={}, maxy={}",
-            self.dig_field.width(),
-            self.dig_field.height(),
-            self.dig_field.min_x(),
-            self.dig_field.min_y(),
-            self.dig_field.max_x(),
-            self.dig_field.max_y()
-        );
-        for y in self.dig_field.min_y()..=self.dig_field.max_y() {
-            for x in self.dig_field.min_x()..=self.dig_field.max_x() {
-                let entry = self.dig_field.get(&Coord2d { x, y });
-                match entry {
-                    Some(c) => print!("{}", c),
-                    None => print!(" "),
-                };
-            }
-            println!();
-        }
-        println!();
+        errors.into_result()
     }
 
-    /// Returns the new direction after moving onto a tile from a
-    /// specific direction.
-    /// act_pos is the tile we're moved to (so where we are now),
-    /// act_dir is the direction in which we were heading while moving.
-    /// The function returns the direction in which we can move away from the tile.
-    fn get_new_dir(&self, act_pos: Coord2d, act_dir: Direction) -> Direction {
-        match act_dir {
-            Direction::UP => {
-                if self.circle_set.contains(&act_pos.up()) {
-                    return Direction::UP;
-                } else if self.circle_set.contains(&act_pos.left()) {
-                    return Direction::LEFT;
-                } else if self.circle_set.contains(&act_pos.right()) {
-                    return Direction::RIGHT;
-                } else {
-                    panic!("Cannot walk anywhere");
-                }
-            }
-            Direction::RIGHT => {
-                if self.circle_set.contains(&act_pos.right()) {
-                    return Direction::RIGHT;
-                } else if self.circle_set.contains(&act_pos.up()) {
-                    return Direction::UP;
-                } else if self.circle_set.contains(&act_pos.down()) {
-                    return Direction::DOWN;
-                } else {
-                    panic!("Cannot walk anywhere");
-                }
-            }
-            Direction::DOWN => {
-                if self.circle_set.contains(&act_pos.down()) {
-                    return Direction::DOWN;
-                } else if self.circle_set.contains(&act_pos.left()) {
-                    return Direction::LEFT;
-                } else if self.circle_set.contains(&act_pos.right()) {
-                    return Direction::RIGHT;
-                } else {
-                    panic!("Cannot walk anywhere");
-                }
-            }
-            Direction::LEFT => {
-                if self.circle_set.contains(&act_pos.left()) {
-                    return Direction::LEFT;
-                } else if self.circle_set.contains(&act_pos.up()) {
-                    return Direction::UP;
-                } else if self.circle_set.contains(&act_pos.down()) {
-                    return Direction::DOWN;
-                } else {
-                    panic!("Cannot walk anywhere");
-                }
-            }
-        }
+    /// Creates the dig polygon from the given instructions.
+    fn dig_polygon(&self, instructions: &[Instruction]) -> Polygon {
+        let steps: Vec<(Direction, i64)> = instructions
+            .iter()
+            .map(|instr| (instr.dir, instr.steps as i64))
+            .collect();
+        Polygon::from_instructions(Coord2d { x: 0, y: 0 }, &steps)
     }
+}
 
-    // fn get_left_of(&self, act_pos: Coord2d, act_dir: Direction) -> Coord2d {
-    //     match act_dir {
-    //         Direction::UP => act_pos.left(),
-    //         Direction::RIGHT => act_pos.up(),
-    //         Direction::DOWN => act_pos.right(),
-    //         Direction::LEFT => act_pos.down(),
-    //     }
-    // }
-    // fn get_right_of(&self, act_pos: Coord2d, act_dir: Direction) -> Coord2d {
-    //     match act_dir {
-    //         Direction::UP => act_pos.right(),
-    //         Direction::RIGHT => act_pos.down(),
-    //         Direction::DOWN => act_pos.left(),
-    //         Direction::LEFT => act_pos.up(),
-    //     }
-    // }
-
-    // fn fill_neighbour_areas(&mut self, act_pos: Coord2d, act_dir: Direction) {
-    //     // flood-fill left and right side of the coordinate. Here, left/right is relative to the
-    //     // actual heading direction:
-    //     let left_coord = self.get_left_of(act_pos, act_dir);
-    //     let right_coord = self.get_right_of(act_pos, act_dir);
-    //     self.fill_area(left_coord, 'l');
-    //     self.fill_area(right_coord, 'r');
-    // }
-
-    /// Flood fill given area, starting at coord,
-    /// using a queue for filling instead of recursion,
-    /// as the recursion would lead to a stack overflow.
-    fn fill_area(&mut self, coord: Coord2d) {
-        let mut queue = VecDeque::new();
-        queue.push_back(coord);
-
-        while queue.len() > 0 {
-            let coord = queue.pop_front().unwrap();
-            if let Some(c) = self.dig_field.get(&coord) {
-                if *c == '.' || *c == ' ' {
-                    self.dig_field.insert(coord.clone(), '*');
-                    queue.push_back(coord.up());
-                    queue.push_back(coord.down());
-                    queue.push_back(coord.left());
-                    queue.push_back(coord.right());
-                }
-            }
-        }
+/// Parses lines like 'R 6 (#70c710)'. The color is checked already here,
+/// as part 2 reads the real instruction from it: 5 hex digits for the steps,
+/// and a direction digit from 0 to 3.
+fn parse_instruction(line: &mut LineParser) -> ParseResult<Instruction> {
+    let dir_pos = line.mark();
+    let dir = match line.chars_of("RDLU")? {
+        "R" => Direction::RIGHT,
+        "D" => Direction::DOWN,
+        "L" => Direction::LEFT,
+        "U" => Direction::UP,
+        _ => return Err(line.error_at(dir_pos, "a single direction (R, D, L, U)")),
+    };
+    let steps = line.number::<usize>()?;
+    line.literal("(")?;
+    let color_pos = line.mark();
+    line.literal("#")?;
+    let hex = line.chars_of("0123456789abcdefABCDEF")?;
+    if hex.len() != 6 || !"0123".contains(&hex[5..]) {
+        return Err(line.error_at(color_pos, "a color like '#70c710', ending with 0-3"));
     }
+    line.literal(")")?;
+    line.end()?;
+    Ok(Instruction {
+        dir,
+        steps,
+        color: format!("#{0}", hex),
+    })
 }
 
 impl Day for Day18 {
@@ -216,30 +85,21 @@ impl Day for Day18 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day18.txt");
-        // let input = read_lines("data/day18-test.txt");
-        self.input = input
-            .iter()
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_string())
-            .collect();
-        self.parse_input();
+        let input = Input::read("data/day18.txt");
+        // let input = Input::read("data/day18-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
     }
 
     /// This is actually a very same problem as day 10:
-    /// flood fill an unknown area. I will re-use some of
-    /// the code from this day.
+    /// First, I solved it with a flood fill of the outer area. Now both parts use
+    /// the polygon calculation (see solve2).
     fn solve1(&mut self) -> String {
-        let solution: i64;
-
-        self.fill_area(Coord2d {
-            x: self.dig_field.min_x(),
-            y: self.dig_field.min_y(),
-        });
-
-        let fill_count: i64 = self.dig_field.iter().filter(|(_, c)| **c == '*').count() as i64;
-        solution = self.dig_field.width() * self.dig_field.height() - fill_count;
-        // self.print_field();
+        let solution: i64 = self.dig_polygon(&self.instructions).lattice_points();
         String::from(format!("{0}", solution))
     }
 
@@ -281,9 +141,7 @@ impl Day for Day18 {
     /// NOTE: I also read some other's source code, for example that one had the final clue:
     /// https://github.com/pkusensei/adventofcode2023/blob/4bad47d109c8b3c72ee44f2fd5964f4e369545fc/d18/src/lib.rs
     fn solve2(&mut self) -> String {
-        let mut solution: i64 = 0;
         let mut instructions: Vec<Instruction> = Vec::new();
-        let mut coords: Vec<Coord2d> = Vec::new();
 
         // Re-parse input:
         let matcher = Regex::new(r"#([a-fA-F0-9]{5})(\d)").unwrap();
@@ -304,44 +162,72 @@ impl Day for Day18 {
 
         // println!("Instructions: {:?}", instructions);
 
-        // Fill in all coordinates as the shoelace algorithm requires:
-        let mut act_coord = Coord2d {
-            x: 0, y: 0
-        };
-        let mut boundary_length = 0;
-
-        for instr in instructions.iter() {
-            match instr.dir {
-                Direction::UP => {
-                    act_coord = act_coord.up_n(instr.steps as i64)
-                }
-                Direction::RIGHT => {
-                    act_coord = act_coord.right_n(instr.steps as i64)
-                }
-                Direction::DOWN => {
-                    act_coord = act_coord.down_n(instr.steps as i64)
-                }
-                Direction::LEFT => {
-                    act_coord = act_coord.left_n(instr.steps as i64)
-                }
-            }
-            boundary_length += instr.steps as i64;
-            coords.push(act_coord);
-        }
-        // double the first entry:
-        coords.push(coords[0]);
-
-        // Now, calc the area using the Shoelace algorithm:
-        let mut s1:i64 = 0;
-        let mut s2:i64 = 0;
-        for i in 0..coords.len()-1 {
-            s1 += coords[i].x * coords[i + 1].y;
-            s2 += coords[i].y * coords[i + 1].x;
-        }
-        // also add the boundary length (half of it, as the shoelace includes the boundary on one side),
-        // and remove 1 from the solution (start of the boundary was visited twice):
-        solution = (s1 - s2).abs() / 2 + boundary_length / 2 + 1;
+        // The polygon calculates the area using the Shoelace algorithm. The area alone
+        // is not enough, as the dig line is 1 tile wide: we need all the lattice points (tiles)
+        // within and on the boundary, which we get from Pick's theorem:
+        // A = I + B/2 - 1, so I + B = A + B/2 + 1
+        let solution: i64 = self.dig_polygon(&instructions).lattice_points();
 
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input: the outline of a random shape of `size` x `size` cells. For the instructions
+/// of part 1, each column / row of cells gets a random width / height of a few meters,
+/// for the colors (part 2) of thousands of meters.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let size = size.max(2) as i64;
+    let shape = generate::random_shape(size, size, (size * size / 2) as usize, rng);
+    let (start, moves) = generate::outline(&shape);
+
+    // scales each outline move by the widths (horizontal) or heights (vertical) of the cells:
+    let mut scale = |range: std::ops::Range<usize>| -> Vec<(Direction, i64)> {
+        let widths: Vec<i64> = (0..size)
+            .map(|_| rng.gen_range(range.clone()) as i64)
+            .collect();
+        let heights: Vec<i64> = (0..size)
+            .map(|_| rng.gen_range(range.clone()) as i64)
+            .collect();
+        let mut pos = start;
+        let mut scaled: Vec<(Direction, i64)> = Vec::new();
+        for (dir, steps) in moves.iter() {
+            let (from, to, sizes) = match dir {
+                Direction::UP => (pos.y - steps, pos.y, &heights),
+                Direction::DOWN => (pos.y, pos.y + steps, &heights),
+                Direction::LEFT => (pos.x - steps, pos.x, &widths),
+                Direction::RIGHT => (pos.x, pos.x + steps, &widths),
+            };
+            scaled.push((*dir, sizes[from as usize..to as usize].iter().sum()));
+            pos = match dir {
+                Direction::UP => pos.up_n(*steps),
+                Direction::DOWN => pos.down_n(*steps),
+                Direction::LEFT => pos.left_n(*steps),
+                Direction::RIGHT => pos.right_n(*steps),
+            };
+        }
+        scaled
+    };
+    let part1 = scale(2..7);
+    // the hex distance has 5 digits, so the sum of all columns must stay below 16^5:
+    let part2 = scale(1000..(1_000_000 / size as usize).max(1001));
+
+    let dir_char = |dir: &Direction| match dir {
+        Direction::RIGHT => ('R', 0),
+        Direction::DOWN => ('D', 1),
+        Direction::LEFT => ('L', 2),
+        Direction::UP => ('U', 3),
+    };
+    part1
+        .iter()
+        .zip(part2.iter())
+        .map(|((dir1, steps1), (dir2, steps2))| {
+            format!(
+                "{0} {1} (#{2:05x}{3})",
+                dir_char(dir1).0,
+                steps1,
+                steps2,
+                dir_char(dir2).1
+            )
+        })
+        .collect()
+}
@@ -1,8 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::Day;
-use alex_lib::read_lines;
-use regex::Regex;
+use crate::generate;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+/// Each of the x, m, a, s ratings of a part is in the range MIN_RATING..=MAX_RATING
+const MIN_RATING: i64 = 1;
+const MAX_RATING: i64 = 4000;
 
 #[derive(Debug)]
 enum Operator {
@@ -56,61 +61,203 @@ impl Workflow {
         panic!("Oops! This should not happen!")
     }
 
-    /// calc how many accepted / rejected  possibilities are there for this
-    /// workflow (and all that follow)
-    /// This is relatively simple:
-    /// go through each rule, and calc the outcome of each rule, and sum them up
-    /// per accepted/rejected.
-    /// returns nr_of_accepted
-    fn calc_max_acc(&self, workflows: &HashMap<String, Workflow>) -> i128 {
-        let possibilities: i128 = 4000;
+    /// Counts how many part rating combinations within the given range box are
+    /// accepted by this workflow (and all that follow).
+    /// The range box is split at each rule: the matching part is sent to the rule's target,
+    /// the non-matching rest continues with the next rule.
+    fn count_accepted(&self, range: PartRange, workflows: &HashMap<String, Workflow>) -> i128 {
         let mut acc: i128 = 0;
-        let mut last_rej: i128 = 1;
+        let mut rest = Some(range);
         for rule in self.rules.iter() {
-            let rule_value = rule.value as i128;
-            match rule.op {
-                Operator::NONE => match rule.target.as_str() {
-                    "A" => acc += last_rej,
-                    "R" => {},
-                    target => {
-                        let target_wf = workflows.get(target).unwrap();
-                        let a = target_wf.calc_max_acc(workflows);
-                        acc += a * last_rej;
-                    }
-                },
-                Operator::GT => {
-                    let local_acc = possibilities - rule_value;
-                    match rule.target.as_str() {
-                        "A" => acc += local_acc * last_rej,
-                        "R" => {},
-                        target => {
-                            let target_wf = workflows.get(target).unwrap();
-                            let a = target_wf.calc_max_acc(workflows);
-                            acc += a * local_acc * last_rej;
-                        }
-                    }
-                    last_rej = possibilities - local_acc;
-                }
-                Operator::LT => {
-                    let local_acc = rule_value - 1;
-                    match rule.target.as_str() {
-                        "A" => acc += local_acc * last_rej,
-                        "R" => {},
-                        target => {
-                            let target_wf = workflows.get(target).unwrap();
-                            let a = target_wf.calc_max_acc(workflows);
-                            acc += a * local_acc * last_rej;
-                        }
-                    }
-                    last_rej = possibilities - local_acc;
-                }
+            let act_range = match rest {
+                Some(r) => r,
+                None => break,
+            };
+            let (matching, not_matching) = act_range.split(rule);
+            if let Some(matching) = matching {
+                acc += match rule.target.as_str() {
+                    "A" => matching.count(),
+                    "R" => 0,
+                    target => workflows
+                        .get(target)
+                        .unwrap()
+                        .count_accepted(matching, workflows),
+                };
             }
+            rest = not_matching;
         }
 
-        acc 
+        acc
     }
 }
 
+/// A 4-dimensional box of part ratings:
+/// an inclusive (min, max) range for each of the x, m, a, s properties.
+#[derive(Debug, Clone, Copy)]
+struct PartRange {
+    x: (i64, i64),
+    m: (i64, i64),
+    a: (i64, i64),
+    s: (i64, i64),
+}
+
+impl PartRange {
+    fn new(min: i64, max: i64) -> PartRange {
+        PartRange {
+            x: (min, max),
+            m: (min, max),
+            a: (min, max),
+            s: (min, max),
+        }
+    }
+
+    fn get(&self, prop: &str) -> (i64, i64) {
+        match prop {
+            "x" => self.x,
+            "m" => self.m,
+            "a" => self.a,
+            "s" => self.s,
+            _ => panic!("Unknown part property"),
+        }
+    }
+
+    /// returns a copy of the range box, with the given property range replaced.
+    /// Returns None if the new property range is empty.
+    fn with(&self, prop: &str, range: (i64, i64)) -> Option<PartRange> {
+        if range.0 > range.1 {
+            return None;
+        }
+        let mut new_range = *self;
+        match prop {
+            "x" => new_range.x = range,
+            "m" => new_range.m = range,
+            "a" => new_range.a = range,
+            "s" => new_range.s = range,
+            _ => panic!("Unknown part property"),
+        }
+        Some(new_range)
+    }
+
+    /// Number of rating combinations in this box
+    fn count(&self) -> i128 {
+        [self.x, self.m, self.a, self.s]
+            .iter()
+            .map(|(min, max)| (max - min + 1).max(0) as i128)
+            .product()
+    }
+
+    /// Splits the box by the rule's condition:
+    /// returns the (matching, not matching) parts, each of them may be empty.
+    fn split(&self, rule: &Rule) -> (Option<PartRange>, Option<PartRange>) {
+        match rule.op {
+            Operator::NONE => (Some(*self), None),
+            Operator::LT => {
+                let (min, max) = self.get(&rule.prop);
+                (
+                    self.with(&rule.prop, (min, max.min(rule.value - 1))),
+                    self.with(&rule.prop, (min.max(rule.value), max)),
+                )
+            }
+            Operator::GT => {
+                let (min, max) = self.get(&rule.prop);
+                (
+                    self.with(&rule.prop, (min.max(rule.value + 1), max)),
+                    self.with(&rule.prop, (min, max.min(rule.value))),
+                )
+            }
+        }
+    }
+}
+
+/// Parses a single workflow rule, like 'a<2006:qkq' or 'rfg'.
+/// Returns the rule and the position of its target in the line.
+fn parse_rule(line: &mut LineParser) -> ParseResult<(Rule, usize)> {
+    line.skip_whitespace();
+    let start = line.mark();
+    let first = line.word()?;
+    let op = if line.try_literal("<") {
+        Operator::LT
+    } else if line.try_literal(">") {
+        Operator::GT
+    } else {
+        // no condition, just a target:
+        let rule = Rule {
+            prop: "-".to_string(),
+            op: Operator::NONE,
+            value: 0,
+            target: first.to_string(),
+        };
+        return Ok((rule, start));
+    };
+    if !["x", "m", "a", "s"].contains(&first) {
+        return Err(line.error_at(start, "one of the properties 'x', 'm', 'a', 's'"));
+    }
+    let value = line.number::<i64>()?;
+    line.literal(":")?;
+    line.skip_whitespace();
+    let target_pos = line.mark();
+    let target = line.word()?;
+    let rule = Rule {
+        prop: first.to_string(),
+        op,
+        value,
+        target: target.to_string(),
+    };
+    Ok((rule, target_pos))
+}
+
+/// Parses a workflow line, like 'px{a<2006:qkq,m>2090:A,rfg}'.
+/// Returns the workflow and the position of each rule target in the line.
+fn parse_workflow(line: &mut LineParser) -> ParseResult<(Workflow, Vec<usize>)> {
+    let name = line.word()?;
+    line.literal("{")?;
+    let (rules, target_positions): (Vec<Rule>, Vec<usize>) =
+        line.separated(",", parse_rule)?.into_iter().unzip();
+    // the last rule catches all parts that no other rule matched:
+    if !matches!(
+        rules.last(),
+        Some(Rule {
+            op: Operator::NONE,
+            ..
+        })
+    ) {
+        return Err(line.error("',' and a last rule without a condition"));
+    }
+    line.literal("}")?;
+    line.end()?;
+    let workflow = Workflow {
+        name: name.to_string(),
+        rules,
+    };
+    Ok((workflow, target_positions))
+}
+
+/// Parses a part line, like '{x=787,m=2655,a=1222,s=2876}'
+fn parse_part(line: &mut LineParser) -> ParseResult<Part> {
+    let mut values: [i64; 4] = [0; 4];
+    line.literal("{")?;
+    for (i, prop) in ["x", "m", "a", "s"].iter().enumerate() {
+        if i > 0 {
+            line.literal(",")?;
+        }
+        line.skip_whitespace();
+        let start = line.mark();
+        let (key, value) = line.key_value::<i64>("=")?;
+        if key != *prop {
+            return Err(line.error_at(start, &format!("'{}'", prop)));
+        }
+        values[i] = value;
+    }
+    line.literal("}")?;
+    line.end()?;
+    Ok(Part {
+        x: values[0],
+        m: values[1],
+        a: values[2],
+        s: values[3],
+    })
+}
+
 #[derive(Debug)]
 struct Part {
     x: i64,
@@ -121,7 +268,7 @@ struct Part {
 
 #[derive(Debug)]
 pub struct Day19 {
-    input: Vec<String>,
+    input: Input,
     workflows: HashMap<String, Workflow>,
     parts: Vec<Part>,
 }
@@ -129,78 +276,112 @@ pub struct Day19 {
 impl Day19 {
     pub fn new() -> Day19 {
         Day19 {
-            input: Vec::new(),
+            input: Input::new(),
             workflows: HashMap::new(),
             parts: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let mut iter = self.input.iter();
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        let end_of_input = self.input.line(self.input.raw_lines().len());
+        let sections = self.input.sections();
+        if sections.len() != 2 {
+            return Err(ParseErrors::from(end_of_input.error(
+                "a workflow block and a parts block, separated by an empty line",
+            )));
+        }
+
         // Part 1: read workflows:
         // matches: px{a<2006:qkq,m>2090:A,rfg}
-        let wf_matcher = Regex::new(r"(\w+)\{(.*)\}").unwrap();
-        let r_matcher = Regex::new(r"([xmas])([<>])(\d+):(\w+)").unwrap();
-        while let Some(line) = iter.next() {
-            if line.is_empty() {
-                break;
+        // The rule targets are remembered with their position, to check that they exist,
+        // and that they do not loop:
+        let mut names: Vec<String> = Vec::new();
+        let mut targets: HashMap<String, (LineParser, Vec<usize>)> = HashMap::new();
+        let workflows = sections[0].records(&mut errors, |line| {
+            let (workflow, positions) = parse_workflow(line)?;
+            Ok((workflow, positions, line.clone()))
+        });
+        for (workflow, positions, line) in workflows {
+            if let Some((defined, _)) = targets.get(&workflow.name) {
+                errors.push(line.error_at(
+                    0,
+                    &format!(
+                        "a new workflow name ('{0}' is already defined in line {1})",
+                        workflow.name,
+                        defined.line_nr()
+                    ),
+                ));
+                continue;
             }
-            let captures = wf_matcher.captures(line).unwrap();
-            let name = captures.get(1).unwrap().as_str();
-            let rules = captures.get(2).unwrap().as_str().split(',');
-            let mut prop: String;
-            let mut op: Operator;
-            let mut value: i64;
-            let mut target: String;
-            let mut rules_vec: Vec<Rule> = Vec::new();
-            for r in rules {
-                if let Some(r_captures) = r_matcher.captures(r) {
-                    prop = r_captures.get(1).unwrap().as_str().to_string();
-                    op = match r_captures.get(2).unwrap().as_str() {
-                        "<" => Operator::LT,
-                        ">" => Operator::GT,
-                        _ => panic!("Unexpected operator"),
-                    };
-                    value = r_captures.get(3).unwrap().as_str().parse::<i64>().unwrap();
-                    target = r_captures.get(4).unwrap().as_str().to_string();
-                } else {
-                    prop = "-".to_string();
-                    op = Operator::NONE;
-                    value = 0;
-                    target = r.to_string();
+            names.push(workflow.name.clone());
+            targets.insert(workflow.name.clone(), (line, positions));
+            self.workflows.insert(workflow.name.clone(), workflow);
+        }
+        for name in names.iter() {
+            let (line, positions) = &targets[name];
+            for (rule, pos) in self.workflows[name].rules.iter().zip(positions) {
+                let target = rule.target.as_str();
+                if target != "A" && target != "R" && !self.workflows.contains_key(target) {
+                    errors.push(line.error_at(*pos, "'A', 'R' or the name of a defined workflow"));
                 }
-                rules_vec.push(Rule {
-                    prop,
-                    op,
-                    value,
-                    target,
-                });
             }
-            self.workflows.insert(
-                name.to_string(),
-                Workflow {
-                    name: name.to_string(),
-                    rules: rules_vec,
-                },
-            );
         }
-
-        // Part 2: Read parts
-        let parts_matcher = Regex::new(r"x=(\d+),m=(\d+),a=(\d+),s=(\d+)").unwrap();
-        while let Some(line) = iter.next() {
-            if let Some(groups) = parts_matcher.captures(line) {
-                let part = Part {
-                    x: groups.get(1).unwrap().as_str().parse::<i64>().unwrap(),
-                    m: groups.get(2).unwrap().as_str().parse::<i64>().unwrap(),
-                    a: groups.get(3).unwrap().as_str().parse::<i64>().unwrap(),
-                    s: groups.get(4).unwrap().as_str().parse::<i64>().unwrap(),
-                };
-                self.parts.push(part);
+        if !self.workflows.contains_key("in") {
+            errors.push(end_of_input.error("a starting workflow named 'in'"));
+        } else {
+            // a loop would send the parts around forever:
+            let mut path: Vec<&str> = Vec::new();
+            if let Some(rule) = self.find_loop("in", &mut path, &mut HashSet::new()) {
+                let (line, positions) = &targets[*path.last().unwrap()];
+                path.push(&self.workflows[*path.last().unwrap()].rules[rule].target);
+                errors.push(line.error_at(
+                    positions[rule],
+                    &format!("a target that does not loop ({0})", path.join(" -> ")),
+                ));
             }
         }
 
+        // Part 2: Read parts
+        // matches: {x=787,m=2655,a=1222,s=2876}
+        self.parts = sections[1].records(&mut errors, parse_part);
+
         // println!("Workflows: {:?}", self.workflows);
         // println!("Parts: {:?}", self.parts);
+        errors.into_result()
+    }
+
+    /// Depth-first search through the workflows that can be reached from `name`:
+    /// returns the index of the first rule that leads back to a workflow on the `path`.
+    /// The path then ends with the workflow of that rule.
+    fn find_loop<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<usize> {
+        path.push(name);
+        for (i, rule) in self.workflows[name].rules.iter().enumerate() {
+            let target = rule.target.as_str();
+            if path.contains(&target) {
+                return Some(i);
+            }
+            if self.workflows.contains_key(target) && !done.contains(target) {
+                if let Some(i) = self.find_loop(target, path, done) {
+                    return Some(i);
+                }
+            }
+        }
+        path.pop();
+        done.insert(name);
+        None
+    }
+
+    /// Counts all accepted part combinations, where each
+    /// rating is in the range min..=max.
+    fn count_accepted_combinations(&self, min: i64, max: i64) -> i128 {
+        let start_wf = self.workflows.get("in").unwrap();
+        start_wf.count_accepted(PartRange::new(min, max), &self.workflows)
     }
 }
 
@@ -213,10 +394,14 @@ impl Day for Day19 {
     }
 
     fn prepare(&mut self) {
-        // let input = read_lines("data/day19.txt");
-        let input = read_lines("data/day19-test.txt");
+        let input = Input::read("data/day19.txt");
+        // let input = Input::read("data/day19-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -249,14 +434,178 @@ impl Day for Day19 {
         String::from(format!("{0}", solution))
     }
 
+    /// Instead of checking every single part, we send a 4-dimensional range box
+    /// (MIN_RATING..=MAX_RATING for each of x, m, a, s) through the workflows: each rule
+    /// splits the box into a matching and a non-matching part, until the parts land in
+    /// A or R. The accepted boxes are disjoint, so we just sum up their sizes.
     fn solve2(&mut self) -> String {
-        let mut solution: i128 = 0;
-
-        // let start_wf = self.workflows.get("lnx").unwrap();
-        let start_wf = self.workflows.get("in").unwrap();
-        let acc = start_wf.calc_max_acc(&self.workflows);
-        solution = acc;
+        let solution: i128 = self.count_accepted_combinations(MIN_RATING, MAX_RATING);
 
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input: a tree of about `size` workflows, starting at "in", and `size` parts
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    used.insert(String::from("in"));
+    let mut workflows: Vec<String> = Vec::new();
+    // workflows to create, the first one is the root:
+    let mut todo: Vec<String> = vec![String::from("in")];
+    while let Some(name) = todo.pop() {
+        let mut new_target = |rng: &mut StdRng, todo: &mut Vec<String>| -> String {
+            if workflows.len() + todo.len() < size && rng.gen_bool(0.5) {
+                let len = rng.gen_range(2..=3);
+                let next = generate::unique_name(rng, len, "abcdefghijklmnopqrstuvwxyz", &mut used);
+                todo.push(next.clone());
+                return next;
+            }
+            String::from(*["A", "R"].choose(rng).unwrap())
+        };
+        let mut rules: Vec<String> = Vec::new();
+        for _ in 0..rng.gen_range(1..=3) {
+            let category = ['x', 'm', 'a', 's'].choose(rng).unwrap();
+            let operator = ['<', '>'].choose(rng).unwrap();
+            let value = rng.gen_range(MIN_RATING..MAX_RATING);
+            let target = new_target(rng, &mut todo);
+            rules.push(format!("{0}{1}{2}:{3}", category, operator, value, target));
+        }
+        rules.push(new_target(rng, &mut todo));
+        workflows.push(format!("{0}{{{1}}}", name, rules.join(",")));
+    }
+    workflows.shuffle(rng);
+
+    let mut lines = workflows;
+    lines.push(String::new());
+    for _ in 0..size {
+        let [x, m, a, s] = [(); 4].map(|_| rng.gen_range(MIN_RATING..=MAX_RATING));
+        lines.push(format!("{{x={0},m={1},a={2},s={3}}}", x, m, a, s));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn parse(lines: Vec<String>) -> Day19 {
+        let mut day = Day19::new();
+        day.parse(Input::from_lines("test.txt", lines)).unwrap();
+        day
+    }
+
+    /// The slow way: sends a single part through the workflows
+    fn is_accepted(day: &Day19, part: &Part) -> bool {
+        let mut workflow = day.workflows.get("in").unwrap();
+        loop {
+            match workflow.process_part(part).as_str() {
+                "A" => return true,
+                "R" => return false,
+                next => workflow = day.workflows.get(next).unwrap(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_workflows() {
+        let errors = |workflows: &[&str]| -> Vec<String> {
+            let mut lines: Vec<String> = workflows.iter().map(|l| l.to_string()).collect();
+            lines.extend([String::new(), String::from("{x=1,m=1,a=1,s=1}")]);
+            let mut day = Day19::new();
+            let result = day.parse(Input::from_lines("test.txt", lines));
+            result
+                .unwrap_err()
+                .errors()
+                .iter()
+                .map(|e| e.to_string())
+                .collect()
+        };
+        assert_eq!(
+            errors(&["in{a<5:A}"]),
+            vec![
+                "test.txt:1:9: expected ',' and a last rule without a condition, found '}'",
+                "test.txt:4:1: expected a starting workflow named 'in', found end of line",
+            ]
+        );
+        assert_eq!(
+            errors(&["in{x<5:px,px}", "px{A}", "px{R}"]),
+            vec!["test.txt:3:1: expected a new workflow name ('px' is already defined in line 2), found 'px{R}'"]
+        );
+        assert_eq!(
+            errors(&["in{x<5:px,px}", "px{in}"]),
+            vec![
+                "test.txt:2:4: expected a target that does not loop (in -> px -> in), found 'in}'"
+            ]
+        );
+        assert_eq!(
+            errors(&["in{x<5:px,A}", "px{m>2:qs,R}", "qs{s<3:A,px}"]),
+            vec!["test.txt:3:10: expected a target that does not loop (in -> px -> qs -> px), found 'px}'"]
+        );
+    }
+
+    #[test]
+    fn test_count_accepted_combinations_example() {
+        let mut day = Day19::new();
+        day.parse(Input::read("data/day19-test.txt")).unwrap();
+        assert_eq!(
+            day.count_accepted_combinations(MIN_RATING, MAX_RATING),
+            167409079868000
+        );
+        assert_eq!(day.solve2(), "167409079868000");
+    }
+
+    #[test]
+    fn test_count_accepted_combinations_matches_all_parts() {
+        // small random workflows (each one only sends parts to later ones, so there are
+        // no loops), and small bounds, so all parts in the bounds can be checked one by one:
+        let rng = &mut StdRng::seed_from_u64(29);
+        for _ in 0..50 {
+            let names: Vec<String> = (0..rng.gen_range(1..=5))
+                .map(|i| match i {
+                    0 => String::from("in"),
+                    i => format!("w{0}", i),
+                })
+                .collect();
+            let lines: Vec<String> = names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let mut targets = vec![String::from("A"), String::from("R")];
+                    targets.extend(names[i + 1..].iter().cloned());
+                    let mut rules: Vec<String> = (0..rng.gen_range(0..=3))
+                        .map(|_| {
+                            format!(
+                                "{0}{1}{2}:{3}",
+                                ['x', 'm', 'a', 's'].choose(rng).unwrap(),
+                                ['<', '>'].choose(rng).unwrap(),
+                                rng.gen_range(0..=7),
+                                targets.choose(rng).unwrap()
+                            )
+                        })
+                        .collect();
+                    rules.push(targets.choose(rng).unwrap().clone());
+                    format!("{0}{{{1}}}", name, rules.join(","))
+                })
+                .chain([String::new(), String::from("{x=1,m=1,a=1,s=1}")])
+                .collect();
+            let day = parse(lines);
+
+            let min = rng.gen_range(0..=4);
+            let max = rng.gen_range(min - 1..=7);
+            let mut expected: i128 = 0;
+            for x in min..=max {
+                for m in min..=max {
+                    for a in min..=max {
+                        for s in min..=max {
+                            if is_accepted(&day, &Part { x, m, a, s }) {
+                                expected += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            assert_eq!(day.count_accepted_combinations(min, max), expected);
+        }
+    }
+}
@@ -1,13 +1,14 @@
 use std::{
     cell::RefCell,
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     marker::PhantomData,
     rc::Rc,
 };
 
 use super::Day;
-use alex_lib::read_lines;
-use regex::Regex;
+use crate::generate;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum PulseLevel {
@@ -133,7 +134,7 @@ type ModuleMap = HashMap<String, ModuleType>;
 
 #[derive(Debug)]
 pub struct Day20 {
-    input: Vec<String>,
+    input: Input,
     module_map: Rc<RefCell<ModuleMap>>,
     pulse_queue: PulseQueue,
 }
@@ -141,83 +142,96 @@ pub struct Day20 {
 impl Day20 {
     pub fn new() -> Day20 {
         Day20 {
-            input: Vec::new(),
+            input: Input::new(),
             module_map: Rc::new(RefCell::new(HashMap::new())),
             pulse_queue: Rc::new(RefCell::new(VecDeque::new())),
         }
     }
 
-    fn parse_input(&mut self) {
-        let matcher = Regex::new(r"(([%&]?)(\w+)) -> (.*)").unwrap();
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
         self.module_map = Rc::new(RefCell::new(HashMap::new()));
         self.pulse_queue = Rc::new(RefCell::new(VecDeque::new()));
+        let mut errors = ParseErrors::new();
+        let mut defined_modules: HashMap<&str, usize> = HashMap::new();
 
-        for line in self.input.iter() {
-            if let Some(caps) = matcher.captures(line) {
-                let mtype = caps.get(2).unwrap().as_str();
-                let module_name = caps.get(3).unwrap().as_str();
-                let outputs = caps
-                    .get(4)
-                    .unwrap()
-                    .as_str()
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .collect::<Vec<String>>();
-
-                // initialize all dest modules with a simple "output" module
-                for mname in outputs.iter() {
-                    if !self.module_map.borrow().contains_key(mname) {
-                        self.module_map.borrow_mut().insert(
-                            mname.to_string(),
-                            ModuleType::Output(OutputModule {
-                                name: mname.to_string(),
-                                pulse_queue: self.pulse_queue.clone(),
-                            }),
-                        );
-                    }
-                }
-                if module_name == "broadcaster" {
+        // matches: %a -> b, c
+        for mut line in self.input.non_empty_lines() {
+            let (mtype, module_name, outputs) = match errors.check(parse_module(&mut line)) {
+                Some(module) => module,
+                None => continue,
+            };
+            if let Some(line_nr) = defined_modules.insert(module_name, line.line_nr()) {
+                errors.push(line.error_at(
+                    0,
+                    &format!(
+                        "a new module name ('{0}' is already defined in line {1})",
+                        module_name, line_nr
+                    ),
+                ));
+                continue;
+            }
+            let outputs: Vec<String> = outputs.iter().map(|s| s.to_string()).collect();
+
+            // initialize all dest modules with a simple "output" module
+            for mname in outputs.iter() {
+                if !self.module_map.borrow().contains_key(mname) {
                     self.module_map.borrow_mut().insert(
+                        mname.to_string(),
+                        ModuleType::Output(OutputModule {
+                            name: mname.to_string(),
+                            pulse_queue: self.pulse_queue.clone(),
+                        }),
+                    );
+                }
+            }
+            if module_name == "broadcaster" {
+                self.module_map.borrow_mut().insert(
+                    module_name.to_string(),
+                    ModuleType::Broadcast(BroadcastModule {
+                        name: module_name.to_string(),
+                        pulse_queue: self.pulse_queue.clone(),
+                        destinations: outputs.to_vec(),
+                    }),
+                );
+            } else if module_name == "output" {
+                self.module_map.borrow_mut().insert(
+                    module_name.to_string(),
+                    ModuleType::Output(OutputModule {
+                        name: module_name.to_string(),
+                        pulse_queue: self.pulse_queue.clone(),
+                    }),
+                );
+            } else {
+                match mtype {
+                    "%" => self.module_map.borrow_mut().insert(
                         module_name.to_string(),
-                        ModuleType::Broadcast(BroadcastModule {
+                        ModuleType::FlipFlop(FlipFlopModule {
                             name: module_name.to_string(),
                             pulse_queue: self.pulse_queue.clone(),
                             destinations: outputs.to_vec(),
+                            is_on: false,
                         }),
-                    );
-                } else if module_name == "output" {
-                    self.module_map.borrow_mut().insert(
+                    ),
+                    "&" => self.module_map.borrow_mut().insert(
                         module_name.to_string(),
-                        ModuleType::Output(OutputModule {
+                        ModuleType::Conjunction(ConjunctionModule {
                             name: module_name.to_string(),
                             pulse_queue: self.pulse_queue.clone(),
+                            destinations: outputs.to_vec(),
+                            input_states: HashMap::new(),
                         }),
-                    );
-                } else {
-                    match mtype {
-                        "%" => self.module_map.borrow_mut().insert(
-                            module_name.to_string(),
-                            ModuleType::FlipFlop(FlipFlopModule {
-                                name: module_name.to_string(),
-                                pulse_queue: self.pulse_queue.clone(),
-                                destinations: outputs.to_vec(),
-                                is_on: false,
-                            }),
-                        ),
-                        "&" => self.module_map.borrow_mut().insert(
-                            module_name.to_string(),
-                            ModuleType::Conjunction(ConjunctionModule {
-                                name: module_name.to_string(),
-                                pulse_queue: self.pulse_queue.clone(),
-                                destinations: outputs.to_vec(),
-                                input_states: HashMap::new(),
-                            }),
-                        ),
-                        _ => panic!("Unknown module type: {}", mtype),
-                    };
-                }
+                    ),
+                    _ => panic!("Unknown module type: {}", mtype),
+                };
             }
         }
+        if !defined_modules.contains_key("broadcaster") {
+            let end_of_input = self.input.line(self.input.raw_lines().len());
+            errors.push(end_of_input.error("a 'broadcaster' module"));
+        }
+        if !errors.is_empty() {
+            return errors.into_result();
+        }
 
         // configure Conjuncture modules: init all intitial states to LOW for all connected inputs:
         let mut module_dest_map: HashMap<String, Vec<String>> = HashMap::new();
@@ -246,11 +260,8 @@ impl Day20 {
         for (name, destinations) in module_dest_map.iter() {
             for dest in destinations.iter() {
                 let mut mm = self.module_map.borrow_mut();
-                let dst = mm.get_mut(dest);
-                if let None = dst {
-                    panic!("Unknown destination module: {}", dest);
-                }
-                let dest_module = dst.unwrap();
+                // all destinations exist: undefined ones were added as output modules above
+                let dest_module = mm.get_mut(dest).unwrap();
                 if let ModuleType::Conjunction(con_module) = dest_module {
                     con_module
                         .input_states
@@ -258,7 +269,30 @@ impl Day20 {
                 }
             }
         }
+        Ok(())
+    }
+}
+
+/// Parses a module line, like '%a -> b, c': returns the module type ('%', '&',
+/// or empty for the broadcaster / output modules), its name and its destinations.
+fn parse_module<'a>(line: &mut LineParser<'a>) -> ParseResult<(&'a str, &'a str, Vec<&'a str>)> {
+    line.skip_whitespace();
+    let start = line.mark();
+    let mtype = if line.try_literal("%") {
+        "%"
+    } else if line.try_literal("&") {
+        "&"
+    } else {
+        ""
+    };
+    let module_name = line.word()?;
+    if mtype.is_empty() && module_name != "broadcaster" && module_name != "output" {
+        return Err(line.error_at(start, "a '%' or '&' module type"));
     }
+    line.literal("->")?;
+    let outputs = line.separated(",", |p| p.word())?;
+    line.end()?;
+    Ok((mtype, module_name, outputs))
 }
 
 impl Day for Day20 {
@@ -270,11 +304,15 @@ impl Day for Day20 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day20.txt");
-        // let input = read_lines("data/day20-test.txt");
-        // let input = read_lines("data/day20-test2.txt");
+        let input = Input::read("data/day20.txt");
+        // let input = Input::read("data/day20-test.txt");
+        // let input = Input::read("data/day20-test2.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -335,7 +373,7 @@ impl Day for Day20 {
 
     fn solve2(&mut self) -> String {
         // Reset by re-initializing things:
-        self.parse_input();
+        parser::exit_on_error(self.parse_input());
 
         let mut solution: u64 = 0;
         // println!("Modules: {:?}", self.module_map);
@@ -392,3 +430,72 @@ impl Day for Day20 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input with the structure of the real puzzle: the broadcaster starts 2 + size / 10
+/// binary counters of `size` flip-flops each (4 to 62). A counter's conjunction hub resets it
+/// when reaching a random number, so the counter periods grow with the size.
+/// The hubs are inverted and collected by a final conjunction, which sends to rx.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let bits = size.clamp(4, 62);
+    let counters = 2 + size / 10;
+    let mut used: HashSet<String> = HashSet::new();
+    used.insert(String::from("rx"));
+    let chars = "abcdefghijklmnopqrstuvwxyz";
+    // long enough names, so that finding an unused one stays fast:
+    let module_count = counters * (bits + 2) + 1;
+    let mut len = 2;
+    while 26usize.pow(len as u32) < 4 * module_count {
+        len += 1;
+    }
+    let mut name = |rng: &mut StdRng| generate::unique_name(rng, len, chars, &mut used);
+    let collector = name(rng);
+    let mut starts: Vec<String> = Vec::new();
+    let mut modules: Vec<String> = Vec::new();
+    for _ in 0..counters {
+        let flip_flops: Vec<String> = (0..bits).map(|_| name(rng)).collect();
+        let hub = name(rng);
+        let inverter = name(rng);
+        // the counter is reset at this number: the lowest and highest bit are always set
+        let number: u64 = rng.gen_range(1 << (bits - 1)..1 << bits) | 1;
+        let mut hub_outputs: Vec<String> = vec![flip_flops[0].clone(), inverter.clone()];
+        for (bit, flip_flop) in flip_flops.iter().enumerate() {
+            let mut outputs: Vec<String> = Vec::new();
+            if let Some(next) = flip_flops.get(bit + 1) {
+                outputs.push(next.clone());
+            }
+            match number & (1 << bit) != 0 {
+                true => outputs.push(hub.clone()),
+                false => hub_outputs.push(flip_flop.clone()),
+            }
+            outputs.shuffle(rng);
+            modules.push(format!("%{0} -> {1}", flip_flop, outputs.join(", ")));
+        }
+        hub_outputs.shuffle(rng);
+        modules.push(format!("&{0} -> {1}", hub, hub_outputs.join(", ")));
+        modules.push(format!("&{0} -> {1}", inverter, collector));
+        starts.push(flip_flops[0].clone());
+    }
+    modules.push(format!("&{0} -> rx", collector));
+    modules.push(format!("broadcaster -> {0}", starts.join(", ")));
+    modules.shuffle(rng);
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_generated_size() {
+        let rng = &mut StdRng::seed_from_u64(20);
+        for (size, counters, bits) in [(1, 2, 4), (10, 3, 10), (100, 12, 62)] {
+            let lines = generate(size, rng);
+            let flip_flops = lines.iter().filter(|l| l.starts_with('%')).count();
+            assert_eq!(flip_flops, counters * bits);
+            let mut day = Day20::new();
+            day.parse(Input::from_lines("generated", lines)).unwrap();
+            day.solve1();
+        }
+    }
+}
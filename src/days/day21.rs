@@ -1,42 +1,70 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
 use super::Day;
-use alex_lib::{read_lines, types::Coord2d};
+use crate::grid::{Grid, Tile};
+use crate::parser::{self, Input, ParseErrors};
+use alex_lib::types::Coord2d;
 use itertools::Itertools;
+use rand::{rngs::StdRng, Rng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Plot {
+    Garden,
+    Rock,
+}
+
+impl Tile for Plot {
+    const CHARS: &'static str = ".#";
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Plot::Garden),
+            '#' => Some(Plot::Rock),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            Plot::Garden => '.',
+            Plot::Rock => '#',
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Day21 {
-    input: Vec<String>,
-    field: Vec<Vec<char>>,
+    input: Input,
+    field: Grid<Plot>,
     start_pos: Option<Coord2d>,
 }
 
 impl Day21 {
     pub fn new() -> Day21 {
         Day21 {
-            input: Vec::new(),
-            field: Vec::new(),
+            input: Input::new(),
+            field: Grid::from_rows(Vec::new()),
             start_pos: None,
         }
     }
 
-    fn parse_input(&mut self) {
-        for (y, line) in self.input.iter().enumerate() {
-            for (x, c) in line.chars().enumerate() {
-                if c == 'S' {
-                    self.start_pos = Some(Coord2d {
-                        x: x as i64,
-                        y: y as i64,
-                    });
-                }
-            }
-            self.field.push(line.chars().collect());
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        // The start 'S' is a garden plot, too:
+        let start_marker = [('S', Plot::Garden)];
+        if let Some(grid) = Grid::parse(self.input.non_empty_lines(), &start_marker, &mut errors) {
+            self.start_pos = grid.marker('S');
+            self.field = grid;
         }
+        errors.into_result()
     }
 
     fn in_bounds(&self, pos: &Coord2d) -> bool {
-        pos.y >= 0
-            && pos.x >= 0
-            && pos.y < self.field.len() as i64
-            && pos.x < self.field[0].len() as i64
+        self.field.in_bounds(pos)
+    }
+
+    fn is_garden(&self, pos: &Coord2d) -> bool {
+        self.field.get(pos) == Some(Plot::Garden)
     }
 
     fn get_next_pos(&self, pos: &Coord2d) -> Vec<Coord2d> {
@@ -45,19 +73,19 @@ impl Day21 {
             return next_pos;
         }
         let up = pos.up();
-        if self.in_bounds(&up) && self.field[up.y as usize][up.x as usize] != '#' {
+        if self.is_garden(&up) {
             next_pos.push(up);
         }
         let right = pos.right();
-        if self.in_bounds(&right) && self.field[right.y as usize][right.x as usize] != '#' {
+        if self.is_garden(&right) {
             next_pos.push(right);
         }
         let down = pos.down();
-        if self.in_bounds(&down) && self.field[down.y as usize][down.x as usize] != '#' {
+        if self.is_garden(&down) {
             next_pos.push(down);
         }
         let left = pos.left();
-        if self.in_bounds(&left) && self.field[left.y as usize][left.x as usize] != '#' {
+        if self.is_garden(&left) {
             next_pos.push(left);
         }
 
@@ -66,8 +94,8 @@ impl Day21 {
 
     // Wraps around at the field's edges
     fn get_wrapped_coord(&self, pos: &Coord2d) -> Coord2d {
-        let height = self.field.len() as i64;
-        let width = self.field[0].len() as i64;
+        let height = self.field.height() as i64;
+        let width = self.field.width() as i64;
         let mut new_x = pos.x;
         let mut new_y = pos.y;
         // new_x = 10, width = 10 --> new_x = 0
@@ -99,41 +127,82 @@ impl Day21 {
         let mut next_pos: Vec<Coord2d> = Vec::new();
         let up = pos.up();
         let up_wrapped = self.get_wrapped_coord(&up);
-        if self.field[up_wrapped.y as usize][up_wrapped.x as usize] != '#' {
+        if self.is_garden(&up_wrapped) {
             next_pos.push(up);
         }
         let right = pos.right();
         let right_wrapped = self.get_wrapped_coord(&right);
-        if self.field[right_wrapped.y as usize][right_wrapped.x as usize] != '#' {
+        if self.is_garden(&right_wrapped) {
             next_pos.push(right);
         }
         let down = pos.down();
         let down_wrapped = self.get_wrapped_coord(&down);
-        if self.field[down_wrapped.y as usize][down_wrapped.x as usize] != '#' {
+        if self.is_garden(&down_wrapped) {
             next_pos.push(down);
         }
         let left = pos.left();
         let left_wrapped = self.get_wrapped_coord(&left);
-        if self.field[left_wrapped.y as usize][left_wrapped.x as usize] != '#' {
+        if self.is_garden(&left_wrapped) {
             next_pos.push(left);
         }
 
         next_pos
     }
 
-    fn print_field(&self, visited: &Vec<Coord2d>) {
-        for (y, line) in self.field.iter().enumerate() {
-            for (x, c) in line.iter().enumerate() {
-                if visited.contains(&Coord2d {
-                    x: x as i64,
-                    y: y as i64,
-                }) {
-                    print!("O");
-                } else {
-                    print!("{}", c);
+    /// Counts the garden plots that can be reached in exactly `steps` steps:
+    /// We can always walk back and forth, so a plot can be reached if its (shortest)
+    /// distance from the start is at most `steps`, and has the same parity.
+    /// So a single BFS is enough, instead of walking all the positions step by step.
+    /// Only a start without any free neighbour cannot be left, not even to come back.
+    fn count_reachable(&self, steps: u64) -> u64 {
+        let start = self.start_pos.unwrap();
+        if steps > 0 && self.get_next_pos(&start).is_empty() {
+            return 0;
+        }
+        let mut distances: HashMap<Coord2d, u64> = HashMap::from([(start, 0)]);
+        let mut queue: VecDeque<Coord2d> = VecDeque::from([start]);
+        while let Some(pos) = queue.pop_front() {
+            let dist = distances[&pos];
+            if dist == steps {
+                continue;
+            }
+            for next in self.get_next_pos(&pos) {
+                if let Entry::Vacant(entry) = distances.entry(next) {
+                    entry.insert(dist + 1);
+                    queue.push_back(next);
                 }
             }
-            println!()
+        }
+        distances.values().filter(|d| *d % 2 == steps % 2).count() as u64
+    }
+
+    /// The step-by-step version of count_reachable(): keeps all positions reachable
+    /// after each step. Slow, but obviously correct.
+    #[cfg(test)]
+    fn count_reachable_by_walking(&self, steps: u64) -> u64 {
+        let mut working_pos: Vec<Coord2d> = vec![self.start_pos.unwrap()];
+        for _ in 0..steps {
+            let mut next_pos: Vec<Coord2d> = Vec::new();
+            for pos in working_pos.iter() {
+                next_pos.append(&mut self.get_next_pos(pos));
+            }
+            working_pos = next_pos.iter().unique().cloned().collect();
+        }
+        working_pos.len() as u64
+    }
+
+    fn print_field(&self, visited: &Vec<Coord2d>) {
+        for (coord, plot) in self.field.iter() {
+            if visited.contains(&coord) {
+                print!("O");
+            } else if Some(coord) == self.start_pos {
+                print!("S");
+            } else {
+                print!("{}", plot.to_char());
+            }
+            if coord.x as usize == self.field.width() - 1 {
+                println!()
+            }
         }
         println!()
     }
@@ -148,34 +217,18 @@ impl Day for Day21 {
     }
 
     fn prepare(&mut self) {
-        // let input = read_lines("data/day21.txt");
-        let input = read_lines("data/day21-test.txt");
-        self.input = input
-            .iter()
-            .filter(|x| !x.is_empty())
-            .map(|x| x.to_string())
-            .collect();
-        self.parse_input();
+        // let input = Input::read("data/day21.txt");
+        let input = Input::read("data/day21-test.txt");
+        parser::exit_on_error(self.parse(input));
     }
 
-    fn solve1(&mut self) -> String {
-        let mut solution: u64 = 0;
-        let mut working_pos: Vec<Coord2d> = Vec::new();
-        let mut next_pos: Vec<Coord2d> = Vec::new();
-        let steps = 64;
-
-        working_pos.push(self.start_pos.unwrap());
-
-        for i in 0..steps {
-            for pos in working_pos.iter() {
-                next_pos.append(&mut self.get_next_pos(pos));
-            }
-            working_pos = next_pos.iter().unique().cloned().collect();
-            // self.print_field(&working_pos);
-            next_pos = Vec::new();
-        }
-        solution = working_pos.iter().unique().count() as u64;
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
+    }
 
+    fn solve1(&mut self) -> String {
+        let solution = self.count_reachable(64);
         String::from(format!("{0}", solution))
     }
 
@@ -201,3 +254,80 @@ impl Day for Day21 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input with the structure of the real puzzle: a square garden of odd size
+/// (2 * `size` + 1), with the start in the center. The center row and column and the
+/// border are free of rocks.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let len = 2 * size.max(2) + 1;
+    let center = len / 2;
+    let rows: Vec<Vec<Plot>> = (0..len)
+        .map(|y| {
+            (0..len)
+                .map(|x| {
+                    let free = x == 0 || y == 0 || x == len - 1 || y == len - 1;
+                    match free || x == center || y == center || !rng.gen_bool(0.15) {
+                        true => Plot::Garden,
+                        false => Plot::Rock,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let mut lines: Vec<String> = Grid::from_rows(rows)
+        .to_string()
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    lines[center].replace_range(center..center + 1, "S");
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difftest;
+
+    fn parse(input: &[String]) -> Option<Day21> {
+        let mut day = Day21::new();
+        day.input = Input::from_lines("generated", input.to_vec());
+        day.parse_input().ok()?;
+        Some(day)
+    }
+
+    #[test]
+    fn test_count_reachable_example() {
+        // the example from the puzzle text: 16 plots after 6 steps (part 1 takes 64)
+        let mut day = Day21::new();
+        day.input = Input::read("data/day21-test.txt");
+        day.parse_input().unwrap();
+        assert_eq!(day.count_reachable(6), 16);
+    }
+
+    #[test]
+    fn test_bfs_matches_walking() {
+        let reference = |input: &[String]| {
+            let day = parse(input)?;
+            let counts: Vec<u64> = (0..16).map(|n| day.count_reachable_by_walking(n)).collect();
+            Some(counts)
+        };
+        let optimised = |input: &[String]| {
+            let day = parse(input)?;
+            let counts: Vec<u64> = (0..16).map(|n| day.count_reachable(n)).collect();
+            Some(counts)
+        };
+        // sometimes with the start walled in by rocks:
+        let generate = |rng: &mut StdRng| {
+            let mut lines = generate(rng.gen_range(2..=5), rng);
+            if rng.gen_bool(0.2) {
+                let center = lines.len() / 2;
+                for (y, x) in [(0, 1), (1, 0), (1, 2), (2, 1)] {
+                    let (y, x) = (center + y - 1, center + x - 1);
+                    lines[y].replace_range(x..x + 1, "#");
+                }
+            }
+            lines
+        };
+        difftest::assert_agree(50, generate, reference, optimised);
+    }
+}
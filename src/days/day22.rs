@@ -1,17 +1,24 @@
+use std::collections::HashSet;
+
 use super::Day;
-use alex_lib::read_lines;
+use crate::parser::{self, Input, ParseErrors};
+use rand::{rngs::StdRng, Rng};
 
 #[derive(Debug)]
 pub struct Day22 {
-    input: Vec<String>,
+    input: Input,
 }
 
 impl Day22 {
     pub fn new() -> Day22 {
-        Day22 { input: Vec::new() }
+        Day22 {
+            input: Input::new(),
+        }
     }
 
-    fn parse_input(&mut self) {}
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        Ok(())
+    }
 }
 
 impl Day for Day22 {
@@ -23,10 +30,14 @@ impl Day for Day22 {
     }
 
     fn prepare(&mut self) {
-        // let input = read_lines("data/day22.txt");
-        let input = read_lines("data/day22-test.txt");
+        // let input = Input::read("data/day22.txt");
+        let input = Input::read("data/day22-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -39,3 +50,37 @@ impl Day for Day22 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input: `size` bricks in a 10 x 10 area, that do not overlap
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let mut occupied: HashSet<(i64, i64, i64)> = HashSet::new();
+    let mut lines: Vec<String> = Vec::new();
+    let max_z = (size as i64 * 2).max(5);
+    while lines.len() < size {
+        let start = (
+            rng.gen_range(0..10),
+            rng.gen_range(0..10),
+            rng.gen_range(1..=max_z),
+        );
+        let len = rng.gen_range(0..4);
+        let end = match rng.gen_range(0..3) {
+            0 => ((start.0 + len).min(9), start.1, start.2),
+            1 => (start.0, (start.1 + len).min(9), start.2),
+            _ => (start.0, start.1, start.2 + len),
+        };
+        let cubes: Vec<(i64, i64, i64)> = (start.0..=end.0)
+            .flat_map(|x| {
+                (start.1..=end.1).flat_map(move |y| (start.2..=end.2).map(move |z| (x, y, z)))
+            })
+            .collect();
+        if cubes.iter().any(|c| occupied.contains(c)) {
+            continue;
+        }
+        occupied.extend(cubes);
+        lines.push(format!(
+            "{0},{1},{2}~{3},{4},{5}",
+            start.0, start.1, start.2, end.0, end.1, end.2
+        ));
+    }
+    lines
+}
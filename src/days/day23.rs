@@ -6,28 +6,55 @@ use std::{
 };
 
 use super::Day;
-use alex_lib::{
-    read_lines,
-    types::{Coord2d, Coord2dMap},
-};
+use crate::grid::{Grid, Tile};
+use crate::parser::{self, Input, LineParser, ParseErrors};
+use alex_lib::types::{Coord2d, Coord2dMap, Direction};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 
 #[derive(Debug, Clone, Copy)]
 enum FieldType {
     Forest,
     Path,
-    Slope(char),
+    // a slope can only be walked down in its direction
+    Slope(Direction),
+}
+
+impl Tile for FieldType {
+    const CHARS: &'static str = "#.^>v<";
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '#' => Some(FieldType::Forest),
+            '.' => Some(FieldType::Path),
+            '^' => Some(FieldType::Slope(Direction::UP)),
+            '>' => Some(FieldType::Slope(Direction::RIGHT)),
+            'v' => Some(FieldType::Slope(Direction::DOWN)),
+            '<' => Some(FieldType::Slope(Direction::LEFT)),
+            _ => None,
+        }
+    }
+
+    fn to_char(&self) -> char {
+        match self {
+            FieldType::Forest => '#',
+            FieldType::Path => '.',
+            FieldType::Slope(Direction::UP) => '^',
+            FieldType::Slope(Direction::RIGHT) => '>',
+            FieldType::Slope(Direction::DOWN) => 'v',
+            FieldType::Slope(Direction::LEFT) => '<',
+        }
+    }
 }
 
 #[derive(Debug)]
 struct FieldInfo {
-    chr: char,
     field_type: FieldType,
     coord: Coord2d,
 }
 
 #[derive(Debug)]
 pub struct Day23 {
-    input: Vec<String>,
+    input: Input,
     field: Coord2dMap<Rc<RefCell<FieldInfo>>>,
     start: Option<Coord2d>,
     end: Option<Coord2d>,
@@ -37,7 +64,7 @@ pub struct Day23 {
 impl Day23 {
     pub fn new() -> Day23 {
         Day23 {
-            input: Vec::new(),
+            input: Input::new(),
             field: Coord2dMap::new(),
             start: None,
             end: None,
@@ -45,46 +72,46 @@ impl Day23 {
         }
     }
 
-    fn parse_input(&mut self) {
-        for (y, line) in self.input.iter().enumerate() {
-            for (x, c) in line.chars().enumerate() {
-                let coord = Coord2d {
-                    x: x as i64,
-                    y: y as i64,
-                };
-                // Detect start position:
-                if y == 0 && c == '.' {
-                    self.start = Some(coord);
-                }
-                // Detect end position:
-                if y == self.input.len() - 1 && c == '.' {
-                    self.end = Some(coord);
-                }
-                self.field.insert(
-                    coord,
-                    Rc::new(RefCell::new(FieldInfo {
-                        chr: c,
-                        field_type: match c {
-                            '.' => FieldType::Path,
-                            '#' => FieldType::Forest,
-                            _ => FieldType::Slope(c),
-                        },
-                        coord,
-                    })),
-                );
-            }
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        let lines: Vec<LineParser> = self.input.non_empty_lines().collect();
+        let grid = Grid::<FieldType>::parse(lines.iter().cloned(), &[], &mut errors);
+
+        // Detect start / end position: the single path in the first / last row
+        let first_row = lines.first().cloned().into_iter();
+        if let Some((x, _)) = parser::find_unique_marker(first_row, '.', &mut errors) {
+            self.start = Some(Coord2d { x: x as i64, y: 0 });
+        }
+        let last_row = lines.last().cloned().into_iter();
+        if let Some((x, _)) = parser::find_unique_marker(last_row, '.', &mut errors) {
+            self.end = Some(Coord2d {
+                x: x as i64,
+                y: lines.len() as i64 - 1,
+            });
+        }
+        let grid = match grid {
+            Some(grid) if errors.is_empty() => grid,
+            _ => return errors.into_result(),
+        };
+
+        for (coord, field_type) in grid.iter() {
+            self.field.insert(
+                coord,
+                Rc::new(RefCell::new(FieldInfo { field_type, coord })),
+            );
         }
+        Ok(())
     }
 
     fn print_field(&self) {
-        for y in 0..self.input.len() {
-            for x in 0..self.input[0].len() {
+        for (y, line) in self.input.non_empty_lines().enumerate() {
+            for x in 0..line.remaining().trim().chars().count() {
                 let coord = Coord2d {
                     x: x as i64,
                     y: y as i64,
                 };
                 let field = self.field.get(&coord).unwrap().as_ref().borrow();
-                print!("{}", field.chr);
+                print!("{}", field.field_type.to_char());
             }
             println!()
         }
@@ -113,12 +140,11 @@ impl Day23 {
                     next_coords.push(act_node.coord.down());
                     next_coords.push(act_node.coord.left());
                 }
-                FieldType::Slope(s) => match s {
-                    '>' => next_coords.push(act_node.coord.right()),
-                    '^' => next_coords.push(act_node.coord.up()),
-                    '<' => next_coords.push(act_node.coord.left()),
-                    'v' => next_coords.push(act_node.coord.down()),
-                    _ => panic!("Unknown slope type: {}", s),
+                FieldType::Slope(dir) => match dir {
+                    Direction::RIGHT => next_coords.push(act_node.coord.right()),
+                    Direction::UP => next_coords.push(act_node.coord.up()),
+                    Direction::LEFT => next_coords.push(act_node.coord.left()),
+                    Direction::DOWN => next_coords.push(act_node.coord.down()),
                 },
                 _ => {
                     panic!("Unsupported field type: {:?}", act_node.field_type)
@@ -130,7 +156,7 @@ impl Day23 {
             if let Some(node) = self.field.get(&coord) {
                 // add node to the next visitable list of nodes, if not yet visited:
                 let n = (*node.as_ref()).borrow();
-                if !visited.contains(&coord) && n.chr != '#' {
+                if !visited.contains(&coord) && !matches!(n.field_type, FieldType::Forest) {
                     res.push(Rc::clone(&node));
                 }
             }
@@ -184,8 +210,7 @@ impl Day23 {
         //     }
         // }
         let mut max = 0;
-        next_nodes =
-            self.find_unvisited_neighbour_nodes(field, &my_visited, treat_slopes_as_path);
+        next_nodes = self.find_unvisited_neighbour_nodes(field, &my_visited, treat_slopes_as_path);
         for n in next_nodes {
             max = std::cmp::max(
                 max,
@@ -212,14 +237,14 @@ impl Day for Day23 {
     }
 
     fn prepare(&mut self) {
-        // let input = read_lines("data/day23.txt");
-        let input = read_lines("data/day23-test.txt");
-        self.input = input
-            .iter()
-            .map(|x| x.trim().to_string())
-            .filter(|x| !x.is_empty())
-            .collect();
-        self.parse_input();
+        // let input = Input::read("data/day23.txt");
+        let input = Input::read("data/day23-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
+        self.input = input;
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -265,3 +290,119 @@ impl Day for Day23 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// The direction of a step from one neighbour to the other
+fn step_direction(from: (usize, usize), to: (usize, usize)) -> Direction {
+    match (to.0 as i64 - from.0 as i64, to.1 as i64 - from.1 as i64) {
+        (0, -1) => Direction::UP,
+        (1, 0) => Direction::RIGHT,
+        (0, 1) => Direction::DOWN,
+        _ => Direction::LEFT,
+    }
+}
+
+/// Random input with the structure of the real puzzle: a maze of `size` x `size` cells with
+/// some loops, entered in the top left and left in the bottom right corner.
+/// All paths around junctions are slopes, pointing away from the entry.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let cells = size.max(2);
+    let len = 2 * cells + 1;
+    let mut field = vec![vec![FieldType::Forest; len]; len];
+    let neighbours = |(x, y): (usize, usize), max: usize| -> Vec<(usize, usize)> {
+        let mut res = Vec::new();
+        if y > 0 {
+            res.push((x, y - 1));
+        }
+        if x + 1 < max {
+            res.push((x + 1, y));
+        }
+        if y + 1 < max {
+            res.push((x, y + 1));
+        }
+        if x > 0 {
+            res.push((x - 1, y));
+        }
+        res
+    };
+
+    // carve the maze with a randomized dfs: cell (x, y) is the tile (2x + 1, 2y + 1)
+    let mut visited: HashSet<(usize, usize)> = HashSet::new();
+    let mut stack = vec![(0, 0)];
+    visited.insert((0, 0));
+    field[1][1] = FieldType::Path;
+    while let Some(cell) = stack.last().copied() {
+        let next: Vec<(usize, usize)> = neighbours(cell, cells)
+            .into_iter()
+            .filter(|n| !visited.contains(n))
+            .collect();
+        match next.choose(rng) {
+            Some(&(nx, ny)) => {
+                field[cell.1 + ny + 1][cell.0 + nx + 1] = FieldType::Path;
+                field[2 * ny + 1][2 * nx + 1] = FieldType::Path;
+                visited.insert((nx, ny));
+                stack.push((nx, ny));
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    // some more openings, for loops:
+    for _ in 0..cells {
+        let (x, y) = (rng.gen_range(1..len - 1), rng.gen_range(1..len - 1));
+        if (x + y) % 2 == 1 {
+            field[y][x] = FieldType::Path;
+        }
+    }
+    field[0][1] = FieldType::Path;
+    field[len - 1][len - 2] = FieldType::Path;
+
+    // bfs distances from the start, to point the slopes away from it:
+    let is_path = |field: &Vec<Vec<FieldType>>, (x, y): (usize, usize)| {
+        !matches!(field[y][x], FieldType::Forest)
+    };
+    let mut dist: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([(1, 0)]);
+    dist.insert((1, 0), 0);
+    while let Some(pos) = queue.pop_front() {
+        for n in neighbours(pos, len) {
+            if is_path(&field, n) && !dist.contains_key(&n) {
+                dist.insert(n, dist[&pos] + 1);
+                queue.push_back(n);
+            }
+        }
+    }
+    let mut slopes: HashMap<(usize, usize), Option<Direction>> = HashMap::new();
+    for (&pos, d) in dist.iter() {
+        let paths: Vec<(usize, usize)> = neighbours(pos, len)
+            .into_iter()
+            .filter(|n| is_path(&field, *n))
+            .collect();
+        if paths.len() < 3 {
+            continue;
+        }
+        // start and end stay paths: they are found by their '.'
+        for n in paths.into_iter().filter(|n| n.1 != 0 && n.1 != len - 1) {
+            let dir = match dist[&n] > *d {
+                true => step_direction(pos, n),
+                false => step_direction(n, pos),
+            };
+            // a path between two junctions may need to point both ways: keep it a path then
+            let slope = slopes.entry(n).or_insert(Some(dir));
+            if *slope != Some(dir) {
+                *slope = None;
+            }
+        }
+    }
+    for ((x, y), slope) in slopes {
+        if let Some(dir) = slope {
+            field[y][x] = FieldType::Slope(dir);
+        }
+    }
+
+    Grid::from_rows(field)
+        .to_string()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
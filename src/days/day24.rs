@@ -1,6 +1,8 @@
+use std::collections::HashSet;
+
 use super::Day;
-use alex_lib::read_lines;
-use regex::Regex;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, Rng};
 
 type Point = (f64, f64, f64);
 type Velocivy = (f64, f64, f64);
@@ -13,38 +15,26 @@ struct Particle {
 
 #[derive(Debug)]
 pub struct Day24 {
-    input: Vec<String>,
+    input: Input,
     particles: Vec<Particle>,
 }
 
 impl Day24 {
     pub fn new() -> Day24 {
         Day24 {
-            input: Vec::new(),
+            input: Input::new(),
             particles: Vec::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let matcher = Regex::new(
-            r"([0-9-.]+),\s+([0-9-.]+),\s+([0-9-.]+)\s+@\s+([0-9-.]+),\s+([0-9-.]+),\s+([0-9-.]+)",
-        )
-        .unwrap();
-
-        for line in self.input.iter() {
-            if let Some(caps) = matcher.captures(line) {
-                let x = caps[1].parse::<f64>().unwrap();
-                let y = caps[2].parse::<f64>().unwrap();
-                let z = caps[3].parse::<f64>().unwrap();
-                let vx = caps[4].parse::<f64>().unwrap();
-                let vy = caps[5].parse::<f64>().unwrap();
-                let vz = caps[6].parse::<f64>().unwrap();
-                self.particles.push(Particle {
-                    position: (x, y, z),
-                    velocity: (vx, vy, vz),
-                });
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        for mut line in self.input.non_empty_lines() {
+            if let Some(particle) = errors.check(parse_particle(&mut line)) {
+                self.particles.push(particle);
             }
         }
+        errors.into_result()
     }
 
     fn calc_intersection_2d(&self, p1: &Particle, p2: &Particle) -> Point {
@@ -86,6 +76,25 @@ impl Day24 {
     }
 }
 
+/// Parses lines like '19, 13, 30 @ -2,  1, -2'
+fn parse_particle(line: &mut LineParser) -> ParseResult<Particle> {
+    let position = parse_triple(line)?;
+    line.literal("@")?;
+    let velocity = parse_triple(line)?;
+    line.end()?;
+    Ok(Particle { position, velocity })
+}
+
+/// Parses 3 comma-separated integers, like '19, 13, 30'
+fn parse_triple(line: &mut LineParser) -> ParseResult<(f64, f64, f64)> {
+    let x = line.number::<i64>()?;
+    line.literal(",")?;
+    let y = line.number::<i64>()?;
+    line.literal(",")?;
+    let z = line.number::<i64>()?;
+    Ok((x as f64, y as f64, z as f64))
+}
+
 impl Day for Day24 {
     fn day_nr(&self) -> String {
         String::from("24")
@@ -95,10 +104,14 @@ impl Day for Day24 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day24.txt");
-        // let input = read_lines("data/day24-test.txt");
+        let input = Input::read("data/day24.txt");
+        // let input = Input::read("data/day24-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
     fn solve1(&mut self) -> String {
@@ -136,3 +149,28 @@ impl Day for Day24 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input: `size` (at least 3) hailstones, all hit by a single rock thrown from a random
+/// position: hailstone i is at the rock's position at time t_i, so it starts at
+/// rock + (rock_velocity - velocity_i) * t_i.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let rock = [(); 3].map(|_| rng.gen_range(100_000_000_000_000_i64..400_000_000_000_000));
+    let rock_velocity = [(); 3].map(|_| rng.gen_range(-300_i64..=300));
+    let mut times: HashSet<i64> = HashSet::new();
+    let mut lines: Vec<String> = Vec::new();
+    while lines.len() < size.max(3) {
+        let time = rng.gen_range(1_000_000_000..100_000_000_000);
+        if !times.insert(time) {
+            continue;
+        }
+        let v = [(); 3].map(|_| rng.gen_range(-300_i64..=300));
+        let p: Vec<i64> = (0..3)
+            .map(|i| rock[i] + (rock_velocity[i] - v[i]) * time)
+            .collect();
+        lines.push(format!(
+            "{0}, {1}, {2} @ {3}, {4}, {5}",
+            p[0], p[1], p[2], v[0], v[1], v[2]
+        ));
+    }
+    lines
+}
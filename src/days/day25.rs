@@ -1,9 +1,13 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    rc::Rc,
+};
 
 use super::Day;
-use alex_lib::read_lines;
-use itertools::Itertools;
-use regex::Regex;
+use crate::generate;
+use crate::parser::{self, Input, LineParser, ParseErrors, ParseResult};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 
 #[derive(Debug, Eq)]
 struct Wire {
@@ -25,9 +29,123 @@ struct Component {
     graph_nr: u64,
 }
 
+/// The result of a minimum cut search: the wires that disconnect the graph.
+#[derive(Debug)]
+struct MinCut {
+    wires: Vec<(String, String)>,
+}
+
+/// An index-based copy of the wire graph, used for the max-flow / min-cut calculation:
+/// Each component is a node index, each wire an undirected edge with capacity 1
+/// in both directions.
+#[derive(Debug)]
+struct FlowGraph {
+    names: Vec<String>,
+    edges: Vec<(usize, usize)>,
+    // per node: list of (edge index, neighbour node index)
+    adjacency: Vec<Vec<(usize, usize)>>,
+}
+
+impl FlowGraph {
+    /// Builds the flow graph from the parsed components and wires.
+    /// Nodes and edges are sorted by name, so the result is deterministic.
+    fn new(
+        components: &HashMap<String, Rc<RefCell<Component>>>,
+        wires: &HashMap<(String, String), Rc<RefCell<Wire>>>,
+    ) -> FlowGraph {
+        let mut names: Vec<String> = components.keys().cloned().collect();
+        names.sort();
+        let indexes: HashMap<&String, usize> =
+            names.iter().enumerate().map(|(i, n)| (n, i)).collect();
+
+        let mut keys: Vec<&(String, String)> = wires.keys().collect();
+        keys.sort();
+        let mut edges = Vec::new();
+        let mut adjacency = vec![Vec::new(); names.len()];
+        for (cmp1, cmp2) in keys {
+            let a = *indexes.get(cmp1).unwrap();
+            let b = *indexes.get(cmp2).unwrap();
+            adjacency[a].push((edges.len(), b));
+            adjacency[b].push((edges.len(), a));
+            edges.push((a, b));
+        }
+
+        FlowGraph {
+            names,
+            edges,
+            adjacency,
+        }
+    }
+
+    /// Remaining capacity of an edge when traversed starting at node `from`.
+    /// A positive flow value means flow from edges[e].0 to edges[e].1.
+    fn residual(&self, edge: usize, from: usize, flow: &[i64]) -> i64 {
+        if self.edges[edge].0 == from {
+            1 - flow[edge]
+        } else {
+            1 + flow[edge]
+        }
+    }
+
+    /// Breadth-first search over all edges with remaining capacity, starting at `source`.
+    /// Returns the reached nodes, and for each reached node the (edge, predecessor node)
+    /// it was reached by, so that the shortest augmenting path can be followed back.
+    fn residual_bfs(
+        &self,
+        source: usize,
+        flow: &[i64],
+    ) -> (Vec<bool>, Vec<Option<(usize, usize)>>) {
+        let mut reached = vec![false; self.names.len()];
+        let mut pred = vec![None; self.names.len()];
+        let mut queue = VecDeque::new();
+        reached[source] = true;
+        queue.push_back(source);
+        while let Some(node) = queue.pop_front() {
+            for (edge, next) in self.adjacency[node].iter() {
+                if !reached[*next] && self.residual(*edge, node, flow) > 0 {
+                    reached[*next] = true;
+                    pred[*next] = Some((*edge, node));
+                    queue.push_back(*next);
+                }
+            }
+        }
+        (reached, pred)
+    }
+
+    /// Edmonds-Karp: Calculates the minimum cut between `source` and `sink`,
+    /// but gives up as soon as the flow reaches `limit` (a cut that big is not interesting any more).
+    /// Returns the cut size and the nodes on the source side of the cut.
+    fn min_st_cut(&self, source: usize, sink: usize, limit: usize) -> Option<(usize, Vec<bool>)> {
+        let mut flow = vec![0; self.edges.len()];
+        let mut flow_value = 0;
+        loop {
+            let (reached, pred) = self.residual_bfs(source, &flow);
+            if !reached[sink] {
+                // no more augmenting path: the reached nodes form the source side of the cut
+                return Some((flow_value, reached));
+            }
+            if flow_value + 1 >= limit {
+                return None;
+            }
+            // follow the augmenting path back from the sink, and push one unit of flow along it:
+            let mut node = sink;
+            while node != source {
+                let (edge, prev) = pred[node].unwrap();
+                if self.edges[edge].0 == prev {
+                    flow[edge] += 1;
+                } else {
+                    flow[edge] -= 1;
+                }
+                node = prev;
+            }
+            flow_value += 1;
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Day25 {
-    input: Vec<String>,
+    input: Input,
     wires: HashMap<(String, String), Rc<RefCell<Wire>>>,
     components: HashMap<String, Rc<RefCell<Component>>>,
 }
@@ -35,56 +153,58 @@ pub struct Day25 {
 impl Day25 {
     pub fn new() -> Day25 {
         Day25 {
-            input: Vec::new(),
+            input: Input::new(),
             wires: HashMap::new(),
             components: HashMap::new(),
         }
     }
 
-    fn parse_input(&mut self) {
-        let matcher = Regex::new(r"(\w+):\s+(.*)").unwrap();
-        for line in self.input.iter() {
-            if let Some(caps) = matcher.captures(line) {
-                let cmp_name = caps[1].to_string();
-                let targets: Vec<String> =
-                    caps[2].split(' ').map(|s| s.trim().to_string()).collect();
-                let cmp = Component {
-                    visited: false,
-                    wires: HashMap::new(),
-                    graph_nr: 0,
-                };
-                self.components
-                    .insert(cmp_name.clone(), Rc::new(RefCell::new(cmp)));
-                for target in targets {
-                    // The cmps in the wire are ordered by alphabet:
-                    // 1st wire is always the one first in the alphabet, this makes
-                    // recognizing easier
-                    if let None = self.components.get(&target) {
-                        self.components.insert(
-                            target.to_string(),
-                            Rc::new(RefCell::new(Component {
-                                visited: false,
-                                wires: HashMap::new(),
-                                graph_nr: 0,
-                            })),
-                        );
-                    }
-                    let wire = Wire {
-                        cmp1: match cmp_name < target {
-                            true => cmp_name.clone(),
-                            false => target.clone(),
-                        },
-                        cmp2: match cmp_name < target {
-                            true => target.clone(),
-                            false => cmp_name.clone(),
-                        },
-                        active: true,
-                    };
-                    self.wires.insert(
-                        (wire.cmp1.clone(), wire.cmp2.clone()),
-                        Rc::new(RefCell::new(wire)),
+    fn parse_input(&mut self) -> Result<(), ParseErrors> {
+        let mut errors = ParseErrors::new();
+        let connections: Vec<(&str, Vec<&str>)> = self
+            .input
+            .non_empty_lines()
+            .filter_map(|mut line| errors.check(parse_connections(&mut line)))
+            .collect();
+        for (cmp_name, targets) in connections {
+            let cmp_name = cmp_name.to_string();
+            let targets: Vec<String> = targets.iter().map(|s| s.to_string()).collect();
+            let cmp = Component {
+                visited: false,
+                wires: HashMap::new(),
+                graph_nr: 0,
+            };
+            self.components
+                .insert(cmp_name.clone(), Rc::new(RefCell::new(cmp)));
+            for target in targets {
+                // The cmps in the wire are ordered by alphabet:
+                // 1st wire is always the one first in the alphabet, this makes
+                // recognizing easier
+                if let None = self.components.get(&target) {
+                    self.components.insert(
+                        target.to_string(),
+                        Rc::new(RefCell::new(Component {
+                            visited: false,
+                            wires: HashMap::new(),
+                            graph_nr: 0,
+                        })),
                     );
                 }
+                let wire = Wire {
+                    cmp1: match cmp_name < target {
+                        true => cmp_name.clone(),
+                        false => target.clone(),
+                    },
+                    cmp2: match cmp_name < target {
+                        true => target.clone(),
+                        false => cmp_name.clone(),
+                    },
+                    active: true,
+                };
+                self.wires.insert(
+                    (wire.cmp1.clone(), wire.cmp2.clone()),
+                    Rc::new(RefCell::new(wire)),
+                );
             }
         }
 
@@ -105,6 +225,7 @@ impl Day25 {
                 .wires
                 .insert(key.clone(), Rc::clone(wire));
         }
+        errors.into_result()
     }
 
     fn reset(&self) {
@@ -182,18 +303,76 @@ impl Day25 {
         graph_counts
     }
 
-    fn print_wires(&self) {
-        println!("// Graphviz Graph - Print it with sfdp, undirected large graph:");
-        println!("graph G {{");
-        for wire in self.wires.values() {
-            println!(
-                "{0} -- {1};",
-                wire.as_ref().borrow().cmp1,
-                wire.as_ref().borrow().cmp2
-            );
+    /// Finds the global minimum cut of the wire graph:
+    /// The first node must be on one side of every cut, so we calculate the
+    /// minimum s-t cut from the first node to every other node, and keep the smallest one.
+    /// Returns None if there are less than 2 components.
+    fn find_min_cut(&self) -> Option<MinCut> {
+        let graph = FlowGraph::new(&self.components, &self.wires);
+        if graph.names.len() < 2 {
+            return None;
+        }
+
+        let mut best: Option<(usize, Vec<bool>)> = None;
+        for sink in 1..graph.names.len() {
+            let limit = match &best {
+                Some((size, _)) => *size,
+                None => usize::MAX,
+            };
+            if let Some(cut) = graph.min_st_cut(0, sink, limit) {
+                best = Some(cut);
+            }
+        }
+
+        let (_, source_side) = best.unwrap();
+        let wires = graph
+            .edges
+            .iter()
+            .filter(|(a, b)| source_side[*a] != source_side[*b])
+            .map(|(a, b)| (graph.names[*a].clone(), graph.names[*b].clone()))
+            .collect();
+        Some(MinCut { wires })
+    }
+
+    /// The brute-force way to part 1: disable each triple of wires, and check if the
+    /// graph falls apart in 2 groups. Way too slow for the real input, but obviously correct,
+    /// so we use it to verify find_min_cut(). Returns the product of the group sizes for each
+    /// splitting triple.
+    #[cfg(test)]
+    fn find_splitting_triples(&self) -> Vec<u64> {
+        let mut keys: Vec<&(String, String)> = self.wires.keys().collect();
+        keys.sort();
+        let mut products = Vec::new();
+        for (i, wire1) in keys.iter().enumerate() {
+            for (j, wire2) in keys.iter().enumerate().skip(i + 1) {
+                for wire3 in keys.iter().skip(j + 1) {
+                    self.reset();
+                    for wire in [wire1, wire2, wire3] {
+                        self.wires[*wire].as_ref().borrow_mut().active = false;
+                    }
+                    let graph_counts = self.count_graphs();
+                    if graph_counts.len() == 2 {
+                        products.push(graph_counts.iter().product());
+                    }
+                }
+            }
         }
-        println!("}}");
+        self.reset();
+        products
+    }
+}
+
+/// Parses lines like 'jqt: rhn xhk nvd'
+fn parse_connections<'a>(line: &mut LineParser<'a>) -> ParseResult<(&'a str, Vec<&'a str>)> {
+    let name = line.word()?;
+    line.literal(":")?;
+    let mut targets = vec![line.word()?];
+    line.skip_whitespace();
+    while !line.remaining().is_empty() {
+        targets.push(line.word()?);
+        line.skip_whitespace();
     }
+    Ok((name, targets))
 }
 
 impl Day for Day25 {
@@ -205,101 +384,54 @@ impl Day for Day25 {
     }
 
     fn prepare(&mut self) {
-        let input = read_lines("data/day25.txt");
-        // let input = read_lines("data/day25-test.txt");
+        let input = Input::read("data/day25.txt");
+        // let input = Input::read("data/day25-test.txt");
+        parser::exit_on_error(self.parse(input));
+    }
+
+    fn parse(&mut self, input: Input) -> Result<(), ParseErrors> {
         self.input = input;
-        self.parse_input();
+        self.parse_input()
     }
 
-    /// For part 1, I just drawn the graph, and solved it visually:
+    /// For part 1, I first just drawn the graph, and solved it visually:
     /// I printed the wires with print_wires() as unidirected graphviz graph using sfdp,
-    /// then just looked at the image.
-    /// 
-    /// Calculating take way toooooo long!
-    /// Note that my solution only works with my input!
+    /// then just looked at the image. Trying all wire triples took way toooooo long!
+    ///
+    /// Now the wires are found with a real minimum cut algorithm (Edmonds-Karp max flow,
+    /// see find_min_cut()), so this works for any input. The cut wires are then disabled,
+    /// and the remaining graphs are counted by walking them.
+    ///
+    /// The wires must connect all components, and the minimum cut must be exactly 3 wires:
+    /// otherwise, there is no (single) way to split the graph with 3 wires.
     fn solve1(&mut self) -> String {
-        let mut solution: u64 = 0;
-        self.print_wires();
-
-        /** Visual solution: */
-        // Visually identified wires to cut from real set:
-        // jff-zns
-        // fts-nvb
-        // kzx-qmr
+        let cut = match self.find_min_cut() {
+            Some(cut) => cut,
+            None => return String::from("no cut: less than 2 components"),
+        };
         self.reset();
-        self.wires
-            .get(&("jff".to_string(), "zns".to_string()))
-            .unwrap()
-            .as_ref()
-            .borrow_mut()
-            .active = false;
-        self.wires
-            .get(&("fts".to_string(), "nvb".to_string()))
-            .unwrap()
-            .as_ref()
-            .borrow_mut()
-            .active = false;
-        self.wires
-            .get(&("kzx".to_string(), "qmr".to_string()))
-            .unwrap()
-            .as_ref()
-            .borrow_mut()
-            .active = false;
-            
-        
-        // Demo set:
-        // Test: disconnect 3 fixed wires from the example:
-        // self.wires
-        //     .get(&("hfx".to_string(), "pzl".to_string()))
-        //     .unwrap()
-        //     .as_ref()
-        //     .borrow_mut()
-        //     .active = false;
-        // self.wires
-        //     .get(&("bvb".to_string(), "cmg".to_string()))
-        //     .unwrap()
-        //     .as_ref()
-        //     .borrow_mut()
-        //     .active = false;
-        // self.wires
-        //     .get(&("jqt".to_string(), "nvd".to_string()))
-        //     .unwrap()
-        //     .as_ref()
-        //     .borrow_mut()
-        //     .active = false;
+        if self.count_graphs().len() != 1 {
+            return String::from("no cut: the components are not all connected");
+        }
+        if cut.wires.len() != 3 {
+            return format!(
+                "no cut: the minimum cut is not 3 wires, but {0}",
+                cut.wires.len()
+            );
+        }
+
+        self.reset();
+        for wire in cut.wires.iter() {
+            self.wires.get(wire).unwrap().as_ref().borrow_mut().active = false;
+        }
+
         // now, start walking the graphs:
         let graph_counts = self.count_graphs();
         let nr_of_graphs = graph_counts.len();
-        solution = graph_counts.iter().product::<u64>();
+        let solution = graph_counts.iter().product::<u64>();
 
         println!("Nr of graphs: {0}", nr_of_graphs);
         println!("Graph sizes: {:?}", graph_counts);
-        /**/
-
-        /*
-        // Disconnect 3 wires, reset, walk, and count:
-        // If we have only 2 graphs, we found our solution:
-        // This solution takes too long!
-        let wires = self.wires.values().collect_vec();
-        'outer: for i1 in 0..wires.len() - 2 {
-            for i2 in i1 + 1..wires.len() - 1 {
-                for i3 in i2 + 1..wires.len() {
-                    // println!("Testing: {0}, {1}, {2}", i1, i2, i3);
-                    self.reset();
-                    wires[i1].as_ref().borrow_mut().active = false;
-                    wires[i2].as_ref().borrow_mut().active = false;
-                    wires[i3].as_ref().borrow_mut().active = false;
-                    let graph_counts = self.count_graphs();
-                    let nr_of_graphs = graph_counts.len();
-                    if nr_of_graphs == 2 {
-                        solution = graph_counts.iter().product::<u64>();
-                        break 'outer;
-                    }
-                }
-            }
-        }
-        */
-
 
         String::from(format!("{0}", solution))
     }
@@ -309,3 +441,130 @@ impl Day for Day25 {
         String::from(format!("{0}", solution))
     }
 }
+
+/// Random input: two groups of `size` (at least 5) components, each group well connected
+/// (at least 4 wires needed to split it), and exactly 3 wires connecting both groups.
+/// Each wire is listed once, at one of its two components.
+pub fn generate(size: usize, rng: &mut StdRng) -> Vec<String> {
+    let group_size = size.max(5);
+    let mut used: HashSet<String> = HashSet::new();
+    let names: Vec<String> = (0..2 * group_size)
+        .map(|_| generate::unique_name(rng, 3, "abcdefghijklmnopqrstuvwxyz", &mut used))
+        .collect();
+    let mut wires: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for group in 0..2 {
+        let nodes: Vec<usize> = (group * group_size..(group + 1) * group_size).collect();
+        for (i, a) in nodes.iter().enumerate() {
+            // each component is wired to its 2 next ones in a ring, plus some random ones:
+            let mut targets = vec![nodes[(i + 1) % group_size], nodes[(i + 2) % group_size]];
+            targets.extend(nodes.choose_multiple(rng, 2));
+            for b in targets.into_iter().filter(|b| b != a) {
+                wires.insert((*a.min(&b), *a.max(&b)));
+            }
+        }
+    }
+    let left: Vec<usize> = (0..group_size).collect();
+    let right: Vec<usize> = (group_size..2 * group_size).collect();
+    let bridges = left
+        .choose_multiple(rng, 3)
+        .zip(right.choose_multiple(rng, 3));
+    wires.extend(bridges.map(|(a, b)| (*a, *b)));
+
+    let mut listed: BTreeMap<usize, Vec<&String>> = BTreeMap::new();
+    for (a, b) in wires {
+        match rng.gen_bool(0.5) {
+            true => listed.entry(a).or_default().push(&names[b]),
+            false => listed.entry(b).or_default().push(&names[a]),
+        }
+    }
+    let mut lines: Vec<String> = listed
+        .iter()
+        .map(|(c, targets)| format!("{0}: {1}", names[*c], generate::join(targets, " ")))
+        .collect();
+    lines.shuffle(rng);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difftest;
+
+    fn parse(input: &[String]) -> Option<Day25> {
+        let mut day = Day25::new();
+        day.input = Input::from_lines("generated", input.to_vec());
+        day.parse_input().ok()?;
+        Some(day)
+    }
+
+    #[test]
+    fn test_min_cut_on_test_input() {
+        let mut day = Day25::new();
+        day.parse(Input::read("data/day25-test.txt")).unwrap();
+        let cut = day.find_min_cut().unwrap();
+        let mut wires: Vec<(String, String)> = cut
+            .wires
+            .into_iter()
+            .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+            .collect();
+        wires.sort();
+        let expected = [("bvb", "cmg"), ("hfx", "pzl"), ("jqt", "nvd")];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect();
+        assert_eq!(wires, expected);
+        assert_eq!(day.solve1(), "54");
+    }
+
+    #[test]
+    fn test_min_cut_needs_2_components() {
+        let mut day = Day25::new();
+        let input = Input::from_lines("test.txt", vec![String::from("abc: abc")]);
+        day.parse(input).unwrap();
+        assert_eq!(day.solve1(), "no cut: less than 2 components");
+    }
+
+    #[test]
+    fn test_min_cut_needs_3_wires_in_a_connected_graph() {
+        let solve1 = |lines: &[&str]| {
+            let mut day = Day25::new();
+            let lines = lines.iter().map(|l| l.to_string()).collect();
+            day.parse(Input::from_lines("test.txt", lines)).unwrap();
+            day.solve1()
+        };
+        // 3 separate groups:
+        assert_eq!(
+            solve1(&["aaa: bbb", "ccc: ddd", "eee: fff"]),
+            "no cut: the components are not all connected"
+        );
+        // a chain, with a cut of a single wire:
+        assert_eq!(
+            solve1(&["aaa: bbb", "bbb: ccc"]),
+            "no cut: the minimum cut is not 3 wires, but 1"
+        );
+    }
+
+    #[test]
+    fn test_min_cut_matches_all_triples() {
+        // only inputs with a single way to split the graph with 3 wires count:
+        let reference = |input: &[String]| {
+            let mut products = parse(input)?.find_splitting_triples();
+            products.sort();
+            products.dedup();
+            match products.len() {
+                1 => Some(products[0].to_string()),
+                _ => None,
+            }
+        };
+        let optimised = |input: &[String]| {
+            let mut day = parse(input)?;
+            match day.find_min_cut()?.wires.len() {
+                3 => Some(day.solve1()),
+                _ => None,
+            }
+        };
+        let generate = |rng: &mut StdRng| generate(5, rng);
+        difftest::assert_agree(20, generate, reference, optimised);
+    }
+}
@@ -1,6 +1,7 @@
 use alex_lib::types::{Coord2dMap, Coord2d};
 
 use super::Day;
+use crate::parser::{Input, ParseErrors};
 
 enum TestValue {
     ValueA(String),
@@ -55,6 +56,10 @@ impl Day for DayTest {
         self.a_value = self.create_value(42);
     }
 
+    fn parse(&mut self, _input: Input) -> Result<(), ParseErrors> {
+        Ok(())
+    }
+
     fn solve1(&mut self) -> String {
         String::from(format!(
             "\n{0}\nDimension: {1}x{2}\n",
@@ -0,0 +1,132 @@
+// Differential testing: compares a slow, but obviously correct reference implementation
+// with an optimised one on many small random inputs. Only used by the tests.
+
+use std::fmt::{Debug, Display};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::shrink::shrink;
+
+/// An input on which the reference and the optimised implementation disagree.
+/// `input` is the shrunk version of the generated `original` input.
+#[derive(Debug)]
+pub struct Counterexample<T> {
+    pub seed: u64,
+    pub original: Vec<String>,
+    pub input: Vec<String>,
+    pub reference: T,
+    pub optimised: T,
+}
+
+impl<T: Debug> Display for Counterexample<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "reference and optimised implementation disagree (seed {0}, shrunk from {1} to {2} lines):",
+            self.seed,
+            self.original.len(),
+            self.input.len()
+        )?;
+        for line in self.input.iter() {
+            writeln!(f, "    {0}", line)?;
+        }
+        writeln!(f, "reference: {0:?}", self.reference)?;
+        write!(f, "optimised: {0:?}", self.optimised)
+    }
+}
+
+/// Runs both implementations on `cases` generated inputs (seeds 0, 1, 2, ...).
+/// The implementations return None for inputs they cannot handle (e.g. invalid inputs
+/// created while shrinking): those are never counted as disagreement.
+/// Returns the first disagreement, shrunk to a minimal input.
+pub fn find_counterexample<T, G, R, O>(
+    cases: u64,
+    mut generate: G,
+    reference: R,
+    optimised: O,
+) -> Option<Counterexample<T>>
+where
+    T: PartialEq,
+    G: FnMut(&mut StdRng) -> Vec<String>,
+    R: Fn(&[String]) -> Option<T>,
+    O: Fn(&[String]) -> Option<T>,
+{
+    let disagree = |input: &[String]| -> Option<(T, T)> {
+        let expected = reference(input)?;
+        let actual = optimised(input)?;
+        match expected == actual {
+            true => None,
+            false => Some((expected, actual)),
+        }
+    };
+
+    for seed in 0..cases {
+        let original = generate(&mut StdRng::seed_from_u64(seed));
+        if disagree(&original).is_none() {
+            continue;
+        }
+        let input = shrink(original.clone(), |input| disagree(input).is_some());
+        let (reference, optimised) = disagree(&input).unwrap();
+        return Some(Counterexample {
+            seed,
+            original,
+            input,
+            reference,
+            optimised,
+        });
+    }
+    None
+}
+
+/// Like find_counterexample(), but panics with the shrunk input if the implementations disagree.
+pub fn assert_agree<T, G, R, O>(cases: u64, generate: G, reference: R, optimised: O)
+where
+    T: PartialEq + Debug,
+    G: FnMut(&mut StdRng) -> Vec<String>,
+    R: Fn(&[String]) -> Option<T>,
+    O: Fn(&[String]) -> Option<T>,
+{
+    if let Some(counterexample) = find_counterexample(cases, generate, reference, optimised) {
+        panic!("{0}", counterexample);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_shrinks_to_minimal_input() {
+        // a buggy sum, that ignores numbers above 50:
+        let sum =
+            |input: &[String]| -> Option<u64> { input.iter().map(|l| l.parse::<u64>().ok()).sum() };
+        let buggy_sum = |input: &[String]| -> Option<u64> {
+            input
+                .iter()
+                .map(|l| l.parse::<u64>().ok().map(|v| if v > 50 { 0 } else { v }))
+                .sum()
+        };
+        let generate = |rng: &mut StdRng| -> Vec<String> {
+            (0..10).map(|_| rng.gen_range(0..100).to_string()).collect()
+        };
+        let counterexample = find_counterexample(10, generate, sum, buggy_sum).unwrap();
+        assert_eq!(counterexample.input, lines(&["51"]));
+        assert_eq!(
+            (counterexample.reference, counterexample.optimised),
+            (51, 0)
+        );
+    }
+
+    #[test]
+    fn test_agreeing_implementations() {
+        let generate = |rng: &mut StdRng| vec![rng.gen_range(0..1000).to_string()];
+        let double = |input: &[String]| Some(input[0].parse::<u64>().ok()? * 2);
+        let add = |input: &[String]| input[0].parse::<u64>().ok().map(|v| v + v);
+        assert_agree(50, generate, double, add);
+    }
+}
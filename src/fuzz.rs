@@ -0,0 +1,324 @@
+// Fuzzing: feeds random and mutated inputs to the parser of every day. Invalid input
+// must be reported as parse error, a panic is a bug. Crashing inputs are saved in
+// data/fuzz/, and replayed by the tests, so they stay fixed.
+
+use std::{
+    any::Any,
+    fmt::Display,
+    fs,
+    panic::{self, AssertUnwindSafe},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::days;
+use crate::generate;
+use crate::parser::Input;
+use crate::shrink::{shrink, tokenize};
+
+/// The crashing inputs found so far, one `dayXX-<seed>.txt` file per crash
+pub const CRASH_DIR: &str = "data/fuzz";
+
+/// Chars that are likely to confuse a parser: separators, signs, whitespace,
+/// and multi-byte UTF-8 chars
+const NASTY_CHARS: &str = " \t,:;=-+#.|@()~?*xé€😀";
+
+/// Numbers at the edges of the usual number types
+const NASTY_NUMBERS: [&str; 6] = [
+    "0",
+    "-1",
+    "256",
+    "4294967296",
+    "9223372036854775808",
+    "99999999999999999999999",
+];
+
+/// A parser panic found by fuzzing. `input` is shrunk from the fuzzed input of `seed`.
+#[derive(Debug)]
+pub struct Crash {
+    pub day: u32,
+    pub seed: u64,
+    pub input: Vec<String>,
+    pub message: String,
+}
+
+impl Display for Crash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "day {0}: parser panics with '{1}' (seed {2}) on input:",
+            self.day, self.message, self.seed
+        )?;
+        for line in self.input.iter() {
+            writeln!(f, "    {0}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the input with a new instance of the given day.
+/// Returns the panic message, if the parser panics.
+pub fn parse_panics(day: u32, input: &[String]) -> Option<String> {
+    let mut problem = days::create(day)?;
+    let input = Input::from_lines("fuzz", input.to_vec());
+    match panic::catch_unwind(AssertUnwindSafe(|| problem.parse(input))) {
+        Ok(_) => None,
+        Err(payload) => Some(panic_message(payload)),
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => String::from("unknown panic"),
+        },
+    }
+}
+
+/// The fuzzed input for the given seed: mostly a generated (valid) input of the day,
+/// sometimes just random lines, with a few random mutations.
+pub fn fuzz_input(day: u32, seed: u64) -> Vec<String> {
+    let rng = &mut StdRng::seed_from_u64(seed);
+    let mut input = match rng.gen_range(0..10) {
+        0 => random_lines(rng),
+        _ => generate::generate(day, rng.gen_range(1..=6), rng.gen()).unwrap_or_default(),
+    };
+    for _ in 0..rng.gen_range(0..=4) {
+        mutate(&mut input, rng);
+    }
+    input
+}
+
+/// A few lines of random chars
+fn random_lines(rng: &mut StdRng) -> Vec<String> {
+    let chars: Vec<char> = format!("abcRDLU0123456789{0}", NASTY_CHARS)
+        .chars()
+        .collect();
+    (0..rng.gen_range(0..5))
+        .map(|_| {
+            (0..rng.gen_range(0..20))
+                .map(|_| *chars.choose(rng).unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+/// Applies a single random mutation: to whole lines, single chars, or a number
+fn mutate(input: &mut Vec<String>, rng: &mut StdRng) {
+    if input.is_empty() {
+        input.push(String::new());
+    }
+    let y = rng.gen_range(0..input.len());
+    let mut chars: Vec<char> = input[y].chars().collect();
+    let x = rng.gen_range(0..=chars.len());
+    let nasty: Vec<char> = NASTY_CHARS.chars().collect();
+    match rng.gen_range(0..9) {
+        0 => {
+            input.remove(y);
+            return;
+        }
+        1 => {
+            let line = input[y].clone();
+            input.insert(y, line);
+            return;
+        }
+        2 => {
+            input.insert(y, String::new());
+            return;
+        }
+        3 => {
+            input.truncate(y);
+            return;
+        }
+        4 => chars.truncate(x),
+        5 if x < chars.len() => {
+            chars.remove(x);
+        }
+        6 => chars.insert(x, *nasty.choose(rng).unwrap()),
+        7 if x < chars.len() => chars[x] = *nasty.choose(rng).unwrap(),
+        _ => {
+            let mut tokens = tokenize(&input[y]);
+            let numbers: Vec<usize> = (0..tokens.len())
+                .filter(|t| tokens[*t].starts_with(|c: char| c.is_ascii_digit()))
+                .collect();
+            if let Some(t) = numbers.choose(rng) {
+                tokens[*t] = NASTY_NUMBERS.choose(rng).unwrap().to_string();
+            }
+            input[y] = tokens.concat();
+            return;
+        }
+    }
+    input[y] = chars.into_iter().collect();
+}
+
+/// Parses `runs` fuzzed inputs (seeds `first_seed`, `first_seed + 1`, ...) with the parser
+/// of the given day. Returns the first crash, shrunk to a minimal input.
+pub fn find_crash(day: u32, runs: u64, first_seed: u64) -> Option<Crash> {
+    for seed in (0..runs).map(|i| first_seed.wrapping_add(i)) {
+        let original = fuzz_input(day, seed);
+        if parse_panics(day, &original).is_none() {
+            continue;
+        }
+        let input = shrink(original, |input| parse_panics(day, input).is_some());
+        let message = parse_panics(day, &input).unwrap();
+        return Some(Crash {
+            day,
+            seed,
+            input,
+            message,
+        });
+    }
+    None
+}
+
+/// Saves the crashing input in the CRASH_DIR, so the tests replay it. Returns the file name.
+fn save_crash(crash: &Crash) -> Result<String, String> {
+    let filename = format!("{0}/day{1:02}-{2}.txt", CRASH_DIR, crash.day, crash.seed);
+    let mut content = crash.input.join("\n");
+    if !crash.input.is_empty() {
+        content.push('\n');
+    }
+    fs::create_dir_all(CRASH_DIR)
+        .and_then(|_| fs::write(&filename, content))
+        .map_err(|e| format!("cannot write {0}: {1}", filename, e))?;
+    Ok(filename)
+}
+
+/// The saved crashing inputs, as (day, file name, input lines)
+pub fn saved_crashes() -> Vec<(u32, String, Vec<String>)> {
+    let mut crashes = Vec::new();
+    let entries = match fs::read_dir(CRASH_DIR) {
+        Ok(entries) => entries,
+        Err(_) => return crashes,
+    };
+    for entry in entries.flatten() {
+        let filename = entry.path().to_string_lossy().to_string();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let day = name
+            .strip_prefix("day")
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|nr| nr.parse::<u32>().ok());
+        if let (Some(day), Ok(content)) = (day, fs::read_to_string(entry.path())) {
+            crashes.push((day, filename, content.lines().map(String::from).collect()));
+        }
+    }
+    crashes.sort();
+    crashes
+}
+
+/// The `fuzz [<day> ...] [--runs N] [--seed S]` command: fuzzes the parsers of the
+/// given days (all, if none given), and saves the crashing inputs.
+/// Without a seed, a random one is used (and printed to stderr, to be able to reproduce it).
+pub fn run(args: &[String]) -> Result<(), String> {
+    let usage = "usage: fuzz [<day> ...] [--runs N] [--seed S]";
+    let mut days: Vec<u32> = Vec::new();
+    let mut runs: u64 = 1000;
+    let mut seed: Option<u64> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => {
+                let value = args.next().ok_or(usage)?;
+                runs = value
+                    .parse()
+                    .map_err(|_| format!("invalid runs: {}", value))?
+            }
+            "--seed" => {
+                let value = args.next().ok_or(usage)?;
+                seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid seed: {}", value))?,
+                )
+            }
+            day => match day.parse::<u32>() {
+                Ok(day) if day <= 25 => days.push(day),
+                _ => return Err(usage.to_string()),
+            },
+        }
+    }
+    if days.is_empty() {
+        days = (0..=25).collect();
+    }
+    let seed = seed.unwrap_or_else(|| {
+        let seed = rand::random();
+        eprintln!("seed: {0}", seed);
+        seed
+    });
+
+    // the panics are expected, and reported below:
+    let prev_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let crashes = report_crashes(&days, runs, seed);
+    panic::set_hook(prev_hook);
+    let crashes = crashes?;
+
+    match crashes {
+        0 => Ok(()),
+        _ => Err(format!("{0} parser(s) panic", crashes)),
+    }
+}
+
+/// Replays the saved crashes of the given days, then fuzzes each day.
+/// New crashes are saved, returns the number of parsers that panic.
+fn report_crashes(days: &[u32], runs: u64, seed: u64) -> Result<usize, String> {
+    let mut crashes = 0;
+    // the crashes found so far first, they must stay fixed:
+    for (day, filename, input) in saved_crashes() {
+        if !days.contains(&day) {
+            continue;
+        }
+        if let Some(message) = parse_panics(day, &input) {
+            crashes += 1;
+            println!(
+                "{0}: day {1} parser panics again: {2}",
+                filename, day, message
+            );
+        }
+    }
+    for &day in days {
+        match find_crash(day, runs, seed) {
+            None => println!("day {0}: {1} runs, no crash", day, runs),
+            Some(crash) => {
+                crashes += 1;
+                println!("{0}saved as {1}", crash, save_crash(&crash)?);
+            }
+        }
+    }
+    Ok(crashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzz_input_is_reproducible() {
+        for seed in 0..20 {
+            assert_eq!(fuzz_input(7, seed), fuzz_input(7, seed));
+        }
+    }
+
+    #[test]
+    fn test_parsers_do_not_panic() {
+        for day in 0..=25 {
+            if let Some(crash) = find_crash(day, 200, 0) {
+                panic!("{0}", crash);
+            }
+        }
+    }
+
+    #[test]
+    fn test_saved_crashes_stay_fixed() {
+        for (day, filename, input) in saved_crashes() {
+            if let Some(message) = parse_panics(day, &input) {
+                panic!(
+                    "{0}: day {1} parser panics again: {2}",
+                    filename, day, message
+                );
+            }
+        }
+    }
+}
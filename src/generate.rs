@@ -0,0 +1,254 @@
+
+use std::collections::HashSet;
+
+use alex_lib::types::{Coord2d, Direction};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::days;
+
+/// Generates a random puzzle input for the given day:
+/// `size` roughly scales the input (number of lines, grid size, ...),
+/// the same seed always produces the same input.
+pub fn generate(day: u32, size: usize, seed: u64) -> Option<Vec<String>> {
+    let rng = &mut StdRng::seed_from_u64(seed);
+    let size = size.max(1);
+    let lines = match day {
+        0 => days::day00::generate(size, rng),
+        1 => days::day01::generate(size, rng),
+        2 => days::day02::generate(size, rng),
+        3 => days::day03::generate(size, rng),
+        4 => days::day04::generate(size, rng),
+        5 => days::day05::generate(size, rng),
+        6 => days::day06::generate(size, rng),
+        7 => days::day07::generate(size, rng),
+        8 => days::day08::generate(size, rng),
+        9 => days::day09::generate(size, rng),
+        10 => days::day10::generate(size, rng),
+        11 => days::day11::generate(size, rng),
+        12 => days::day12::generate(size, rng),
+        13 => days::day13::generate(size, rng),
+        14 => days::day14::generate(size, rng),
+        15 => days::day15::generate(size, rng),
+        16 => days::day16::generate(size, rng),
+        17 => days::day17::generate(size, rng),
+        18 => days::day18::generate(size, rng),
+        19 => days::day19::generate(size, rng),
+        20 => days::day20::generate(size, rng),
+        21 => days::day21::generate(size, rng),
+        22 => days::day22::generate(size, rng),
+        23 => days::day23::generate(size, rng),
+        24 => days::day24::generate(size, rng),
+        25 => days::day25::generate(size, rng),
+        _ => return None,
+    };
+    Some(lines)
+}
+
+/// The `generate <day> [--size N] [--seed S]` command: prints the generated input.
+/// Without a seed, a random one is used (and printed to stderr, to be able to reproduce it).
+pub fn run(args: &[String]) -> Result<(), String> {
+    let usage = "usage: generate <day> [--size N] [--seed S]";
+    let mut args = args.iter();
+    let day = match args.next().map(|d| d.parse::<u32>()) {
+        Some(Ok(day)) => day,
+        _ => return Err(usage.to_string()),
+    };
+    let mut size: usize = 10;
+    let mut seed: Option<u64> = None;
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(usage)?;
+        match arg.as_str() {
+            "--size" => {
+                size = value
+                    .parse()
+                    .map_err(|_| format!("invalid size: {}", value))?
+            }
+            "--seed" => {
+                seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid seed: {}", value))?,
+                )
+            }
+            _ => return Err(usage.to_string()),
+        }
+    }
+    let seed = seed.unwrap_or_else(|| {
+        let seed = rand::random();
+        eprintln!("seed: {0}", seed);
+        seed
+    });
+
+    let lines = generate(day, size, seed).ok_or(format!("No generator for day {}", day))?;
+    for line in lines {
+        println!("{0}", line);
+    }
+    Ok(())
+}
+
+/// A name with the given length from the given chars, that is not yet in `used`.
+pub fn unique_name(
+    rng: &mut StdRng,
+    len: usize,
+    chars: &str,
+    used: &mut HashSet<String>,
+) -> String {
+    let chars: Vec<char> = chars.chars().collect();
+    loop {
+        let name: String = (0..len).map(|_| *chars.choose(rng).unwrap()).collect();
+        if used.insert(name.clone()) {
+            return name;
+        }
+    }
+}
+
+/// Joins the values with the given separator
+pub fn join<T: ToString>(values: &[T], separator: &str) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Grows a random shape of connected cells within a width x height area, by adding
+/// random neighbour cells, until it has (about) the given number of cells.
+/// Cells that only touch diagonally are never added, so the shape's outline is
+/// always a simple closed path (see outline()).
+pub fn random_shape(width: i64, height: i64, cells: usize, rng: &mut StdRng) -> HashSet<Coord2d> {
+    let mut shape: HashSet<Coord2d> = HashSet::new();
+    let start = Coord2d {
+        x: rng.gen_range(0..width),
+        y: rng.gen_range(0..height),
+    };
+    shape.insert(start);
+    let mut candidates: Vec<Coord2d> = vec![start];
+    let cells = cells.min((width * height) as usize);
+    let mut tries = 0;
+    while shape.len() < cells && tries < cells * 100 {
+        tries += 1;
+        let from = *candidates.choose(rng).unwrap();
+        let next = match rng.gen_range(0..4) {
+            0 => from.up(),
+            1 => from.right(),
+            2 => from.down(),
+            _ => from.left(),
+        };
+        if next.x < 0 || next.y < 0 || next.x >= width || next.y >= height {
+            continue;
+        }
+        if shape.contains(&next) || touches_diagonally(&shape, next) {
+            continue;
+        }
+        shape.insert(next);
+        candidates.push(next);
+    }
+    shape
+}
+
+/// true if adding the cell would create a 2x2 block where only the diagonal cells are set
+fn touches_diagonally(shape: &HashSet<Coord2d>, cell: Coord2d) -> bool {
+    for (dx, dy) in [(-1, -1), (1, -1), (1, 1), (-1, 1)] {
+        let diagonal = Coord2d {
+            x: cell.x + dx,
+            y: cell.y + dy,
+        };
+        let side1 = Coord2d {
+            x: cell.x + dx,
+            y: cell.y,
+        };
+        let side2 = Coord2d {
+            x: cell.x,
+            y: cell.y + dy,
+        };
+        if shape.contains(&diagonal) && !shape.contains(&side1) && !shape.contains(&side2) {
+            return true;
+        }
+    }
+    false
+}
+
+/// The outer outline of a shape (see random_shape()), as closed path of corner points,
+/// walked clockwise (on a y-down grid), starting at the top-left corner.
+/// Each cell (x, y) is a square from corner point (x, y) to (x + 1, y + 1).
+/// Returns the list of (direction, steps) moves, consecutive moves in the same direction are merged.
+pub fn outline(shape: &HashSet<Coord2d>) -> (Coord2d, Vec<(Direction, i64)>) {
+    // all boundary edges, from corner point to corner point, in walking direction:
+    let mut edges: Vec<(Coord2d, Direction)> = Vec::new();
+    for cell in shape.iter() {
+        let (x, y) = (cell.x, cell.y);
+        if !shape.contains(&cell.up()) {
+            edges.push((Coord2d { x, y }, Direction::RIGHT));
+        }
+        if !shape.contains(&cell.right()) {
+            edges.push((Coord2d { x: x + 1, y }, Direction::DOWN));
+        }
+        if !shape.contains(&cell.down()) {
+            edges.push((Coord2d { x: x + 1, y: y + 1 }, Direction::LEFT));
+        }
+        if !shape.contains(&cell.left()) {
+            edges.push((Coord2d { x, y: y + 1 }, Direction::UP));
+        }
+    }
+    let outgoing: std::collections::HashMap<Coord2d, Direction> = edges.iter().copied().collect();
+
+    // the top-left corner is always on the outer outline:
+    let start = *outgoing.keys().min_by_key(|c| (c.y, c.x)).unwrap();
+    let mut moves: Vec<(Direction, i64)> = Vec::new();
+    let mut act = start;
+    loop {
+        let dir = outgoing[&act];
+        match moves.last_mut() {
+            Some((last_dir, steps)) if *last_dir == dir => *steps += 1,
+            _ => moves.push((dir, 1)),
+        }
+        act = match dir {
+            Direction::UP => act.up(),
+            Direction::RIGHT => act.right(),
+            Direction::DOWN => act.down(),
+            Direction::LEFT => act.left(),
+        };
+        if act == start {
+            break;
+        }
+    }
+    (start, moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_input() {
+        for day in 0..=25 {
+            let input = generate(day, 5, 42).unwrap();
+            assert!(!input.is_empty(), "day {}", day);
+            assert_eq!(generate(day, 5, 42).unwrap(), input, "day {}", day);
+        }
+        assert_eq!(generate(26, 5, 42), None);
+    }
+
+    #[test]
+    fn test_outline_is_closed() {
+        let rng = &mut StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let shape = random_shape(8, 6, 25, rng);
+            let (_, moves) = outline(&shape);
+            let (mut x, mut y) = (0, 0);
+            for (dir, steps) in moves.iter() {
+                match dir {
+                    Direction::UP => y -= steps,
+                    Direction::RIGHT => x += steps,
+                    Direction::DOWN => y += steps,
+                    Direction::LEFT => x -= steps,
+                }
+            }
+            assert_eq!((x, y), (0, 0));
+            // consecutive moves always turn:
+            for (i, (dir, _)) in moves.iter().enumerate() {
+                assert!(moves[(i + 1) % moves.len()].0 != *dir);
+            }
+        }
+    }
+}
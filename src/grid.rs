@@ -0,0 +1,211 @@
+
+use std::{collections::HashMap, fmt::Display};
+
+use alex_lib::types::Coord2d;
+
+use crate::parser::{self, LineParser, ParseErrors};
+
+/// A single grid cell type, that is read from / printed as a single char.
+pub trait Tile: Sized + Copy {
+    /// All valid tile chars, used for error messages.
+    const CHARS: &'static str;
+
+    fn from_char(c: char) -> Option<Self>;
+    fn to_char(&self) -> char;
+}
+
+/// A rectangular grid of tiles. Markers (like a start position 'S') are not tiles by itself:
+/// they are replaced by a normal tile while parsing, but their position is remembered.
+#[derive(Debug, Clone)]
+pub struct Grid<T: Tile> {
+    rows: Vec<Vec<T>>,
+    markers: HashMap<char, Coord2d>,
+}
+
+impl<T: Tile> Grid<T> {
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Grid<T> {
+        Grid {
+            rows,
+            markers: HashMap::new(),
+        }
+    }
+
+    /// Parses the lines as a rectangular grid of tiles. `markers` lists the marker chars,
+    /// with the tile they stand on. Each marker must occur exactly once.
+    /// All problems (unknown chars, rows of different width, missing or duplicate markers)
+    /// are recorded in `errors`: the grid is only returned if there are none.
+    pub fn parse<'a>(
+        lines: impl Iterator<Item = LineParser<'a>>,
+        markers: &[(char, T)],
+        errors: &mut ParseErrors,
+    ) -> Option<Grid<T>> {
+        let lines: Vec<LineParser<'a>> = lines.collect();
+        let mut allowed = T::CHARS.to_string();
+        allowed.extend(markers.iter().map(|(c, _)| c));
+        let nr_of_errors = errors.errors().len();
+        let chars = parser::parse_grid(lines.iter().cloned(), &allowed, errors);
+
+        let mut grid = Grid::from_rows(Vec::new());
+        for (marker, _) in markers.iter() {
+            let pos = parser::find_unique_marker(lines.iter().cloned(), *marker, errors);
+            if let Some((x, y)) = pos {
+                let coord = Coord2d {
+                    x: x as i64,
+                    y: y as i64,
+                };
+                grid.markers.insert(*marker, coord);
+            }
+        }
+        if errors.errors().len() > nr_of_errors {
+            return None;
+        }
+
+        for row in chars.iter() {
+            let tiles = row
+                .iter()
+                .map(|c| match markers.iter().find(|(m, _)| m == c) {
+                    Some((_, tile)) => *tile,
+                    None => T::from_char(*c).unwrap(),
+                });
+            grid.rows.push(tiles.collect());
+        }
+        Some(grid)
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn in_bounds(&self, coord: &Coord2d) -> bool {
+        coord.x >= 0
+            && coord.y >= 0
+            && (coord.x as usize) < self.width()
+            && (coord.y as usize) < self.height()
+    }
+
+    /// The tile at the given coordinate, None if outside of the grid
+    pub fn get(&self, coord: &Coord2d) -> Option<T> {
+        match self.in_bounds(coord) {
+            true => Some(self.rows[coord.y as usize][coord.x as usize]),
+            false => None,
+        }
+    }
+
+    pub fn set(&mut self, coord: &Coord2d, tile: T) {
+        self.rows[coord.y as usize][coord.x as usize] = tile;
+    }
+
+    /// The position of a marker found while parsing
+    pub fn marker(&self, marker: char) -> Option<Coord2d> {
+        self.markers.get(&marker).copied()
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<T>> {
+        self.rows
+    }
+
+    /// All tiles with their coordinate, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Coord2d, T)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, tile)| {
+                let coord = Coord2d {
+                    x: x as i64,
+                    y: y as i64,
+                };
+                (coord, *tile)
+            })
+        })
+    }
+}
+
+/// Prints the grid exactly as it was read: markers are printed instead of their tile.
+impl<T: Tile> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let markers: HashMap<Coord2d, char> =
+            self.markers.iter().map(|(c, pos)| (*pos, *c)).collect();
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let coord = Coord2d {
+                    x: x as i64,
+                    y: y as i64,
+                };
+                match markers.get(&coord) {
+                    Some(c) => write!(f, "{0}", c)?,
+                    None => write!(f, "{0}", tile.to_char())?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Input;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Plot {
+        Garden,
+        Rock,
+    }
+
+    impl Tile for Plot {
+        const CHARS: &'static str = ".#";
+
+        fn from_char(c: char) -> Option<Self> {
+            match c {
+                '.' => Some(Plot::Garden),
+                '#' => Some(Plot::Rock),
+                _ => None,
+            }
+        }
+
+        fn to_char(&self) -> char {
+            match self {
+                Plot::Garden => '.',
+                Plot::Rock => '#',
+            }
+        }
+    }
+
+    fn input(lines: &[&str]) -> Input {
+        Input::from_lines("test.txt", lines.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_parse_and_print() {
+        let input = input(&["..#", ".S#", "#.."]);
+        let mut errors = ParseErrors::new();
+        let grid = Grid::<Plot>::parse(input.lines(), &[('S', Plot::Garden)], &mut errors).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.marker('S'), Some(Coord2d { x: 1, y: 1 }));
+        assert_eq!(grid.get(&Coord2d { x: 1, y: 1 }), Some(Plot::Garden));
+        assert_eq!(grid.get(&Coord2d { x: 2, y: 0 }), Some(Plot::Rock));
+        assert_eq!(grid.get(&Coord2d { x: 3, y: 0 }), None);
+        assert_eq!(grid.iter().filter(|(_, t)| *t == Plot::Rock).count(), 3);
+        assert_eq!(grid.to_string(), "..#\n.S#\n#..\n");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = input(&["..#", ".x#", "#..", ".S.", "S.."]);
+        let mut errors = ParseErrors::new();
+        let grid = Grid::<Plot>::parse(input.lines(), &[('S', Plot::Garden)], &mut errors);
+        assert!(grid.is_none());
+        let messages: Vec<String> = errors.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "test.txt:2:2: expected one of '.#S', found 'x'",
+                "test.txt:5:1: expected only a single 'S' marker, found 'S'",
+            ]
+        );
+    }
+}
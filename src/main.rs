@@ -1,9 +1,16 @@
+mod cycles;
 mod days;
-use days::{
-    Day, Day00, Day01, Day02, Day03, Day04, Day05, Day06, Day07, Day08, Day09, Day10, Day11,
-    Day12, Day13, Day14, Day15, Day16, Day17, Day18, Day19, Day20, Day21, Day22, Day23, Day24,
-    Day25, DayTest,
-};
+#[cfg(test)]
+mod difftest;
+mod fuzz;
+mod generate;
+mod grid;
+mod nonogram;
+mod parser;
+mod patterns;
+mod polygon;
+mod shrink;
+use days::{Day, DayTest};
 use std::env;
 use std::time::{Duration, Instant};
 use std::vec::Vec;
@@ -21,38 +28,75 @@ struct Result {
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // generate <day> [--size N] [--seed S]: print a random puzzle input instead of solving
+    if args.first().map(|a| a.as_str()) == Some("generate") {
+        if let Err(e) = generate::run(&args[1..]) {
+            eprintln!("{0}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // fuzz [<day> ...] [--runs N] [--seed S]: check that the parsers never panic
+    if args.first().map(|a| a.as_str()) == Some("fuzz") {
+        if let Err(e) = fuzz::run(&args[1..]) {
+            eprintln!("{0}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // almanac <file> [location ...]: print the composed Day 05 seed -> location map
+    if args.first().map(|a| a.as_str()) == Some("almanac") {
+        if let Err(e) = days::day05::run(&args[1..]) {
+            eprintln!("{0}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // ghosts <file>: print the cycles of the Day 08 ghosts
+    if args.first().map(|a| a.as_str()) == Some("ghosts") {
+        if let Err(e) = days::day08::run(&args[1..]) {
+            eprintln!("{0}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // sequences <file>: print the polynomial degrees of the Day 09 sequences
+    if args.first().map(|a| a.as_str()) == Some("sequences") {
+        if let Err(e) = days::day09::run(&args[1..]) {
+            eprintln!("{0}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // pipes <file>: draw the Day 10 pipe loop
+    if args.first().map(|a| a.as_str()) == Some("pipes") {
+        if let Err(e) = days::day10::run(&args[1..]) {
+            eprintln!("{0}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // nonogram <file>: solve a nonogram puzzle, and tell if its solution is unique
+    if args.first().map(|a| a.as_str()) == Some("nonogram") {
+        if let Err(e) = nonogram::run(&args[1..]) {
+            eprintln!("{0}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let mut probs = Vec::<Box<dyn Day>>::new();
     // Create the problems instances to solve:
     for a in env::args().skip(1) {
         probs.push(match a.as_str() {
             "test" => Box::new(DayTest::new()),
-            "0" => Box::new(Day00::new()),
-            "1" => Box::new(Day01::new()),
-            "2" => Box::new(Day02::new()),
-            "3" => Box::new(Day03::new()),
-            "4" => Box::new(Day04::new()),
-            "5" => Box::new(Day05::new()),
-            "6" => Box::new(Day06::new()),
-            "7" => Box::new(Day07::new()),
-            "8" => Box::new(Day08::new()),
-            "9" => Box::new(Day09::new()),
-            "10" => Box::new(Day10::new()),
-            "11" => Box::new(Day11::new()),
-            "12" => Box::new(Day12::new()),
-            "13" => Box::new(Day13::new()),
-            "14" => Box::new(Day14::new()),
-            "15" => Box::new(Day15::new()),
-            "16" => Box::new(Day16::new()),
-            "17" => Box::new(Day17::new()),
-            "18" => Box::new(Day18::new()),
-            "19" => Box::new(Day19::new()),
-            "20" => Box::new(Day20::new()),
-            "21" => Box::new(Day21::new()),
-            "22" => Box::new(Day22::new()),
-            "23" => Box::new(Day23::new()),
-            "24" => Box::new(Day24::new()),
-            "25" => Box::new(Day25::new()),
-            _ => panic!("Unknown problem"),
+            nr => nr
+                .parse()
+                .ok()
+                .and_then(days::create)
+                .expect("Unknown problem"),
         })
     }
 
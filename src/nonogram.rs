@@ -0,0 +1,338 @@
+// Nonogram solver, built on the row logic of Day 12: the clues of a row are the groups of
+// filled cells, just like the groups of damaged springs. Used for puzzle authoring, with the
+// `nonogram <file>` command: it solves the puzzle, and tells if the solution is unique.
+
+use std::path::Path;
+
+use crate::days::day12::count_arrangements;
+use crate::parser::{Input, LineParser, ParseErrors, ParseResult};
+
+/// The cells of a puzzle: '#' is filled, '.' is empty, '?' is unknown (as the springs in Day 12)
+pub type Board = Vec<Vec<char>>;
+
+/// A puzzle: the clues of each row and column, and optionally some known cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nonogram {
+    rows: Vec<Vec<u64>>,
+    columns: Vec<Vec<u64>>,
+    given: Board,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Solution {
+    None,
+    Unique(Board),
+    /// Two different solutions (there may be more)
+    Multiple(Board, Board),
+}
+
+impl Nonogram {
+    /// A puzzle without known cells
+    pub fn new(rows: Vec<Vec<u64>>, columns: Vec<Vec<u64>>) -> Nonogram {
+        let given = vec![vec!['?'; columns.len()]; rows.len()];
+        Nonogram {
+            rows,
+            columns,
+            given,
+        }
+    }
+
+    #[cfg(test)]
+    /// The puzzle for a picture of filled ('#') and empty cells: its clues, without known cells
+    pub fn from_picture(picture: &Board) -> Nonogram {
+        let width = picture.first().map_or(0, |row| row.len());
+        let columns: Board = (0..width)
+            .map(|x| picture.iter().map(|row| row[x]).collect())
+            .collect();
+        Nonogram::new(
+            picture.iter().map(|row| clue(row)).collect(),
+            columns.iter().map(|column| clue(column)).collect(),
+        )
+    }
+
+    /// Parses a puzzle: a 'rows:' block and a 'columns:' block with one clue per line,
+    /// like '1,3,1' ('0' for a line without filled cells), and an optional 'grid:' block
+    /// with the known cells ('#', '.', or '?' if unknown).
+    pub fn parse(input: &Input) -> Result<Nonogram, ParseErrors> {
+        let mut errors = ParseErrors::new();
+        let sections = input.sections();
+        if sections.len() < 2 || sections.len() > 3 {
+            return Err(ParseErrors::from(input.line(0).error(
+                "a 'rows:', a 'columns:' and an optional 'grid:' block, separated by empty lines",
+            )));
+        }
+        let mut clues = |section: &crate::parser::Section, name: &str| {
+            let (mut header, body) = section.split_header();
+            if let Some(()) = errors.check(parse_header(&mut header, name)) {
+                body.records(&mut errors, parse_clue)
+            } else {
+                Vec::new()
+            }
+        };
+        let rows = clues(&sections[0], "rows:");
+        let columns = clues(&sections[1], "columns:");
+        let mut nonogram = Nonogram::new(rows, columns);
+
+        if let Some(section) = sections.get(2) {
+            let (mut header, body) = section.split_header();
+            if errors.check(parse_header(&mut header, "grid:")).is_some() {
+                let given = body.grid(".#?", &mut errors);
+                let width = given.first().map_or(0, |row| row.len());
+                if given.len() != nonogram.rows.len() || width != nonogram.columns.len() {
+                    errors.push(header.error(&format!(
+                        "a grid of {0} rows and {1} columns",
+                        nonogram.rows.len(),
+                        nonogram.columns.len()
+                    )));
+                } else {
+                    nonogram.given = given;
+                }
+            }
+        }
+        errors.into_result()?;
+        Ok(nonogram)
+    }
+
+    /// Solves the puzzle: line solving (see solve_line()) as far as it gets, then guessing a
+    /// cell and backtracking. Stops after the 2nd solution, to tell if the solution is unique.
+    pub fn solve(&self) -> Solution {
+        let mut solutions: Vec<Board> = Vec::new();
+        self.search(self.given.clone(), &mut solutions);
+        let mut solutions = solutions.into_iter();
+        match (solutions.next(), solutions.next()) {
+            (None, _) => Solution::None,
+            (Some(solution), None) => Solution::Unique(solution),
+            (Some(first), Some(second)) => Solution::Multiple(first, second),
+        }
+    }
+
+    fn search(&self, mut board: Board, solutions: &mut Vec<Board>) {
+        if solutions.len() >= 2 || !self.propagate(&mut board) {
+            return;
+        }
+        let unknown = board
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| row.iter().position(|c| *c == '?').map(|x| (x, y)));
+        match unknown {
+            // all lines are solved, and each one fits its clue:
+            None => solutions.push(board),
+            Some((x, y)) => {
+                for guess in ['#', '.'] {
+                    let mut next = board.clone();
+                    next[y][x] = guess;
+                    self.search(next, solutions);
+                }
+            }
+        }
+    }
+
+    /// Solves all rows and columns, until nothing changes anymore.
+    /// Returns false if a line contradicts its clue.
+    fn propagate(&self, board: &mut Board) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (y, clue) in self.rows.iter().enumerate() {
+                match solve_line(&board[y], clue) {
+                    None => return false,
+                    Some(line) if line != board[y] => {
+                        board[y] = line;
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            for (x, clue) in self.columns.iter().enumerate() {
+                let column: Vec<char> = board.iter().map(|row| row[x]).collect();
+                match solve_line(&column, clue) {
+                    None => return false,
+                    Some(line) if line != column => {
+                        for (row, c) in board.iter_mut().zip(line) {
+                            row[x] = c;
+                        }
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+/// The groups of filled cells in a line
+fn clue(line: &[char]) -> Vec<u64> {
+    line.split(|c| *c != '#')
+        .filter(|group| !group.is_empty())
+        .map(|group| group.len() as u64)
+        .collect()
+}
+
+/// Line solving: fixes each unknown cell that is the same in all arrangements that fit the
+/// clue. An unknown cell must be filled, if no arrangement with an empty cell there exists,
+/// and vice versa: the arrangements are counted with the Day 12 counter.
+/// Returns None if no arrangement fits at all.
+pub fn solve_line(line: &[char], clue: &[u64]) -> Option<Vec<char>> {
+    let mut cells: Vec<char> = line.to_vec();
+    let fits = |cells: &[char]| count_arrangements(&cells.iter().collect::<String>(), clue) > 0;
+    if !fits(&cells) {
+        return None;
+    }
+    for i in 0..cells.len() {
+        if cells[i] != '?' {
+            continue;
+        }
+        cells[i] = '#';
+        let can_fill = fits(&cells);
+        cells[i] = '.';
+        let can_be_empty = fits(&cells);
+        cells[i] = match (can_fill, can_be_empty) {
+            (true, false) => '#',
+            (false, true) => '.',
+            _ => '?',
+        };
+    }
+    Some(cells)
+}
+
+/// A block header, like 'rows:'
+fn parse_header(line: &mut LineParser, name: &str) -> ParseResult<()> {
+    line.literal(name)?;
+    line.end()
+}
+
+/// A clue line, like '1,3,1', or '0' for a line without filled cells
+fn parse_clue(line: &mut LineParser) -> ParseResult<Vec<u64>> {
+    let groups = line.separated(",", |group| group.number::<u64>())?;
+    line.end()?;
+    Ok(groups.into_iter().filter(|len| *len > 0).collect())
+}
+
+pub fn render(board: &Board) -> String {
+    let lines: Vec<String> = board.iter().map(|row| row.iter().collect()).collect();
+    lines.join("\n")
+}
+
+/// The `nonogram <file>` command: solves the puzzle in the file, and prints the solution,
+/// or two different solutions if it is not unique.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let usage = "usage: nonogram <file>";
+    let file = match args {
+        [file] => file,
+        _ => return Err(usage.to_string()),
+    };
+    if !Path::new(file).is_file() {
+        return Err(format!("cannot read {0}", file));
+    }
+    let nonogram = Nonogram::parse(&Input::read(file)).map_err(|e| e.to_string())?;
+    match nonogram.solve() {
+        Solution::None => Err(String::from("no solution")),
+        Solution::Unique(board) => {
+            println!("unique solution:\n{0}", render(&board));
+            Ok(())
+        }
+        Solution::Multiple(first, second) => {
+            println!(
+                "not unique, two of the solutions:\n{0}\n\n{1}",
+                render(&first),
+                render(&second)
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn board(lines: &[&str]) -> Board {
+        lines.iter().map(|l| l.chars().collect()).collect()
+    }
+
+    fn parse(lines: &[&str]) -> Result<Nonogram, ParseErrors> {
+        let lines = lines.iter().map(|l| l.to_string()).collect();
+        Nonogram::parse(&Input::from_lines("test.txt", lines))
+    }
+
+    #[test]
+    fn test_unique_solution() {
+        let nonogram = Nonogram::parse(&Input::read("data/nonogram-test.txt")).unwrap();
+        let expected = board(&[".###.", "#...#", "#####", "#...#", "#...#"]);
+        assert_eq!(nonogram, Nonogram::from_picture(&expected));
+        assert_eq!(nonogram.solve(), Solution::Unique(expected));
+    }
+
+    #[test]
+    fn test_solve_line() {
+        let line: Vec<char> = "??????????".chars().collect();
+        let solved: String = solve_line(&line, &[8]).unwrap().into_iter().collect();
+        assert_eq!(solved, "??######??");
+        let line: Vec<char> = "?#??.?".chars().collect();
+        let solved: String = solve_line(&line, &[3, 1]).unwrap().into_iter().collect();
+        assert_eq!(solved, "?##?.#");
+        assert_eq!(solve_line(&line, &[5]), None);
+    }
+
+    #[test]
+    fn test_not_unique_and_given_cells() {
+        // a diagonal: either one
+        let lines = ["rows:", "1", "1", "", "columns:", "1", "1"];
+        match parse(&lines).unwrap().solve() {
+            Solution::Multiple(first, second) => {
+                assert_eq!(first, board(&["#.", ".#"]));
+                assert_eq!(second, board(&[".#", "#."]));
+            }
+            other => panic!("not unique, but {0:?}", other),
+        }
+        let mut lines = lines.to_vec();
+        lines.extend(["", "grid:", "?#", "??"]);
+        let solution = parse(&lines).unwrap().solve();
+        assert_eq!(solution, Solution::Unique(board(&[".#", "#."])));
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let lines = ["rows:", "2", "0", "", "columns:", "1", "0"];
+        assert_eq!(parse(&lines).unwrap().solve(), Solution::None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&["rows:", "1"]).is_err());
+        assert!(parse(&["rows:", "1 x", "", "columns:", "1"]).is_err());
+        assert!(parse(&["rows:", "1", "", "cols:", "1"]).is_err());
+        // the grid does not match the clues:
+        assert!(parse(&["rows:", "1", "", "columns:", "1", "", "grid:", "??"]).is_err());
+    }
+
+    #[test]
+    fn test_random_pictures() {
+        // the solver finds the picture, or another one with the same clues:
+        let rng = &mut StdRng::seed_from_u64(50);
+        for _ in 0..50 {
+            let width = rng.gen_range(1..=8);
+            let height = rng.gen_range(1..=8);
+            let picture: Board = (0..height)
+                .map(|_| {
+                    (0..width)
+                        .map(|_| if rng.gen_bool(0.5) { '#' } else { '.' })
+                        .collect()
+                })
+                .collect();
+            let nonogram = Nonogram::from_picture(&picture);
+            match nonogram.solve() {
+                Solution::None => panic!("no solution for {0:?}", picture),
+                Solution::Unique(solution) => assert_eq!(solution, picture),
+                Solution::Multiple(first, second) => {
+                    assert_ne!(first, second);
+                    assert_eq!(Nonogram::from_picture(&first), nonogram);
+                    assert_eq!(Nonogram::from_picture(&second), nonogram);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,691 @@
+
+use std::{any::type_name, fmt::Display, str::FromStr};
+
+use alex_lib::read_lines;
+
+/// A parse error, pointing to the exact input position (line and column are 1-based,
+/// the column counts characters, not bytes).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{0}:{1}:{2}: expected {3}, found {4}",
+            self.file, self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// Collects all problems found in an input, so that they can be reported
+/// all at once, instead of stopping at the first one.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParseErrors {
+    errors: Vec<ParseError>,
+}
+
+impl ParseErrors {
+    pub fn new() -> ParseErrors {
+        ParseErrors { errors: Vec::new() }
+    }
+
+    pub fn push(&mut self, error: ParseError) {
+        self.errors.push(error);
+    }
+
+    /// Records the error of a failed parse result, or returns the parsed value.
+    pub fn check<T>(&mut self, result: ParseResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.push(e);
+                None
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Ok if no problems were found, the collected errors otherwise.
+    pub fn into_result(self) -> Result<(), ParseErrors> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl From<ParseError> for ParseErrors {
+    fn from(error: ParseError) -> Self {
+        ParseErrors {
+            errors: vec![error],
+        }
+    }
+}
+
+impl Display for ParseErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{0} problem(s) in the input:", self.errors.len())?;
+        for e in self.errors.iter() {
+            write!(f, "\n  {0}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
+/// Returns the parsed value, or prints the input problems and stops the program:
+/// invalid input is reported before solving starts, instead of panicking
+/// somewhere in the middle of the solution.
+pub fn exit_on_error<T, E: Display>(result: Result<T, E>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Invalid input: {0}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses the lines as a rectangular grid of chars. Every char must be one of `allowed`,
+/// and all rows must have the same width as the first one.
+/// All problems are recorded in `errors`: the grid is returned anyway.
+pub fn parse_grid<'a>(
+    lines: impl Iterator<Item = LineParser<'a>>,
+    allowed: &str,
+    errors: &mut ParseErrors,
+) -> Vec<Vec<char>> {
+    let mut grid: Vec<Vec<char>> = Vec::new();
+    let mut first_line_nr = 0;
+    for mut line in lines {
+        line.skip_whitespace();
+        let row_start = line.clone();
+        let row = line.grid_row(allowed, errors);
+        match grid.first() {
+            None => first_line_nr = line.line_nr(),
+            Some(first) if first.len() != row.len() => errors.push(row_start.char_error_at(
+                row_start.byte_pos(first.len().min(row.len())),
+                &format!(
+                    "{0} cells per row, as in line {1}",
+                    first.len(),
+                    first_line_nr
+                ),
+            )),
+            _ => {}
+        }
+        grid.push(row);
+    }
+    grid
+}
+
+/// Finds the (x, y) position of a marker that must occur exactly once in the given lines
+/// (the same lines the grid was parsed from).
+pub fn find_unique_marker<'a>(
+    lines: impl Iterator<Item = LineParser<'a>>,
+    marker: char,
+    errors: &mut ParseErrors,
+) -> Option<(usize, usize)> {
+    let mut found: Option<(usize, usize)> = None;
+    let mut last_line: Option<LineParser> = None;
+    for (y, mut line) in lines.enumerate() {
+        line.skip_whitespace();
+        for (x, (pos, c)) in line.remaining().char_indices().enumerate() {
+            if c != marker {
+                continue;
+            }
+            match found {
+                None => found = Some((x, y)),
+                Some(_) => errors.push(line.char_error_at(
+                    line.mark() + pos,
+                    &format!("only a single '{0}' marker", marker),
+                )),
+            }
+        }
+        last_line = Some(line);
+    }
+    if found.is_none() {
+        if let Some(mut line) = last_line {
+            line.pos = line.line.len();
+            errors.push(line.error(&format!("a '{0}' marker somewhere in the grid", marker)));
+        }
+    }
+    found
+}
+
+/// The puzzle input: the file name and its lines. The file name is only
+/// used for error messages.
+#[derive(Debug, Clone)]
+pub struct Input {
+    file: String,
+    lines: Vec<String>,
+}
+
+impl Input {
+    pub fn new() -> Input {
+        Input {
+            file: String::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn read(filename: &str) -> Input {
+        Input::from_lines(filename, read_lines(filename))
+    }
+
+    pub fn from_lines(filename: &str, lines: Vec<String>) -> Input {
+        Input {
+            file: filename.to_string(),
+            lines,
+        }
+    }
+
+    pub fn raw_lines(&self) -> &Vec<String> {
+        &self.lines
+    }
+
+    /// Returns a parser for the line with the given (0-based) index.
+    /// A missing line is treated as an empty line.
+    pub fn line(&self, index: usize) -> LineParser<'_> {
+        let line = match self.lines.get(index) {
+            Some(l) => l.as_str(),
+            None => "",
+        };
+        LineParser::new(&self.file, index + 1, line)
+    }
+
+    /// Returns a parser for each line.
+    pub fn lines(&self) -> impl Iterator<Item = LineParser<'_>> {
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| LineParser::new(&self.file, i + 1, l))
+    }
+
+    /// Returns a parser for each line that contains more than just whitespace.
+    pub fn non_empty_lines(&self) -> impl Iterator<Item = LineParser<'_>> {
+        self.lines().filter(|l| !l.is_blank())
+    }
+
+    /// Splits the input into blocks of lines, separated by one or more blank lines.
+    /// Leading and trailing blank lines are ignored, so the last block is always complete.
+    pub fn sections(&self) -> Vec<Section<'_>> {
+        let mut sections = Vec::new();
+        let mut start: Option<usize> = None;
+        for (i, line) in self.lines.iter().enumerate() {
+            match (start, line.trim().is_empty()) {
+                (None, false) => start = Some(i),
+                (Some(s), true) => {
+                    sections.push(Section::new(&self.file, s, &self.lines[s..i]));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            sections.push(Section::new(&self.file, s, &self.lines[s..]));
+        }
+        sections
+    }
+}
+
+/// A block of consecutive non-blank input lines.
+#[derive(Debug, Clone, Copy)]
+pub struct Section<'a> {
+    file: &'a str,
+    // 0-based index of the first line in the whole input
+    start: usize,
+    lines: &'a [String],
+}
+
+impl<'a> Section<'a> {
+    fn new(file: &'a str, start: usize, lines: &'a [String]) -> Section<'a> {
+        Section { file, start, lines }
+    }
+
+    /// Returns a parser for each line of the section.
+    pub fn lines(&self) -> impl Iterator<Item = LineParser<'a>> {
+        let file = self.file;
+        let start = self.start;
+        self.lines
+            .iter()
+            .enumerate()
+            .map(move |(i, l)| LineParser::new(file, start + i + 1, l))
+    }
+
+    /// Splits off the first line as a header (like 'seed-to-soil map:'):
+    /// returns a parser for the header line, and the rest of the section.
+    pub fn split_header(&self) -> (LineParser<'a>, Section<'a>) {
+        let header = LineParser::new(self.file, self.start + 1, &self.lines[0]);
+        let body = Section::new(self.file, self.start + 1, &self.lines[1..]);
+        (header, body)
+    }
+
+    /// Parses each line as a record with the given function. Lines that cannot be parsed
+    /// are recorded in `errors`, and skipped.
+    pub fn records<T, F>(&self, errors: &mut ParseErrors, mut record: F) -> Vec<T>
+    where
+        F: FnMut(&mut LineParser<'a>) -> ParseResult<T>,
+    {
+        self.lines()
+            .filter_map(|mut line| errors.check(record(&mut line)))
+            .collect()
+    }
+
+    /// Parses each line as a whitespace-separated list of numbers.
+    pub fn numbers<T: FromStr>(&self, errors: &mut ParseErrors) -> Vec<Vec<T>> {
+        self.records(errors, |line| line.numbers())
+    }
+
+    /// Parses the section as a rectangular grid of the allowed chars (see parse_grid()).
+    pub fn grid(&self, allowed: &str, errors: &mut ParseErrors) -> Vec<Vec<char>> {
+        parse_grid(self.lines(), allowed, errors)
+    }
+}
+
+/// A cursor over a single input line, that consumes typed tokens from left to right.
+/// All token functions skip leading whitespace, and return an error pointing
+/// to the actual position if the expected token is not there.
+#[derive(Debug, Clone)]
+pub struct LineParser<'a> {
+    file: &'a str,
+    line_nr: usize,
+    line: &'a str,
+    // actual byte position in line
+    pos: usize,
+}
+
+impl<'a> LineParser<'a> {
+    pub fn new(file: &'a str, line_nr: usize, line: &'a str) -> LineParser<'a> {
+        LineParser {
+            file,
+            line_nr,
+            line,
+            pos: 0,
+        }
+    }
+
+    pub fn line_nr(&self) -> usize {
+        self.line_nr
+    }
+
+    /// true if the line contains only whitespace
+    pub fn is_blank(&self) -> bool {
+        self.line.trim().is_empty()
+    }
+
+    /// The not yet consumed part of the line
+    pub fn remaining(&self) -> &'a str {
+        &self.line[self.pos..]
+    }
+
+    /// The actual position, to create errors for already consumed tokens (see error_at()).
+    pub fn mark(&self) -> usize {
+        self.pos
+    }
+
+    /// Creates an error at the actual position.
+    pub fn error(&self, expected: &str) -> ParseError {
+        self.error_at(self.pos, expected)
+    }
+
+    /// Creates an error at a previously marked position.
+    pub fn error_at(&self, pos: usize, expected: &str) -> ParseError {
+        let rest = &self.line[pos..];
+        let found = match rest.split_whitespace().next() {
+            Some(token) if rest.starts_with(token) => format!("'{}'", token),
+            Some(_) => String::from("whitespace"),
+            None => String::from("end of line"),
+        };
+        ParseError {
+            file: self.file.to_string(),
+            line: self.line_nr,
+            column: self.line[..pos].chars().count() + 1,
+            expected: expected.to_string(),
+            found,
+        }
+    }
+
+    /// Creates an error for the single char at the given position.
+    pub fn char_error_at(&self, pos: usize, expected: &str) -> ParseError {
+        let mut error = self.error_at(pos, expected);
+        if let Some(c) = self.line[pos..].chars().next() {
+            error.found = format!("'{}'", c);
+        }
+        error
+    }
+
+    /// byte position of the n-th (not yet consumed) char
+    fn byte_pos(&self, n: usize) -> usize {
+        match self.remaining().char_indices().nth(n) {
+            Some((pos, _)) => self.pos + pos,
+            None => self.line.len(),
+        }
+    }
+
+    /// Consumes the rest of the line (without surrounding whitespace) as a row of grid cells,
+    /// each of them one of the `allowed` chars. Invalid chars are recorded in `errors`,
+    /// but kept in the row.
+    pub fn grid_row(&mut self, allowed: &str, errors: &mut ParseErrors) -> Vec<char> {
+        self.skip_whitespace();
+        let cells = self.remaining().trim_end();
+        for (pos, c) in cells.char_indices() {
+            if !allowed.contains(c) {
+                errors.push(self.char_error_at(self.pos + pos, &format!("one of '{0}'", allowed)));
+            }
+        }
+        self.pos += cells.len();
+        cells.chars().collect()
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        let rest = self.remaining();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes the given literal, if it is next (after whitespace).
+    pub fn try_literal(&mut self, literal: &str) -> bool {
+        self.skip_whitespace();
+        if self.remaining().starts_with(literal) {
+            self.pos += literal.len();
+            return true;
+        }
+        false
+    }
+
+    /// Expects the given literal as next token.
+    pub fn literal(&mut self, literal: &str) -> ParseResult<()> {
+        match self.try_literal(literal) {
+            true => Ok(()),
+            false => Err(self.error(&format!("'{}'", literal))),
+        }
+    }
+
+    /// A word: a sequence of letters, digits and underscores.
+    pub fn word(&mut self) -> ParseResult<&'a str> {
+        self.skip_whitespace();
+        let rest = self.remaining();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("a name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// A (non-empty) sequence of the allowed chars, like '#.??#' for allowed '.#?'.
+    pub fn chars_of(&mut self, allowed: &str) -> ParseResult<&'a str> {
+        self.skip_whitespace();
+        let rest = self.remaining();
+        let len = rest.find(|c: char| !allowed.contains(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.char_error_at(self.pos, &format!("one of '{0}'", allowed)));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// An integer number with an optional sign, converted to the wanted number type.
+    pub fn number<T: FromStr>(&mut self) -> ParseResult<T> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let rest = self.remaining();
+        let sign_len = match rest.starts_with(['-', '+']) {
+            true => 1,
+            false => 0,
+        };
+        let digits = rest[sign_len..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - sign_len);
+        if digits == 0 {
+            return Err(self.error("a number"));
+        }
+        let len = sign_len + digits;
+        match rest[..len].parse::<T>() {
+            Ok(nr) => {
+                self.pos += len;
+                Ok(nr)
+            }
+            Err(_) => Err(self.error_at(start, &format!("a number of type {}", type_name::<T>()))),
+        }
+    }
+
+    /// A whitespace-separated list of numbers, up to the end of the line.
+    pub fn numbers<T: FromStr>(&mut self) -> ParseResult<Vec<T>> {
+        let mut numbers = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.remaining().is_empty() {
+                return Ok(numbers);
+            }
+            numbers.push(self.number()?);
+        }
+    }
+
+    /// A list of (at least one) items, separated by the given separator.
+    /// Each item is parsed by the given function.
+    pub fn separated<T, F>(&mut self, separator: &str, mut item: F) -> ParseResult<Vec<T>>
+    where
+        F: FnMut(&mut LineParser<'a>) -> ParseResult<T>,
+    {
+        let mut items = vec![item(self)?];
+        while self.try_literal(separator) {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    /// A key/value pair, like 'x=42', with the given assignment operator.
+    pub fn key_value<T: FromStr>(&mut self, assign: &str) -> ParseResult<(&'a str, T)> {
+        let key = self.word()?;
+        self.literal(assign)?;
+        let value = self.number()?;
+        Ok((key, value))
+    }
+
+    /// A named tuple of names, like 'AAA = (BBB, CCC)', with exactly `size` elements.
+    /// Each element comes with its position (see mark()), to report errors about it later.
+    pub fn name_tuple(&mut self, size: usize) -> ParseResult<(&'a str, Vec<(usize, &'a str)>)> {
+        let name = self.word()?;
+        self.literal("=")?;
+        self.literal("(")?;
+        let mut elements = Vec::new();
+        for i in 0..size {
+            if i > 0 {
+                self.literal(",")?;
+            }
+            self.skip_whitespace();
+            elements.push((self.mark(), self.word()?));
+        }
+        self.literal(")")?;
+        Ok((name, elements))
+    }
+
+    /// Expects the end of the line (only whitespace left).
+    pub fn end(&mut self) -> ParseResult<()> {
+        self.skip_whitespace();
+        match self.remaining().is_empty() {
+            true => Ok(()),
+            false => Err(self.error("end of line")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(line: &str) -> LineParser<'_> {
+        LineParser::new("test.txt", 3, line)
+    }
+
+    #[test]
+    fn test_numbers() {
+        let mut p = parser("Time:      7  15   -30");
+        p.literal("Time:").unwrap();
+        assert_eq!(p.numbers::<i64>().unwrap(), vec![7, 15, -30]);
+        assert_eq!(p.end(), Ok(()));
+    }
+
+    #[test]
+    fn test_number_errors() {
+        let mut p = parser("  12 x4");
+        assert_eq!(p.number::<u8>().unwrap(), 12);
+        let err = p.number::<u8>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test.txt:3:6: expected a number, found 'x4'"
+        );
+
+        let err = parser("300").number::<u8>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test.txt:3:1: expected a number of type u8, found '300'"
+        );
+    }
+
+    #[test]
+    fn test_name_tuple() {
+        let mut p = parser("AAA = (BBB, CCC)");
+        assert_eq!(
+            p.name_tuple(2).unwrap(),
+            ("AAA", vec![(7, "BBB"), (12, "CCC")])
+        );
+
+        let err = parser("AAA = (BBB CCC)").name_tuple(2).unwrap_err();
+        assert_eq!(err.column, 12);
+        assert_eq!(err.expected, "','");
+    }
+
+    #[test]
+    fn test_chars_of() {
+        let mut p = parser("???.### 1,1,3");
+        assert_eq!(p.chars_of(".#?").unwrap(), "???.###");
+        let err = p.chars_of(".#?").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test.txt:3:9: expected one of '.#?', found '1'"
+        );
+    }
+
+    #[test]
+    fn test_separated_key_values() {
+        let mut p = parser("{x=787,m=2655,a=1222,s=2876}");
+        p.literal("{").unwrap();
+        let values = p.separated(",", |p| p.key_value::<i64>("=")).unwrap();
+        p.literal("}").unwrap();
+        assert_eq!(
+            values,
+            vec![("x", 787), ("m", 2655), ("a", 1222), ("s", 2876)]
+        );
+    }
+
+    #[test]
+    fn test_column_counts_chars() {
+        let mut p = parser("äöü: 12");
+        let err = p.number::<i64>().unwrap_err();
+        assert_eq!(err.column, 1);
+        p.literal("äöü:").unwrap();
+        p.skip_whitespace();
+        assert_eq!(p.error("x").column, 6);
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = Input::from_lines(
+            "test.txt",
+            ["a", "b", "", "", "c", ""]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        );
+        let sections = input.sections();
+        assert_eq!(sections.len(), 2);
+        let line_nrs: Vec<usize> = sections[1].lines().map(|l| l.line_nr()).collect();
+        assert_eq!(line_nrs, vec![5]);
+    }
+
+    #[test]
+    fn test_section_header_and_records() {
+        let input = input(&["", "values:", "1 2", "3 x", "4", ""]);
+        let sections = input.sections();
+        assert_eq!(sections.len(), 1);
+        let (mut header, body) = sections[0].split_header();
+        assert_eq!(header.word(), Ok("values"));
+        let mut errors = ParseErrors::new();
+        assert_eq!(body.numbers::<i64>(&mut errors), vec![vec![1, 2], vec![4]]);
+        assert_eq!(errors.errors()[0].line, 4);
+    }
+
+    fn input(lines: &[&str]) -> Input {
+        Input::from_lines("test.txt", lines.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_parse_grid_collects_all_errors() {
+        let input = input(&["..#.", ".X#.", "...", "#..?"]);
+        let mut errors = ParseErrors::new();
+        let grid = parse_grid(input.lines(), ".#", &mut errors);
+        assert_eq!(grid.len(), 4);
+        let messages: Vec<String> = errors.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "test.txt:2:2: expected one of '.#', found 'X'",
+                "test.txt:3:4: expected 4 cells per row, as in line 1, found end of line",
+                "test.txt:4:4: expected one of '.#', found '?'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_unique_marker() {
+        let mut errors = ParseErrors::new();
+        let start = find_unique_marker(input(&["...", ".S.", "..."]).lines(), 'S', &mut errors);
+        assert_eq!(start, Some((1, 1)));
+        assert!(errors.is_empty());
+
+        let start = find_unique_marker(input(&["S..", "..S"]).lines(), 'S', &mut errors);
+        assert_eq!(start, Some((0, 0)));
+        find_unique_marker(input(&["...", "..."]).lines(), 'S', &mut errors);
+        let messages: Vec<String> = errors.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "test.txt:2:3: expected only a single 'S' marker, found 'S'",
+                "test.txt:2:4: expected a 'S' marker somewhere in the grid, found end of line",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_display() {
+        let mut errors = ParseErrors::new();
+        assert_eq!(errors.check(parser("12").number::<u8>()), Some(12));
+        assert_eq!(errors.check(parser("x").number::<u8>()), None);
+        errors.push(parser("a b").error("c"));
+        assert_eq!(
+            errors.into_result().unwrap_err().to_string(),
+            "2 problem(s) in the input:\n  test.txt:3:1: expected a number, found 'x'\n  test.txt:3:1: expected c, found 'a'"
+        );
+    }
+}
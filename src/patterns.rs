@@ -0,0 +1,124 @@
+// Multi-pattern string search (Aho-Corasick): finds all occurrences of a set of patterns
+// in a single pass over the text, overlapping ones included.
+
+use std::collections::VecDeque;
+
+/// A pattern found in the text: its byte range, and the value given for the pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Match<T> {
+    pub start: usize,
+    pub end: usize,
+    pub value: T,
+}
+
+/// The automaton works on bytes, so it takes any UTF-8 text: as the patterns are valid
+/// UTF-8 too, a match always starts and ends at a char boundary.
+#[derive(Debug)]
+pub struct PatternMatcher<T> {
+    // next state, for each state and input byte:
+    transitions: Vec<[usize; 256]>,
+    // the patterns (length, value) ending in each state, including the ones ending in its suffixes:
+    outputs: Vec<Vec<(usize, T)>>,
+}
+
+const NO_STATE: usize = usize::MAX;
+
+impl<T: Copy> PatternMatcher<T> {
+    /// Builds the automaton for the given (pattern, value) pairs. Empty patterns are ignored.
+    pub fn new(patterns: &[(&str, T)]) -> PatternMatcher<T> {
+        let mut transitions: Vec<[usize; 256]> = vec![[NO_STATE; 256]];
+        let mut outputs: Vec<Vec<(usize, T)>> = vec![Vec::new()];
+
+        // 1st, a trie of all patterns, state 0 is the root:
+        for (pattern, value) in patterns.iter().filter(|(p, _)| !p.is_empty()) {
+            let mut state = 0;
+            for b in pattern.bytes() {
+                if transitions[state][b as usize] == NO_STATE {
+                    transitions.push([NO_STATE; 256]);
+                    outputs.push(Vec::new());
+                    transitions[state][b as usize] = transitions.len() - 1;
+                }
+                state = transitions[state][b as usize];
+            }
+            outputs[state].push((pattern.len(), *value));
+        }
+
+        // then, breadth first, the missing transitions: they continue from the longest suffix
+        // that is also in the trie (the failure state), which is always nearer to the root.
+        let mut failure = vec![0; transitions.len()];
+        let mut queue = VecDeque::new();
+        for next in transitions[0].iter_mut() {
+            match *next {
+                NO_STATE => *next = 0,
+                state => queue.push_back(state),
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            let suffix_outputs = outputs[failure[state]].clone();
+            outputs[state].extend(suffix_outputs);
+            let fallbacks = transitions[failure[state]];
+            for (next, fallback) in transitions[state].iter_mut().zip(fallbacks) {
+                match *next {
+                    NO_STATE => *next = fallback,
+                    child => {
+                        failure[child] = fallback;
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
+        PatternMatcher {
+            transitions,
+            outputs,
+        }
+    }
+
+    /// All matches in the text, overlapping ones included, ordered by their end position.
+    pub fn find_all<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Match<T>> + 'a {
+        let mut state = 0;
+        text.bytes().enumerate().flat_map(move |(pos, b)| {
+            state = self.transitions[state][b as usize];
+            self.outputs[state].iter().map(move |(len, value)| Match {
+                start: pos + 1 - len,
+                end: pos + 1,
+                value: *value,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(matcher: &PatternMatcher<u32>, text: &str) -> Vec<(usize, u32)> {
+        matcher.find_all(text).map(|m| (m.start, m.value)).collect()
+    }
+
+    #[test]
+    fn test_overlapping_patterns() {
+        let matcher = PatternMatcher::new(&[("one", 1), ("two", 2), ("eight", 8), ("2", 2)]);
+        assert_eq!(
+            words(&matcher, "xtwone2eightwo"),
+            vec![(1, 2), (3, 1), (6, 2), (7, 8), (11, 2)]
+        );
+    }
+
+    #[test]
+    fn test_patterns_within_patterns() {
+        let matcher =
+            PatternMatcher::new(&[("seventeen", 17), ("seven", 7), ("teen", 0), ("", 99)]);
+        assert_eq!(words(&matcher, "seventeen"), vec![(0, 7), (0, 17), (5, 0)]);
+    }
+
+    #[test]
+    fn test_utf8_text_and_patterns() {
+        let matcher = PatternMatcher::new(&[("zwölf", 12), ("drei", 3)]);
+        let text = "€zwölfdrei😀";
+        let found: Vec<&str> = matcher
+            .find_all(text)
+            .map(|m| &text[m.start..m.end])
+            .collect();
+        assert_eq!(found, vec!["zwölf", "drei"]);
+    }
+}
@@ -0,0 +1,124 @@
+
+use alex_lib::types::{Coord2d, Direction};
+use num::integer::gcd;
+
+/// A closed polygon on the integer grid, given by its vertices in walking order.
+/// The last vertex is connected back to the first one. Vertices may also be
+/// collinear (e.g. every single tile of a path), this does not change the results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<Coord2d>,
+}
+
+impl Polygon {
+    pub fn from_vertices(vertices: Vec<Coord2d>) -> Polygon {
+        Polygon { vertices }
+    }
+
+    /// Creates a rectilinear polygon by walking the given (direction, steps) instructions,
+    /// starting at the given coordinate. The instructions must lead back to the start.
+    pub fn from_instructions(start: Coord2d, instructions: &[(Direction, i64)]) -> Polygon {
+        let mut vertices = Vec::new();
+        let mut act_coord = start;
+        for (dir, steps) in instructions.iter() {
+            vertices.push(act_coord);
+            act_coord = match dir {
+                Direction::UP => act_coord.up_n(*steps),
+                Direction::RIGHT => act_coord.right_n(*steps),
+                Direction::DOWN => act_coord.down_n(*steps),
+                Direction::LEFT => act_coord.left_n(*steps),
+            };
+        }
+        Polygon { vertices }
+    }
+
+    /// Twice the signed area, using the Shoelace formula: summing up the cross
+    /// products of each vertex with the next one.
+    /// Positive for counter-clockwise polygons (in a y-up coordinate system, which
+    /// is clockwise on a y-down grid), negative otherwise. Always an integer, in contrast
+    /// to the area itself.
+    pub fn double_signed_area(&self) -> i64 {
+        let mut sum: i64 = 0;
+        for (i, a) in self.vertices.iter().enumerate() {
+            let b = &self.vertices[(i + 1) % self.vertices.len()];
+            sum += a.x * b.y - a.y * b.x;
+        }
+        sum
+    }
+
+    /// Number of lattice points on the boundary: each edge from a to b
+    /// contains gcd(|dx|, |dy|) points (counting a, but not b).
+    pub fn boundary_points(&self) -> i64 {
+        let mut sum: i64 = 0;
+        for (i, a) in self.vertices.iter().enumerate() {
+            let b = &self.vertices[(i + 1) % self.vertices.len()];
+            sum += gcd((b.x - a.x).abs(), (b.y - a.y).abs());
+        }
+        sum
+    }
+
+    /// Number of lattice points strictly inside the polygon, using Pick's theorem:
+    /// A = I + B/2 - 1, so I = A - B/2 + 1
+    pub fn interior_points(&self) -> i64 {
+        (self.double_signed_area().abs() - self.boundary_points() + 2) / 2
+    }
+
+    /// Number of lattice points inside the polygon, including the boundary.
+    /// If the polygon is a path of grid tiles, this is the number of tiles covered by
+    /// the path and its inner area.
+    pub fn lattice_points(&self) -> i64 {
+        self.interior_points() + self.boundary_points()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        // 3x3 tiles, 2x2 area:
+        let polygon = Polygon::from_vertices(vec![
+            Coord2d { x: 0, y: 0 },
+            Coord2d { x: 2, y: 0 },
+            Coord2d { x: 2, y: 2 },
+            Coord2d { x: 0, y: 2 },
+        ]);
+        assert_eq!(polygon.double_signed_area(), 8);
+        assert_eq!(polygon.boundary_points(), 8);
+        assert_eq!(polygon.interior_points(), 1);
+        assert_eq!(polygon.lattice_points(), 9);
+    }
+
+    #[test]
+    fn test_from_instructions() {
+        // an L shape, walked counter-clockwise on screen:
+        let polygon = Polygon::from_instructions(
+            Coord2d { x: 0, y: 0 },
+            &[
+                (Direction::DOWN, 4),
+                (Direction::RIGHT, 4),
+                (Direction::UP, 2),
+                (Direction::LEFT, 2),
+                (Direction::UP, 2),
+                (Direction::LEFT, 2),
+            ],
+        );
+        assert_eq!(polygon.vertices.len(), 6);
+        assert_eq!(polygon.double_signed_area(), -24);
+        assert_eq!(polygon.boundary_points(), 16);
+        assert_eq!(polygon.interior_points(), 5);
+    }
+
+    #[test]
+    fn test_diagonal_edges() {
+        let polygon = Polygon::from_vertices(vec![
+            Coord2d { x: 0, y: 0 },
+            Coord2d { x: 4, y: 0 },
+            Coord2d { x: 0, y: 4 },
+        ]);
+        assert_eq!(polygon.double_signed_area(), 16);
+        assert_eq!(polygon.boundary_points(), 12);
+        assert_eq!(polygon.interior_points(), 3);
+    }
+}
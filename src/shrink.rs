@@ -0,0 +1,118 @@
+// Shrinking of failing inputs: used to report minimal counterexamples
+// (see difftest) and crashing inputs (see fuzz).
+
+/// Shrinks a failing input: takes the first smaller variant that still fails,
+/// until no smaller variant fails any more.
+pub fn shrink<F: Fn(&[String]) -> bool>(mut input: Vec<String>, fails: F) -> Vec<String> {
+    'shrink: loop {
+        for candidate in shrink_candidates(&input) {
+            if fails(&candidate) {
+                input = candidate;
+                continue 'shrink;
+            }
+        }
+        return input;
+    }
+}
+
+/// Smaller variants of an input, the big steps first:
+/// - without a block of lines (halves, quarters, ..., single lines)
+/// - without a column, if all lines have the same length (for grids)
+/// - without a single token of a line (a number, a word, or any other char)
+/// - with a single number made smaller (0, half of it, one less)
+fn shrink_candidates(input: &[String]) -> Vec<Vec<String>> {
+    let mut candidates: Vec<Vec<String>> = Vec::new();
+
+    let mut block = input.len() / 2;
+    while block > 0 {
+        for start in (0..input.len()).step_by(block) {
+            let mut candidate = input.to_vec();
+            candidate.drain(start..(start + block).min(input.len()));
+            candidates.push(candidate);
+        }
+        block /= 2;
+    }
+
+    let width = input.first().map_or(0, |l| l.chars().count());
+    if width > 1 && input.iter().all(|l| l.chars().count() == width) {
+        for col in 0..width {
+            let candidate = input
+                .iter()
+                .map(|l| {
+                    l.chars()
+                        .enumerate()
+                        .filter(|(x, _)| *x != col)
+                        .map(|(_, c)| c)
+                        .collect()
+                })
+                .collect();
+            candidates.push(candidate);
+        }
+    }
+
+    for (i, line) in input.iter().enumerate() {
+        let tokens = tokenize(line);
+        for t in 0..tokens.len() {
+            let mut candidate = input.to_vec();
+            candidate[i] = [&tokens[..t], &tokens[t + 1..]].concat().concat();
+            candidates.push(candidate);
+        }
+        for (t, token) in tokens.iter().enumerate() {
+            let value = match token.parse::<u64>() {
+                Ok(value) if value > 0 => value,
+                _ => continue,
+            };
+            let mut smaller_values = vec![0, value / 2, value - 1];
+            smaller_values.dedup();
+            for smaller in smaller_values {
+                let mut tokens = tokens.clone();
+                tokens[t] = smaller.to_string();
+                let mut candidate = input.to_vec();
+                candidate[i] = tokens.concat();
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+/// Splits a line into runs of digits, runs of letters, and single other chars
+pub fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let kind = |c: char| match c {
+        '0'..='9' => 0,
+        c if c.is_alphabetic() => 1,
+        _ => 2,
+    };
+    for c in line.chars() {
+        match tokens.last_mut() {
+            Some(last) if kind(c) != 2 && last.chars().all(|l| kind(l) == kind(c)) => last.push(c),
+            _ => tokens.push(c.to_string()),
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Game 12: 3 blue,-4"),
+            vec!["Game", " ", "12", ":", " ", "3", " ", "blue", ",", "-", "4"]
+        );
+    }
+
+    #[test]
+    fn test_shrinks_grids_by_rows_and_columns() {
+        let input = lines(&["..#..", ".....", "..#.."]);
+        let has_rock = |input: &[String]| input.iter().any(|l| l.contains('#'));
+        assert_eq!(shrink(input, has_rock), lines(&["#"]));
+    }
+}